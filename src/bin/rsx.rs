@@ -0,0 +1,468 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+extern crate rsx_parser;
+extern crate self_tokenize_trait;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::process;
+
+use rsx_parser::types::*;
+use rsx_parser::{convert_jsx, html_to_rsx, parse_complete_with_options, print};
+use self_tokenize_trait::{ToCustomTokens, Tokens};
+
+const USAGE: &str = "Usage: rsx <command> [options] [FILE...]
+
+Reads from stdin when no files are given, or when a file is `-`.
+
+Commands:
+    parse     Parse and print the normalized RSX source
+    check     Report parse errors with their line and column
+    dump      Print the AST, as Rust debug output or with `--json`
+    expand    Print the Rust code generated for the RSX
+    import    Convert HTML into RSX source
+    convert   Convert JSX into RSX source, reporting JavaScript to port by hand
+
+Options:
+    --validate    Also report code blocks which aren't valid Rust expressions";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Parse,
+    Check,
+    Dump,
+    Expand,
+    Import,
+    Convert
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let command = match args.first().map(|v| v.as_str()) {
+        Some("parse") => Command::Parse,
+        Some("check") => Command::Check,
+        Some("dump") => Command::Dump,
+        Some("expand") => Command::Expand,
+        Some("import") => Command::Import,
+        Some("convert") => Command::Convert,
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let unknown = args.iter().skip(1).find(|v| {
+        v.starts_with("--") && *v != "--json" && *v != "--validate"
+    });
+    if let Some(option) = unknown {
+        eprintln!("Unknown option `{}`\n\n{}", option, USAGE);
+        process::exit(2);
+    }
+
+    let json = args.iter().any(|v| v == "--json");
+    let options = RSXParseOptions {
+        validate_expressions: args.iter().any(|v| v == "--validate"),
+        ..RSXParseOptions::default()
+    };
+    let mut paths = args.iter()
+        .skip(1)
+        .filter(|v| *v != "--json" && *v != "--validate")
+        .cloned()
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        paths.push("-".to_string());
+    }
+
+    let mut failed = false;
+    for path in &paths {
+        let source = match read_source(path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                failed = true;
+                continue;
+            }
+        };
+
+        let succeeded = match command {
+            Command::Parse => with_parsed(path, &source, options, |ast, _| {
+                println!("{}", print(ast, &RSXPrintOptions::default()))
+            }),
+            Command::Check => with_parsed(path, &source, options, |_, _| {
+                println!("{}: ok", path)
+            }),
+            Command::Dump => with_parsed(path, &source, options, |ast, spans| {
+                if json {
+                    println!("{}", json_element(ast, spans))
+                } else {
+                    println!("{:#?}", ast)
+                }
+            }),
+            Command::Expand => with_parsed(path, &source, options, |ast, _| {
+                let mut tokens = Tokens::new();
+                ast.to_custom_tokens(&mut tokens);
+                println!("{}", indent_tokens(tokens.as_str()));
+            }),
+            Command::Import => {
+                let (rsx, diagnostics) = html_to_rsx(&source, &RSXPrintOptions::default());
+                println!("{}", rsx);
+                let path: &str = if path == "-" { "<stdin>" } else { path };
+                for diagnostic in &diagnostics {
+                    eprintln!("{}:{}", path, diagnostic);
+                }
+                true
+            }
+            Command::Convert => match convert_jsx(&source) {
+                Ok(conversion) => {
+                    println!("{}", print(&conversion.element, &RSXPrintOptions::default()));
+                    let path: &str = if path == "-" { "<stdin>" } else { path };
+                    for issue in &conversion.issues {
+                        eprintln!("{}:{}", path, issue);
+                    }
+                    true
+                }
+                Err(error) => {
+                    report_error(path, &source, &error);
+                    false
+                }
+            }
+        };
+        failed = failed || !succeeded;
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
+
+// Parses the source and hands the AST to the given function, or reports the
+// parse error. Returns whether parsing succeeded.
+fn with_parsed<F>(path: &str, source: &str, options: RSXParseOptions, f: F) -> bool
+where
+    F: FnOnce(&RSXElement, &RSXSpans)
+{
+    let result = parse_complete_with_options(source, options).and_then(|(ast, table)| {
+        f(&ast, &RSXSpans::new(&ast, &table)?);
+        Ok(())
+    });
+    match result {
+        Ok(()) => true,
+        Err(error) => {
+            report_error(path, source, &error);
+            false
+        }
+    }
+}
+
+fn read_source(path: &str) -> io::Result<String> {
+    let mut source = String::new();
+    if path == "-" {
+        io::stdin().read_to_string(&mut source)?;
+    } else {
+        File::open(path)?.read_to_string(&mut source)?;
+    }
+    Ok(source)
+}
+
+fn report_error(path: &str, source: &str, error: &RSXParseError) {
+    let path = if path == "-" { "<stdin>" } else { path };
+    eprintln!("{}:{}", path, error);
+
+    if let Some(line) = source.lines().nth(error.line().saturating_sub(1)) {
+        let caret = " ".repeat(error.column().saturating_sub(1));
+        eprintln!("    {}\n    {}^", line, caret);
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut output = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c)
+        }
+    }
+    output.push('"');
+    output
+}
+
+fn json_array<T, F>(items: &[T], f: F) -> String
+where
+    F: Fn(&T) -> String
+{
+    format!("[{}]", items.iter().map(f).collect::<Vec<_>>().join(","))
+}
+
+fn json_span(span: RSXSpan) -> String {
+    format!(
+        "{{\"start\":{},\"end\":{}}}",
+        json_position(span.start),
+        json_position(span.end)
+    )
+}
+
+fn json_position(position: RSXPosition) -> String {
+    format!(
+        "{{\"offset\":{},\"line\":{},\"column\":{}}}",
+        position.offset, position.line, position.column
+    )
+}
+
+fn json_element(element: &RSXElement, spans: &RSXSpans) -> String {
+    match element {
+        &RSXElement::SelfClosing(ref element) => format!(
+            "{{\"type\":\"SelfClosingElement\",\"name\":{},\"attributes\":{},\"span\":{}}}",
+            json_string(&element.0.to_string()),
+            json_array(&(element.1).0[..], |a| json_attribute(a, spans)),
+            json_span(element.span(spans))
+        ),
+        &RSXElement::Normal(ref element) => format!(
+            "{{\"type\":\"Element\",\"name\":{},\"attributes\":{},\"children\":{},\"span\":{}}}",
+            json_string(&element.0.to_string()),
+            json_array(&(element.1).0[..], |a| json_attribute(a, spans)),
+            json_array(&(element.2).0[..], |c| json_child(c, spans)),
+            json_span(element.span(spans))
+        ),
+        &RSXElement::Fragment(ref children) => format!(
+            "{{\"type\":\"Fragment\",\"children\":{},\"span\":{}}}",
+            json_array(&children.0[..], |c| json_child(c, spans)),
+            json_span(children.span(spans))
+        ),
+        &RSXElement::Error(ref error) => json_error(error)
+    }
+}
+
+fn json_attribute(attribute: &RSXAttribute, spans: &RSXSpans) -> String {
+    match attribute {
+        &RSXAttribute::Named(ref name, ref value) => format!(
+            "{{\"type\":\"Attribute\",\"name\":{},\"value\":{},\"span\":{}}}",
+            json_string(&name.to_string()),
+            json_attribute_value(value, spans),
+            json_span(attribute.span(spans))
+        ),
+        &RSXAttribute::Spread(ref expression) => format!(
+            "{{\"type\":\"SpreadAttribute\",\"expression\":{}}}",
+            json_expression(expression, spans)
+        ),
+        &RSXAttribute::Error(ref error) => json_error(error)
+    }
+}
+
+fn json_attribute_value(value: &RSXAttributeValue, spans: &RSXSpans) -> String {
+    match value {
+        &RSXAttributeValue::Default => "null".to_string(),
+        &RSXAttributeValue::Boolean(ref b) => b.0.to_string(),
+        // Numbers are written as their literal, since JSON numbers can't
+        // hold every integer exactly, nor infinite values at all.
+        &RSXAttributeValue::Number(ref n) if n.0.value().is_finite() => json_string(&n.0.lexeme()),
+        &RSXAttributeValue::Number(_) => "null".to_string(),
+        &RSXAttributeValue::Str(ref s) => json_string(s.value()),
+        &RSXAttributeValue::Element(ref element) => json_element(element, spans),
+        &RSXAttributeValue::CodeBlock(ref expression) => json_expression(expression, spans)
+    }
+}
+
+fn json_child(child: &RSXChild, spans: &RSXSpans) -> String {
+    match child {
+        &RSXChild::Element(ref element) => json_element(element, spans),
+        &RSXChild::Text(ref text) => format!(
+            "{{\"type\":\"Text\",\"text\":{},\"span\":{}}}",
+            json_string(&text.0),
+            json_span(text.span(spans))
+        ),
+        &RSXChild::CodeBlock(ref expression) => json_expression(expression, spans),
+        &RSXChild::Error(ref error) => json_error(error)
+    }
+}
+
+fn json_expression(expression: &RSXParsedExpression, spans: &RSXSpans) -> String {
+    format!(
+        "{{\"type\":\"CodeBlock\",\"code\":{},\"elements\":{},\"span\":{}}}",
+        json_array(&expression.code[..], |piece| json_string(piece)),
+        json_array(&expression.elements[..], |e| json_element(e, spans)),
+        json_span(expression.span(spans))
+    )
+}
+
+fn json_error(error: &RSXErrorNode) -> String {
+    format!("{{\"type\":\"Error\",\"span\":{}}}", json_span(error.span()))
+}
+
+// The tokens generated by `quote` are all on a single line, separated by
+// spaces. Break them into lines after commas and around groups, and remove
+// the spaces which rustfmt wouldn't have.
+fn indent_tokens(source: &str) -> String {
+    let tokens = split_tokens(source);
+    let mut output = String::new();
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let previous = if i > 0 { tokens[i - 1].as_str() } else { "" };
+        let next = tokens.get(i + 1).map(|v| v.as_str()).unwrap_or("");
+
+        if is_closing(token) && !is_opening(previous) {
+            depth = depth.saturating_sub(1);
+            output.push('\n');
+            output.push_str(&"    ".repeat(depth));
+        } else if (is_opening(previous) && !is_closing(token)) || is_separator(previous) {
+            output.push('\n');
+            output.push_str(&"    ".repeat(depth));
+        } else if i > 0 && !is_glued(previous, token) {
+            output.push(' ');
+        }
+
+        output.push_str(token);
+        if is_opening(token) && !is_closing(next) {
+            depth += 1;
+        }
+    }
+    output
+}
+
+fn is_opening(token: &str) -> bool {
+    token == "(" || token == "[" || token == "{"
+}
+
+fn is_closing(token: &str) -> bool {
+    token == ")" || token == "]" || token == "}"
+}
+
+fn is_separator(token: &str) -> bool {
+    token == "," || token == ";"
+}
+
+fn is_glued(previous: &str, token: &str) -> bool {
+    match (previous, token) {
+        _ if is_opening(previous) && is_closing(token) => true,
+        (_, "::") | ("::", _) | (_, ".") | (".", _) | ("&", _) => true,
+        (_, ",") | (_, ";") | (_, "?") => true,
+        (_, "!") => previous.chars().all(|c| c.is_alphanumeric() || c == '_'),
+        (_, "(") | (_, "[") => {
+            previous == "!" || is_closing(previous)
+                || previous.chars().all(|c| c.is_alphanumeric() || c == '_')
+        }
+        _ => false
+    }
+}
+
+// Whitespace only separates tokens outside of string and char literals.
+fn split_tokens(source: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut token = c.to_string();
+        if c == '"' {
+            while let Some(c) = chars.next() {
+                token.push(c);
+                if c == '\\' {
+                    token.extend(chars.next());
+                } else if c == '"' {
+                    break;
+                }
+            }
+        } else if c == '\'' && chars.peek() == Some(&'\\') {
+            // Escapes come first, since the escaped character can be a quote.
+            while let Some(c) = chars.next() {
+                token.push(c);
+                if c == '\\' {
+                    token.extend(chars.next());
+                } else if c == '\'' {
+                    break;
+                }
+            }
+        } else if c == '\'' && chars.clone().nth(1) == Some('\'') {
+            token.extend(chars.by_ref().take(2));
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rsx_parser::parse_complete;
+
+    #[test]
+    pub fn test_indent_tokens() {
+        assert_eq!(
+            indent_tokens(
+                "DOMNode :: from ( ( DOMTagName :: from ( \"a b\" ) , DOMChildren :: from ( \
+                 vec ! [ DOMNode :: from ( ' ' ) , ] ) ) )"
+            ),
+            [
+                "DOMNode::from(",
+                "    (",
+                "        DOMTagName::from(",
+                "            \"a b\"",
+                "        ),",
+                "        DOMChildren::from(",
+                "            vec![",
+                "                DOMNode::from(",
+                "                    ' '",
+                "                ),",
+                "            ]",
+                "        )",
+                "    )",
+                ")"
+            ].join("\n")
+        );
+        assert_eq!(indent_tokens("foo ( )"), "foo()");
+    }
+
+    #[test]
+    pub fn test_split_tokens() {
+        assert_eq!(
+            split_tokens("'\\'' ( 'a' \"b \\\" c\" '\\\\' 'static"),
+            vec!["'\\''", "(", "'a'", "\"b \\\" c\"", "'\\\\'", "'static"]
+        );
+    }
+
+    #[test]
+    pub fn test_json_element() {
+        let (ast, table) = parse_complete("<div a='b'>c</div>").unwrap();
+        assert_eq!(
+            json_element(&ast, &RSXSpans::new(&ast, &table).unwrap()),
+            "{\"type\":\"Element\",\"name\":\"div\",\"attributes\":[{\"type\":\"Attribute\",\
+             \"name\":\"a\",\"value\":\"b\",\"span\":{\"start\":{\"offset\":5,\"line\":1,\
+             \"column\":6},\"end\":{\"offset\":10,\"line\":1,\"column\":11}}}],\"children\":[{\
+             \"type\":\"Text\",\"text\":\"c\",\"span\":{\"start\":{\"offset\":11,\"line\":1,\
+             \"column\":12},\"end\":{\"offset\":12,\"line\":1,\"column\":13}}}],\"span\":{\"start\
+             \":{\"offset\":0,\"line\":1,\"column\":1},\"end\":{\"offset\":18,\"line\":1,\"column\
+             \":19}}}"
+        );
+    }
+}
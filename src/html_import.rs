@@ -0,0 +1,636 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::mem;
+
+use syn::{Lit, StrStyle, Token};
+
+use html_render::{rsx_is_raw_text_element, rsx_is_void_element};
+use parse_attributes_types::{RSXAttribute, RSXAttributeName, RSXAttributeString, RSXAttributeValue};
+use parse_children_types::{RSXChild, RSXChildren, RSXText};
+use parse_elements_types::{
+    RSXElement,
+    RSXElementName,
+    RSXIdentifier,
+    RSXNormalElement,
+    RSXSelfClosingElement
+};
+use parse_error_types::{RSXDiagnostic, RSXParseError, RSXParseErrorKind};
+use parse_external_types::{RSXCodeTokenTree, RSXParsedExpression};
+use parse_html_entities::rsx_decode_entities;
+use parse_js_types::JSDoubleStringCharacters;
+use parse_span_types::{RSXPosition, RSXSpan};
+
+// Elements which are closed by the start tag of any of these elements, when
+// they're the innermost open element, without an end tag of their own.
+const RSX_HTML_IMPLIED_END_TAGS: &[(&str, &[&str])] = &[
+    ("li", &["li"]),
+    ("dt", &["dt", "dd"]),
+    ("dd", &["dt", "dd"]),
+    ("option", &["option", "optgroup"]),
+    ("optgroup", &["optgroup"]),
+    ("tr", &["tr", "tbody", "tfoot"]),
+    ("td", &["td", "th", "tr", "tbody", "tfoot"]),
+    ("th", &["td", "th", "tr", "tbody", "tfoot"]),
+    ("thead", &["tbody", "tfoot"]),
+    ("tbody", &["tbody", "tfoot"]),
+    (
+        "p",
+        &[
+            "address", "article", "aside", "blockquote", "details", "div", "dl", "fieldset",
+            "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6",
+            "header", "hgroup", "hr", "main", "nav", "ol", "p", "pre", "section", "table", "ul"
+        ]
+    )
+];
+
+// Imports an HTML document or fragment, returning its only element, or a
+// fragment when there's more than one node at the top. HTML is parsed as
+// leniently as browsers do, so this never fails: end tags which don't match
+// any open element are skipped, and elements still open at the end are closed.
+// Doctypes and processing instructions are dropped. Names which can't be
+// written in RSX are reported: such attributes are dropped, and such tags are
+// kept as text.
+pub fn rsx_import_html(source: &str) -> (RSXElement, Vec<RSXDiagnostic>) {
+    let mut importer = RSXHtmlImporter {
+        source,
+        rest: source,
+        open: vec![RSXHtmlOpenElement::new(String::new(), vec![])],
+        diagnostics: vec![]
+    };
+    importer.import();
+
+    let mut children = importer.close_all();
+    let diagnostics = importer.diagnostics;
+    if children.len() == 1 {
+        if let Some(RSXChild::Element(element)) = children.pop() {
+            return (element, diagnostics);
+        }
+    }
+    (RSXElement::Fragment(RSXChildren::from(children)), diagnostics)
+}
+
+struct RSXHtmlOpenElement {
+    name: String,
+    attributes: Vec<RSXAttribute>,
+    children: Vec<RSXChild>
+}
+
+impl RSXHtmlOpenElement {
+    fn new(name: String, attributes: Vec<RSXAttribute>) -> Self {
+        RSXHtmlOpenElement {
+            name,
+            attributes,
+            children: vec![]
+        }
+    }
+
+    // Text is decoded as it's read, and only turned into RSX children once the
+    // element is closed, since it can arrive in more than one piece.
+    fn push_text(&mut self, text: &str) {
+        if let Some(&mut RSXChild::Text(ref mut previous)) = self.children.last_mut() {
+            previous.0.push_str(text);
+            return;
+        }
+        self.children.push(RSXChild::Text(RSXText(text.to_string())));
+    }
+
+    fn into_children(self) -> Vec<RSXChild> {
+        rsx_html_children(&self.name, self.children)
+    }
+
+    fn into_element(self) -> RSXElement {
+        let RSXHtmlOpenElement {
+            name,
+            attributes,
+            children
+        } = self;
+        let children = RSXChildren::from(rsx_html_children(&name, children));
+        RSXElement::Normal(RSXNormalElement(
+            rsx_html_element_name(&name),
+            attributes.into_iter().collect(),
+            children
+        ))
+    }
+}
+
+struct RSXHtmlImporter<'a> {
+    source: &'a str,
+    rest: &'a str,
+    // The innermost open element is last. The first one stands for the
+    // document, and is never closed by an end tag.
+    open: Vec<RSXHtmlOpenElement>,
+    diagnostics: Vec<RSXDiagnostic>
+}
+
+impl<'a> RSXHtmlImporter<'a> {
+    fn import(&mut self) {
+        while !self.rest.is_empty() {
+            if self.rest.starts_with("<!--") {
+                let comment = self.take_until(4, "-->");
+                self.push_child(rsx_html_comment(comment));
+            } else if self.rest.starts_with("<![CDATA[") {
+                let text = self.take_until(9, "]]>");
+                self.top().push_text(text);
+            } else if self.rest.starts_with("<!") || self.rest.starts_with("<?") {
+                self.take_until(2, ">");
+            } else if self.starts_tag("</") {
+                self.end_tag();
+            } else if self.starts_tag("<") {
+                self.start_tag();
+            } else {
+                // A `<` which doesn't start a tag is text, like everything
+                // up to the next one.
+                let first = self.rest.chars().next().map_or(0, |c| c.len_utf8());
+                let end = self.rest[first..].find('<').map_or(self.rest.len(), |i| i + first);
+                let text = self.advance(end);
+                self.top().push_text(&rsx_decode_entities(text));
+            }
+        }
+    }
+
+    fn top(&mut self) -> &mut RSXHtmlOpenElement {
+        self.open.last_mut().unwrap()
+    }
+
+    fn push_child(&mut self, child: RSXChild) {
+        self.top().children.push(child);
+    }
+
+    fn offset(&self) -> usize {
+        self.source.len() - self.rest.len()
+    }
+
+    fn position(&self, offset: usize) -> RSXPosition {
+        let mut position = RSXPosition::start();
+        for c in self.source[..offset].chars() {
+            position.advance(c);
+        }
+        position
+    }
+
+    // Reports a name which can't be written in RSX, found between the given
+    // offsets.
+    fn report_name(&mut self, name: &str, start: usize, end: usize) {
+        let span = RSXSpan::new(self.position(start), self.position(end));
+        let error = RSXParseError::new(RSXParseErrorKind::InvalidName, span.start).with_found(name);
+        self.diagnostics.push(RSXDiagnostic { error, span });
+    }
+
+    // Keeps the tag which started at the given offset as text, since its name
+    // can't be written in RSX.
+    fn unimportable_tag(&mut self, name: &str, start: usize) {
+        self.take_until(0, ">");
+        let end = self.offset();
+        self.report_name(name, start, end);
+        let tag = rsx_decode_entities(&self.source[start..end]);
+        self.top().push_text(&tag);
+    }
+
+    fn advance(&mut self, len: usize) -> &'a str {
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        taken
+    }
+
+    // Skips the given number of bytes, then returns everything up to the
+    // delimiter, skipping it too. Runs to the end if there's no delimiter.
+    fn take_until(&mut self, skip: usize, delimiter: &str) -> &'a str {
+        self.advance(skip);
+        match self.rest.find(delimiter) {
+            Some(end) => {
+                let taken = self.advance(end);
+                self.advance(delimiter.len());
+                taken
+            }
+            None => {
+                let len = self.rest.len();
+                self.advance(len)
+            }
+        }
+    }
+
+    fn starts_tag(&self, open: &str) -> bool {
+        self.rest.starts_with(open)
+            && self.rest[open.len()..].chars().next().map_or(false, |c| c.is_alphabetic())
+    }
+
+    fn skip_whitespace(&mut self) {
+        let len = self.rest.len() - self.rest.trim_left_matches(rsx_is_html_whitespace).len();
+        self.advance(len);
+    }
+
+    fn take_name(&mut self) -> &'a str {
+        let end = self.rest
+            .find(|c| rsx_is_html_whitespace(c) || c == '/' || c == '>' || c == '=')
+            .unwrap_or(self.rest.len());
+        self.advance(end)
+    }
+
+    fn end_tag(&mut self) {
+        let start = self.offset();
+        self.advance(2);
+        let name = self.take_name();
+        if !rsx_is_importable_name(name) {
+            return self.unimportable_tag(name, start);
+        }
+        self.take_until(0, ">");
+
+        let index = self.open.iter().rposition(|element| element.name.eq_ignore_ascii_case(name));
+        if let Some(index) = index {
+            if index != 0 {
+                self.close_until(index);
+            }
+        }
+    }
+
+    fn start_tag(&mut self) {
+        let start = self.offset();
+        self.advance(1);
+        let name = self.take_name().to_string();
+        if !rsx_is_importable_name(&name) {
+            return self.unimportable_tag(&name, start);
+        }
+
+        let mut attributes = vec![];
+        let mut self_closing = false;
+        loop {
+            self.skip_whitespace();
+            if self.rest.is_empty() {
+                break;
+            } else if self.rest.starts_with('>') {
+                self.advance(1);
+                break;
+            } else if self.rest.starts_with("/>") {
+                self.advance(2);
+                self_closing = true;
+                break;
+            } else if self.rest.starts_with('/') {
+                self.advance(1);
+                continue;
+            }
+
+            let name_start = self.offset();
+            let attribute_name = self.take_name();
+            let name_end = self.offset();
+            self.skip_whitespace();
+            let value = if self.rest.starts_with('=') {
+                self.advance(1);
+                self.skip_whitespace();
+                Some(rsx_decode_entities(self.take_attribute_value()))
+            } else {
+                None
+            };
+            match rsx_html_attribute(attribute_name, value) {
+                Some(attribute) => attributes.push(attribute),
+                None => self.report_name(attribute_name, name_start, name_end)
+            }
+        }
+
+        while self.open.len() > 1
+            && rsx_html_implies_end_tag(&self.open[self.open.len() - 1].name, &name)
+        {
+            let index = self.open.len() - 1;
+            self.close_until(index);
+        }
+
+        if rsx_is_void_element(&name) || self_closing {
+            let name = rsx_html_element_name(&name);
+            let attributes = attributes.into_iter().collect();
+            let element = RSXSelfClosingElement(name, attributes);
+            self.push_child(RSXChild::Element(RSXElement::SelfClosing(element)));
+            return;
+        }
+
+        // Raw text is taken as is, up to the matching end tag, and kept as a
+        // string literal since it's likely to contain braces.
+        let raw_text = rsx_is_raw_text_element(&name);
+        let textarea = name.eq_ignore_ascii_case("textarea");
+        let end = if raw_text || textarea {
+            Some(rsx_find_end_tag(self.rest, &name))
+        } else {
+            None
+        };
+        self.open.push(RSXHtmlOpenElement::new(name, attributes));
+        if let Some(end) = end {
+            let text = self.advance(end);
+            if raw_text && !text.trim().is_empty() {
+                self.push_child(rsx_html_string(text));
+            } else if textarea {
+                self.top().push_text(&rsx_decode_entities(text));
+            }
+        }
+    }
+
+    fn take_attribute_value(&mut self) -> &'a str {
+        match self.rest.chars().next() {
+            Some('"') => self.take_until(1, "\""),
+            Some('\'') => self.take_until(1, "'"),
+            _ => {
+                let end = self.rest
+                    .find(|c| rsx_is_html_whitespace(c) || c == '>')
+                    .unwrap_or(self.rest.len());
+                self.advance(end)
+            }
+        }
+    }
+
+    // Closes the open element at the given index, and every element inside it.
+    fn close_until(&mut self, index: usize) {
+        while self.open.len() > index {
+            let element = self.open.pop().unwrap().into_element();
+            self.push_child(RSXChild::Element(element));
+        }
+    }
+
+    fn close_all(&mut self) -> Vec<RSXChild> {
+        self.close_until(1);
+        self.open.pop().unwrap().into_children()
+    }
+}
+
+fn rsx_html_children(name: &str, children: Vec<RSXChild>) -> Vec<RSXChild> {
+    let preserve = name.eq_ignore_ascii_case("pre") || name.eq_ignore_ascii_case("textarea");
+    let mut imported = vec![];
+    for child in children {
+        match child {
+            RSXChild::Text(text) => {
+                let text = if preserve {
+                    text.0
+                } else {
+                    rsx_collapse_html_whitespace(&text.0)
+                };
+                rsx_push_html_text(text, &mut imported);
+            }
+            child => imported.push(child)
+        }
+    }
+    imported
+}
+
+fn rsx_is_html_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n' || c == '\r' || c == '\x0C'
+}
+
+fn rsx_html_implies_end_tag(open: &str, name: &str) -> bool {
+    RSX_HTML_IMPLIED_END_TAGS.iter().any(|&(element, closed_by)| {
+        element.eq_ignore_ascii_case(open) && closed_by.iter().any(|n| n.eq_ignore_ascii_case(name))
+    })
+}
+
+fn rsx_find_end_tag(s: &str, name: &str) -> usize {
+    let lowercase = s.to_ascii_lowercase();
+    lowercase.find(&format!("</{}", name.to_ascii_lowercase())).unwrap_or(s.len())
+}
+
+// Runs of whitespace become a single space, like browsers render them, except
+// for those spanning lines at either end of the text, which are dropped like
+// RSX drops them, so that indented HTML imports without stray spaces.
+fn rsx_collapse_html_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut whitespace = None;
+    for c in text.chars() {
+        if rsx_is_html_whitespace(c) {
+            let line_break = c == '\n' || c == '\r';
+            whitespace = Some(whitespace.unwrap_or(false) || line_break);
+            continue;
+        }
+        if let Some(line_break) = whitespace.take() {
+            if !line_break || !collapsed.is_empty() {
+                collapsed.push(' ');
+            }
+        }
+        collapsed.push(c);
+    }
+    if let Some(false) = whitespace {
+        collapsed.push(' ');
+    }
+    collapsed
+}
+
+// RSX text can't contain braces or angle brackets, so they're written as
+// string literals in code blocks instead.
+fn rsx_push_html_text(text: String, children: &mut Vec<RSXChild>) {
+    let mut plain = String::new();
+    let mut special = String::new();
+    for c in text.chars() {
+        if c == '{' || c == '}' || c == '<' || c == '>' {
+            if !plain.is_empty() {
+                let text = mem::replace(&mut plain, String::new());
+                children.push(RSXChild::Text(RSXText(text)));
+            }
+            special.push(c);
+        } else {
+            if !special.is_empty() {
+                children.push(rsx_html_string(&mem::replace(&mut special, String::new())));
+            }
+            plain.push(c);
+        }
+    }
+    if !plain.is_empty() {
+        children.push(RSXChild::Text(RSXText(plain)));
+    }
+    if !special.is_empty() {
+        children.push(rsx_html_string(&special));
+    }
+}
+
+fn rsx_html_code_block(code: String, token_trees: Vec<RSXCodeTokenTree>) -> RSXChild {
+    RSXChild::CodeBlock(RSXParsedExpression {
+        token_trees,
+        elements: vec![],
+        code: vec![code]
+    })
+}
+
+fn rsx_html_string(text: &str) -> RSXChild {
+    let literal = Token::Literal(Lit::Str(text.to_string(), StrStyle::Cooked));
+    rsx_html_code_block(format!("{:?}", text), vec![RSXCodeTokenTree::Token(literal)])
+}
+
+// Rust block comments nest, so comment delimiters inside the comment are
+// broken up to keep it from ending early, or not at all.
+fn rsx_html_comment(comment: &str) -> RSXChild {
+    let comment = comment.replace("/*", "/ *").replace("*/", "* /");
+    rsx_html_code_block(format!("/*{}*/", comment), vec![])
+}
+
+fn rsx_html_identifier(name: &str) -> RSXIdentifier {
+    RSXIdentifier(name.to_string())
+}
+
+fn rsx_html_element_name(name: &str) -> RSXElementName {
+    match name.find(':') {
+        Some(index) => RSXElementName::NamedspacedName(
+            rsx_html_identifier(&name[..index]),
+            rsx_html_identifier(&name[index + 1..])
+        ),
+        None => RSXElementName::Name(rsx_html_identifier(name))
+    }
+}
+
+// Names are dash separated identifiers, optionally behind a namespace.
+fn rsx_is_importable_name(name: &str) -> bool {
+    let is_identifier = |part: &str| {
+        let mut chars = part.chars();
+        chars.next().map_or(false, |c| c.is_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    };
+    let parts = name.split(':').collect::<Vec<_>>();
+    parts.len() <= 2 && parts.iter().all(|part| part.split('-').all(&is_identifier))
+}
+
+fn rsx_html_attribute(name: &str, value: Option<String>) -> Option<RSXAttribute> {
+    if !rsx_is_importable_name(name) {
+        return None;
+    }
+
+    let attribute_name = match rsx_html_element_name(name) {
+        RSXElementName::NamedspacedName(ns, n) => RSXAttributeName::NamedspacedName(ns, n),
+        _ => RSXAttributeName::Name(rsx_html_identifier(name))
+    };
+    let value = match value {
+        Some(value) => {
+            let value = if name.eq_ignore_ascii_case("style") {
+                rsx_import_style(&value)
+            } else {
+                value
+            };
+            let string = JSDoubleStringCharacters(value);
+            RSXAttributeValue::Str(RSXAttributeString::DoubleQuoted(string))
+        }
+        None => RSXAttributeValue::Default
+    };
+    Some(RSXAttribute::Named(attribute_name, value))
+}
+
+// Style strings are normalized to `property: value` declarations separated by
+// `; `, with lowercase property names and no empty declarations.
+fn rsx_import_style(style: &str) -> String {
+    rsx_split_style(style)
+        .into_iter()
+        .filter_map(|declaration| {
+            let colon = match declaration.find(':') {
+                Some(colon) => colon,
+                None => return None
+            };
+            let property = declaration[..colon].trim().to_ascii_lowercase();
+            let value = rsx_collapse_html_whitespace(&declaration[colon + 1..]);
+            if property.is_empty() {
+                None
+            } else {
+                Some(format!("{}: {}", property, value.trim()))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// Splits declarations on semicolons outside of strings and parentheses, since
+// values like `url()` can contain them.
+fn rsx_split_style(style: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    let mut quote = None;
+    for (index, c) in style.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') if depth > 0 => depth -= 1,
+            (None, ';') if depth == 0 => {
+                parts.push(&style[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&style[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use print_rsx::rsx_print;
+    use print_types::RSXPrintOptions;
+
+    fn import(source: &str) -> String {
+        rsx_print(&rsx_import_html(source).0, &RSXPrintOptions::default())
+    }
+
+    #[test]
+    pub fn test_rsx_import_html() {
+        assert_eq!(
+            import("<div class=a id='b' hidden>Hi</div>"),
+            "<div class=\"a\" id=\"b\" hidden>Hi</div>"
+        );
+        assert_eq!(
+            import("<p>One<br>Two<img src=\"a.png\"></p>"),
+            "<p>One<br/>Two<img src=\"a.png\"/></p>"
+        );
+        assert_eq!(
+            import("<!DOCTYPE html><p>a</p><p>b"),
+            "<><p>a</p><p>b</p></>"
+        );
+        assert_eq!(import("<div></span>a</div>"), "<div>a</div>");
+        assert_eq!(
+            import("<div @click=\"x\" :a=\"b\" data-x-y=\"1\"/>"),
+            "<div data-x-y=\"1\"/>"
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_import_html_invalid_names() {
+        assert_eq!(
+            import("<p><x@y a=1>Hi</x@y><foo.bar/></p>"),
+            "<p>{\"<\"}x@y a=1{\">\"}Hi{\"</\"}x@y{\"><\"}foo.bar/{\">\"}</p>"
+        );
+
+        let (_, diagnostics) = rsx_import_html("<div @click=\"x\">\n<x@y></div>");
+        let diagnostics = diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec!["1:6: invalid name, found `@click`", "2:1: invalid name, found `x@y`"]
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_import_html_text() {
+        assert_eq!(
+            import("<p>Fish &amp; chips &lt;3 &copy; {x}</p>"),
+            "<p>Fish &amp; chips {\"<\"}3 \u{a9} {\"{\"}x{\"}\"}</p>"
+        );
+        assert_eq!(
+            import("<ul>\n  <li>One\n  <li>Two,\n    three\n</ul>"),
+            "<ul><li>One</li><li>Two, three</li></ul>"
+        );
+        assert_eq!(
+            import("<script>if (a < b) { go(); }</script>"),
+            "<script>{\"if (a < b) { go(); }\"}</script>"
+        );
+        assert_eq!(
+            import("<div><!-- a */ note --><span>a</span></div>"),
+            "<div>{/* a * / note */}<span>a</span></div>"
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_import_html_style() {
+        assert_eq!(
+            import("<div style=\"COLOR:red;;  margin : 0  auto ;background:url('a;b')\"></div>"),
+            "<div style=\"color: red; margin: 0 auto; background: url('a;b')\"></div>"
+        );
+    }
+}
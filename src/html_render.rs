@@ -0,0 +1,339 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use html_types::{RSXHtmlError, RSXHtmlErrorKind};
+use parse_attributes_types::{RSXAttribute, RSXAttributeValue, RSXAttributes};
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_elements_types::{RSXElement, RSXElementName};
+use parse_external_types::RSXParsedExpression;
+use parse_span_types::RSXSpans;
+
+// Elements which never have contents, and are written without a closing tag.
+const RSX_HTML_VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param",
+    "source", "track", "wbr"
+];
+
+// Elements whose text isn't parsed for character references or tags, and so
+// is written without escaping.
+const RSX_HTML_RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+// Errors have the default span when there are no spans to look them up in,
+// like for trees which weren't parsed.
+pub fn rsx_render_html(
+    element: &RSXElement,
+    spans: Option<&RSXSpans>,
+    evaluate: &mut FnMut(&RSXParsedExpression) -> Option<String>
+) -> Result<String, RSXHtmlError> {
+    let no_spans = RSXSpans::default();
+    let mut renderer = RSXHtmlRenderer {
+        spans: spans.unwrap_or(&no_spans),
+        evaluate,
+        output: String::new()
+    };
+    renderer.element(element)?;
+    Ok(renderer.output)
+}
+
+pub fn rsx_is_void_element(name: &str) -> bool {
+    RSX_HTML_VOID_ELEMENTS.iter().any(|void| void.eq_ignore_ascii_case(name))
+}
+
+pub fn rsx_is_raw_text_element(name: &str) -> bool {
+    RSX_HTML_RAW_TEXT_ELEMENTS.iter().any(|raw_text| raw_text.eq_ignore_ascii_case(name))
+}
+
+pub fn rsx_escape_html_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn rsx_escape_html_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+// Whether raw text contains what an HTML parser would take for the end tag of
+// the element named `name`, i.e. `</` followed by the name in any case.
+fn rsx_contains_end_tag(text: &str, name: &str) -> bool {
+    let text = text.to_ascii_lowercase();
+    let end_tag = format!("</{}", name.to_ascii_lowercase());
+    text.contains(&end_tag)
+}
+
+fn rsx_is_empty_expression(expression: &RSXParsedExpression) -> bool {
+    expression.token_trees.is_empty()
+}
+
+// Code blocks are handed to the caller to evaluate. What they evaluate to is
+// escaped like any other text or attribute value, except for spread
+// attributes, which are written as is and need to be attribute markup already.
+struct RSXHtmlRenderer<'a> {
+    spans: &'a RSXSpans<'a>,
+    evaluate: &'a mut FnMut(&RSXParsedExpression) -> Option<String>,
+    output: String
+}
+
+impl<'a> RSXHtmlRenderer<'a> {
+    fn evaluate(&mut self, expression: &RSXParsedExpression) -> Result<String, RSXHtmlError> {
+        (self.evaluate)(expression).ok_or_else(|| {
+            RSXHtmlError::new(RSXHtmlErrorKind::UnevaluatedExpression, expression.span(self.spans))
+        })
+    }
+
+    fn element(&mut self, element: &RSXElement) -> Result<(), RSXHtmlError> {
+        match element {
+            &RSXElement::SelfClosing(ref element) => self.tag(&element.0, &element.1, None),
+            &RSXElement::Normal(ref element) => {
+                self.tag(&element.0, &element.1, Some(&element.2))
+            }
+            &RSXElement::Fragment(ref children) => self.children(children, false),
+            &RSXElement::Error(ref error) => {
+                Err(RSXHtmlError::new(RSXHtmlErrorKind::ErrorNode, error.span()))
+            }
+        }
+    }
+
+    fn tag(
+        &mut self,
+        name: &RSXElementName,
+        attributes: &RSXAttributes,
+        children: Option<&RSXChildren>
+    ) -> Result<(), RSXHtmlError> {
+        if let &RSXElementName::MemberExpression(_) = name {
+            let span = name.span(self.spans);
+            return Err(RSXHtmlError::new(RSXHtmlErrorKind::MemberExpressionName, span));
+        }
+
+        let name = name.to_string();
+        let void = rsx_is_void_element(&name);
+        let raw_text = if rsx_is_raw_text_element(&name) {
+            Some(name.as_str())
+        } else {
+            None
+        };
+
+        self.output.push_str(&format!("<{}", name));
+        for attribute in attributes.0.iter() {
+            self.attribute(attribute)?;
+        }
+        self.output.push('>');
+
+        if void {
+            return match children {
+                Some(children) if !children.0.is_empty() => Err(RSXHtmlError::new(
+                    RSXHtmlErrorKind::VoidElementChildren,
+                    children.span(self.spans)
+                )),
+                _ => Ok(())
+            };
+        }
+
+        if let Some(children) = children {
+            self.children(children, raw_text)?;
+        }
+        self.output.push_str(&format!("</{}>", name));
+        Ok(())
+    }
+
+    fn attribute(&mut self, attribute: &RSXAttribute) -> Result<(), RSXHtmlError> {
+        let (name, value) = match attribute {
+            &RSXAttribute::Named(ref name, ref value) => (name, value),
+            &RSXAttribute::Spread(ref expression) => {
+                let attributes = self.evaluate(expression)?;
+                if !attributes.trim().is_empty() {
+                    self.output.push_str(&format!(" {}", attributes.trim()));
+                }
+                return Ok(());
+            }
+            &RSXAttribute::Error(ref error) => {
+                return Err(RSXHtmlError::new(RSXHtmlErrorKind::ErrorNode, error.span()));
+            }
+        };
+
+        let value = match value {
+            &RSXAttributeValue::Default => None,
+            // Boolean attributes are true when present, whatever their value.
+            &RSXAttributeValue::Boolean(ref boolean) if boolean.0 => None,
+            &RSXAttributeValue::Boolean(_) => return Ok(()),
+            &RSXAttributeValue::Number(ref number) => Some(number.0.decimal()),
+            &RSXAttributeValue::Str(ref string) => Some(string.value().to_string()),
+            &RSXAttributeValue::CodeBlock(ref expression) => Some(self.evaluate(expression)?),
+            &RSXAttributeValue::Element(_) => {
+                return Err(RSXHtmlError::new(
+                    RSXHtmlErrorKind::ElementAttributeValue,
+                    attribute.span(self.spans)
+                ));
+            }
+        };
+
+        match value {
+            Some(value) => {
+                let value = rsx_escape_html_attribute(&value);
+                self.output.push_str(&format!(" {}=\"{}\"", name, value));
+            }
+            None => self.output.push_str(&format!(" {}", name))
+        }
+        Ok(())
+    }
+
+    fn children(
+        &mut self,
+        children: &RSXChildren,
+        raw_text: Option<&str>
+    ) -> Result<(), RSXHtmlError> {
+        let start = self.output.len();
+        for child in children.0.iter() {
+            let text = match child {
+                &RSXChild::Element(ref element) => {
+                    self.element(element)?;
+                    continue;
+                }
+                &RSXChild::Text(ref text) => text.0.clone(),
+                // Code blocks holding nothing but comments render nothing.
+                &RSXChild::CodeBlock(ref expression) if rsx_is_empty_expression(expression) => {
+                    continue;
+                }
+                &RSXChild::CodeBlock(ref expression) => self.evaluate(expression)?,
+                &RSXChild::Error(ref error) => {
+                    return Err(RSXHtmlError::new(RSXHtmlErrorKind::ErrorNode, error.span()));
+                }
+            };
+            match raw_text {
+                Some(name) => {
+                    self.output.push_str(&text);
+                    // Raw text can't be escaped, so it mustn't close its element early.
+                    if rsx_contains_end_tag(&self.output[start..], name) {
+                        return Err(RSXHtmlError::new(
+                            RSXHtmlErrorKind::RawTextEndTag,
+                            child.span(self.spans)
+                        ));
+                    }
+                }
+                None => self.output.push_str(&rsx_escape_html_text(&text))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_helpers::with_spans;
+
+    use parse_attributes_types::{RSXAttributeName, RSXAttributeNumber};
+    use parse_elements_types::RSXSelfClosingElement;
+    use parse_js_types::JSNumberKind;
+
+    fn render(source: &str) -> Result<String, RSXHtmlError> {
+        with_spans(source, |element, spans| {
+            rsx_render_html(element, Some(spans), &mut |expression| {
+                Some(expression.replace_elements(|_| String::new()).trim().to_string())
+            })
+        })
+    }
+
+    #[test]
+    pub fn test_rsx_render_html() {
+        assert_eq!(
+            render("<div class='a \"b\"'>1 &lt; 2 & <b>bold</b></div>"),
+            Ok("<div class=\"a &quot;b&quot;\">1 &lt; 2 &amp; <b>bold</b></div>".to_string())
+        );
+        assert_eq!(render("<p>a<br/>b</p>"), Ok("<p>a<br>b</p>".to_string()));
+        assert_eq!(
+            render("<img src='a.png' hidden/>"),
+            Ok("<img src=\"a.png\" hidden>".to_string())
+        );
+        assert_eq!(render("<div/>"), Ok("<div></div>".to_string()));
+        assert_eq!(render("<><b/><i/></>"), Ok("<b></b><i></i>".to_string()));
+        assert_eq!(
+            render("<input disabled=true checked=false tabindex=1/>"),
+            Ok("<input disabled tabindex=\"1\">".to_string())
+        );
+        assert_eq!(
+            render("<style>a &gt; b</style>"),
+            Ok("<style>a > b</style>".to_string())
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_render_html_numbers() {
+        assert_eq!(
+            render("<a x={18446744073709551615u64} y=1_000 z=0xff w={-1.50f32}/>"),
+            Ok("<a x=\"18446744073709551615\" y=\"1000\" z=\"255\" w=\"-1.50\"></a>".to_string())
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_render_html_without_spans() {
+        let number = RSXAttributeNumber::from(("1", JSNumberKind::Integer));
+        let element = RSXElement::SelfClosing(RSXSelfClosingElement(
+            RSXElementName::Name("hr".into()),
+            RSXAttributes::from(vec![
+                RSXAttribute::Named(
+                    RSXAttributeName::Name("size".into()),
+                    RSXAttributeValue::Number(number)
+                ),
+            ])
+        ));
+        assert_eq!(
+            rsx_render_html(&element, None, &mut |_| None),
+            Ok("<hr size=\"1\">".to_string())
+        );
+
+        let element = RSXElement::Fragment(RSXChildren::from(vec![
+            RSXChild::CodeBlock(RSXParsedExpression::from("x")),
+        ]));
+        let error = rsx_render_html(&element, None, &mut |_| None).unwrap_err();
+        assert_eq!(error.kind, RSXHtmlErrorKind::UnevaluatedExpression);
+        assert_eq!(error.span, Default::default());
+    }
+
+    #[test]
+    pub fn test_rsx_render_html_expressions() {
+        assert_eq!(
+            render("<div title={x}>{a & b}{/* comment */}</div>"),
+            Ok("<div title=\"x\">a &amp; b</div>".to_string())
+        );
+
+        let error = with_spans("<div>{x}</div>", |element, spans| {
+            rsx_render_html(element, Some(spans), &mut |_| None)
+        }).unwrap_err();
+        assert_eq!(error.kind, RSXHtmlErrorKind::UnevaluatedExpression);
+        assert_eq!((error.span.start.column, error.span.end.column), (6, 9));
+    }
+
+    #[test]
+    pub fn test_rsx_render_html_errors() {
+        let error = render("<div icon=<img/>/>").unwrap_err();
+        assert_eq!(error.kind, RSXHtmlErrorKind::ElementAttributeValue);
+        let error = render("<a.b/>").unwrap_err();
+        assert_eq!(error.kind, RSXHtmlErrorKind::MemberExpressionName);
+        let error = render("<br>x</br>").unwrap_err();
+        assert_eq!(error.kind, RSXHtmlErrorKind::VoidElementChildren);
+    }
+
+    #[test]
+    pub fn test_rsx_render_html_raw_text_end_tags() {
+        assert_eq!(
+            render("<script>{a < b / 2}</script>"),
+            Ok("<script>a < b / 2</script>".to_string())
+        );
+        let error = render("<script>{\"</SCRIPT>\"}</script>").unwrap_err();
+        assert_eq!(error.kind, RSXHtmlErrorKind::RawTextEndTag);
+        assert_eq!((error.span.start.column, error.span.end.column), (9, 22));
+        let error = render("<style>&lt;/style></style>").unwrap_err();
+        assert_eq!(error.kind, RSXHtmlErrorKind::RawTextEndTag);
+        // The end tag can also be split across children.
+        let error = render("<script>&lt;{/script}</script>").unwrap_err();
+        assert_eq!(error.kind, RSXHtmlErrorKind::RawTextEndTag);
+    }
+}
@@ -0,0 +1,64 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::error::Error;
+use std::fmt;
+
+use parse_span_types::RSXSpan;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RSXHtmlErrorKind {
+    // A code block, or spread attribute, which the caller didn't evaluate.
+    UnevaluatedExpression,
+    ElementAttributeValue,
+    MemberExpressionName,
+    VoidElementChildren,
+    // Text in a `<script>` or `<style>` element which would end it early.
+    RawTextEndTag,
+    ErrorNode
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RSXHtmlError {
+    pub kind: RSXHtmlErrorKind,
+    pub span: RSXSpan
+}
+
+impl RSXHtmlError {
+    pub fn new(kind: RSXHtmlErrorKind, span: RSXSpan) -> Self {
+        RSXHtmlError { kind, span }
+    }
+}
+
+impl fmt::Display for RSXHtmlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.span.start, self.description())
+    }
+}
+
+impl Error for RSXHtmlError {
+    fn description(&self) -> &str {
+        match self.kind {
+            RSXHtmlErrorKind::UnevaluatedExpression => "code block can't be rendered as HTML",
+            RSXHtmlErrorKind::ElementAttributeValue => {
+                "attribute value which is an element can't be rendered as HTML"
+            }
+            RSXHtmlErrorKind::MemberExpressionName => {
+                "element name which is a member expression can't be rendered as HTML"
+            }
+            RSXHtmlErrorKind::VoidElementChildren => "void element can't have children",
+            RSXHtmlErrorKind::RawTextEndTag => {
+                "text in a script or style element can't contain its end tag"
+            }
+            RSXHtmlErrorKind::ErrorNode => "source which couldn't be parsed"
+        }
+    }
+}
@@ -0,0 +1,850 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use combine::Parser;
+use combine::combinator::{optional, parser};
+
+use jsx_types::{RSXJsxConversion, RSXPortingIssue, RSXPortingIssueKind};
+use parse_attributes_types::{RSXAttribute, RSXAttributeName, RSXAttributeValue, RSXAttributes};
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_elements_types::RSXElement;
+use parse_error_types::RSXParseError;
+use parse_external_types::RSXParsedExpression;
+use parse_js::{
+    js_double_string_characters,
+    js_identifier_part,
+    js_identifier_start,
+    js_number,
+    js_single_string_characters,
+    js_whitespace
+};
+use parse_span_types::{RSXPosition, RSXSpan, RSXSpanTable, RSXSpans};
+use print_rsx::rsx_print;
+use print_types::RSXPrintOptions;
+
+// React's camel cased names for HTML attributes. Event handlers keep theirs.
+const JSX_REACT_ATTRIBUTE_NAMES: &[(&str, &str)] = &[
+    ("acceptCharset", "accept-charset"),
+    ("accessKey", "accesskey"),
+    ("autoComplete", "autocomplete"),
+    ("autoFocus", "autofocus"),
+    ("autoPlay", "autoplay"),
+    ("cellPadding", "cellpadding"),
+    ("cellSpacing", "cellspacing"),
+    ("charSet", "charset"),
+    ("className", "class"),
+    ("colSpan", "colspan"),
+    ("contentEditable", "contenteditable"),
+    ("contextMenu", "contextmenu"),
+    ("crossOrigin", "crossorigin"),
+    ("dateTime", "datetime"),
+    ("encType", "enctype"),
+    ("formAction", "formaction"),
+    ("frameBorder", "frameborder"),
+    ("htmlFor", "for"),
+    ("httpEquiv", "http-equiv"),
+    ("maxLength", "maxlength"),
+    ("minLength", "minlength"),
+    ("noValidate", "novalidate"),
+    ("readOnly", "readonly"),
+    ("rowSpan", "rowspan"),
+    ("spellCheck", "spellcheck"),
+    ("srcSet", "srcset"),
+    ("tabIndex", "tabindex"),
+    ("useMap", "usemap")
+];
+
+// Longest first, so that the longest one matches.
+const JSX_PUNCTUATORS: &[&str] = &[
+    "===", "!==", "...", "=>", "&&", "||", "==", "!=", "<=", ">=", "?."
+];
+
+// Renames React attributes in the element parsed from the given JSX source,
+// and looks for JavaScript needing porting in its code blocks. Issues are
+// reported in source order, with spans into the source.
+pub fn rsx_convert_jsx(
+    source: &str,
+    mut element: RSXElement,
+    table: &RSXSpanTable
+) -> Result<RSXJsxConversion, RSXParseError> {
+    rsx_rename_react_element(&mut element);
+
+    // Renaming keeps every node in place, so the table still matches.
+    let mut issues = {
+        let spans = RSXSpans::new(&element, table)?;
+        let mut analyzer = JSXPortingAnalyzer {
+            source,
+            spans: &spans,
+            issues: vec![]
+        };
+        analyzer.element(&element);
+        analyzer.issues
+    };
+    issues.sort_by_key(|issue| issue.span);
+    Ok(RSXJsxConversion { element, issues })
+}
+
+fn rsx_rename_react_element(element: &mut RSXElement) {
+    match element {
+        &mut RSXElement::SelfClosing(ref mut element) => {
+            rsx_rename_react_attributes(&mut element.1)
+        }
+        &mut RSXElement::Normal(ref mut element) => {
+            rsx_rename_react_attributes(&mut element.1);
+            rsx_rename_react_children(&mut element.2);
+        }
+        &mut RSXElement::Fragment(ref mut children) => rsx_rename_react_children(children),
+        &mut RSXElement::Error(_) => {}
+    }
+}
+
+fn rsx_rename_react_attributes(attributes: &mut RSXAttributes) {
+    for attribute in attributes.0.iter_mut() {
+        match attribute {
+            &mut RSXAttribute::Named(ref mut name, ref mut value) => {
+                if let &mut RSXAttributeName::Name(ref mut identifier) = name {
+                    let html = JSX_REACT_ATTRIBUTE_NAMES
+                        .iter()
+                        .find(|&&(react, _)| react == identifier.0);
+                    if let Some(&(_, html)) = html {
+                        identifier.0 = html.to_string();
+                    }
+                }
+                match value {
+                    &mut RSXAttributeValue::Element(ref mut element) => {
+                        rsx_rename_react_element(element)
+                    }
+                    &mut RSXAttributeValue::CodeBlock(ref mut expression) => {
+                        rsx_rename_react_expression(expression)
+                    }
+                    _ => {}
+                }
+            }
+            &mut RSXAttribute::Spread(ref mut expression) => {
+                rsx_rename_react_expression(expression)
+            }
+            &mut RSXAttribute::Error(_) => {}
+        }
+    }
+}
+
+fn rsx_rename_react_children(children: &mut RSXChildren) {
+    for child in children.0.iter_mut() {
+        match child {
+            &mut RSXChild::Element(ref mut element) => rsx_rename_react_element(element),
+            &mut RSXChild::CodeBlock(ref mut expression) => {
+                rsx_rename_react_expression(expression)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn rsx_rename_react_expression(expression: &mut RSXParsedExpression) {
+    for element in expression.elements.iter_mut() {
+        rsx_rename_react_element(element);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum JSXToken {
+    Identifier(String),
+    Punctuator(String),
+    Number,
+    String,
+    SingleQuotedString(String),
+    // Carries the suggested Rust equivalent.
+    TemplateString(String),
+    // An element inside the code block, printed after renaming.
+    Element(String)
+}
+
+// Tokens carry their byte range in the source.
+#[derive(Debug, Clone, PartialEq)]
+struct JSXLexeme {
+    token: JSXToken,
+    start: usize,
+    end: usize
+}
+
+impl JSXLexeme {
+    fn is_punctuator(&self, punctuator: &str) -> bool {
+        match self.token {
+            JSXToken::Punctuator(ref p) => p == punctuator,
+            _ => false
+        }
+    }
+
+    fn is_identifier(&self, identifier: &str) -> bool {
+        match self.token {
+            JSXToken::Identifier(ref i) => i == identifier,
+            _ => false
+        }
+    }
+}
+
+// Code blocks are lexed as JavaScript again, straight from the source, since
+// the parsed code lost its comments and positions. Elements inside them were
+// parsed and renamed already, so they're single tokens here, suggested as
+// printed, and analyzed on their own.
+struct JSXPortingAnalyzer<'a> {
+    source: &'a str,
+    spans: &'a RSXSpans<'a>,
+    issues: Vec<RSXPortingIssue>
+}
+
+impl<'a> JSXPortingAnalyzer<'a> {
+    fn element(&mut self, element: &RSXElement) {
+        match element {
+            &RSXElement::SelfClosing(ref element) => self.attributes(&element.1),
+            &RSXElement::Normal(ref element) => {
+                self.attributes(&element.1);
+                self.children(&element.2);
+            }
+            &RSXElement::Fragment(ref children) => self.children(children),
+            &RSXElement::Error(_) => {}
+        }
+    }
+
+    fn attributes(&mut self, attributes: &RSXAttributes) {
+        for attribute in attributes.0.iter() {
+            match attribute {
+                &RSXAttribute::Named(_, RSXAttributeValue::Element(ref element)) => {
+                    self.element(element)
+                }
+                &RSXAttribute::Named(_, RSXAttributeValue::CodeBlock(ref expression)) => {
+                    self.expression(expression)
+                }
+                &RSXAttribute::Spread(ref expression) => self.expression(expression),
+                _ => {}
+            }
+        }
+    }
+
+    fn children(&mut self, children: &RSXChildren) {
+        for child in children.0.iter() {
+            match child {
+                &RSXChild::Element(ref element) => self.element(element),
+                &RSXChild::CodeBlock(ref expression) => self.expression(expression),
+                _ => {}
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &RSXParsedExpression) {
+        let span = expression.span(self.spans);
+        // Skips code blocks without a span into this source.
+        if span.start.offset < span.end.offset && span.end.offset <= self.source.len() {
+            let options = RSXPrintOptions::default();
+            let holes = expression
+                .holes()
+                .map(|element| {
+                    let span = element.span(self.spans);
+                    (span.start.offset, span.end.offset, rsx_print(element, &options))
+                })
+                .collect::<Vec<_>>();
+            let lexemes = jsx_lex(self.source, span.start.offset + 1, span.end.offset - 1, &holes);
+            self.lexemes(span.start, &lexemes);
+        }
+
+        for element in expression.holes() {
+            self.element(element);
+        }
+    }
+
+    fn lexemes(&mut self, block_start: RSXPosition, lexemes: &[JSXLexeme]) {
+        let source = self.source;
+        let code = |start: usize, end: usize| jsx_code(source, lexemes, start, end);
+        let mut issues = vec![];
+        let mut callbacks = vec![];
+
+        // `.map()` calls are reported along with their callback, which isn't
+        // reported as an arrow function of its own.
+        for (index, lexeme) in lexemes.iter().enumerate() {
+            let is_call = index >= 1 && index + 1 < lexemes.len()
+                && lexemes[index - 1].is_punctuator(".")
+                && lexemes[index + 1].is_punctuator("(");
+            if !is_call || !lexeme.is_identifier("map") {
+                continue;
+            }
+            let close = match jsx_matching(lexemes, index + 1) {
+                Some(close) => close,
+                None => continue
+            };
+
+            let arguments = &lexemes[index + 2..close];
+            let arrow = arguments.iter().position(|lexeme| lexeme.is_punctuator("=>"));
+            let start = arrow.and_then(|arrow| jsx_arrow_start(arguments, arrow));
+            let callback = match (arrow, start) {
+                (Some(arrow), Some(0)) => {
+                    callbacks.push(index + 2 + arrow);
+                    let parameters = jsx_arrow_parameters(source, arguments, 0, arrow);
+                    let body = code(arguments[arrow].end, lexemes[close].start);
+                    format!("|{}| {}", parameters, body)
+                }
+                _ => code(lexemes[index + 1].end, lexemes[close].start)
+            };
+            issues.push((
+                RSXPortingIssueKind::MapCallback,
+                lexemes[index - 1].start,
+                lexemes[close].end,
+                Some(format!(".iter().map({}).collect::<Vec<_>>()", callback))
+            ));
+        }
+
+        for (index, lexeme) in lexemes.iter().enumerate() {
+            let (kind, start, end, suggestion) = match lexeme.token {
+                JSXToken::TemplateString(ref suggestion) => (
+                    RSXPortingIssueKind::TemplateString,
+                    lexeme.start,
+                    lexeme.end,
+                    Some(suggestion.clone())
+                ),
+                JSXToken::SingleQuotedString(ref value) => (
+                    RSXPortingIssueKind::SingleQuotedString,
+                    lexeme.start,
+                    lexeme.end,
+                    Some(format!("{:?}", value))
+                ),
+                JSXToken::Identifier(ref i) if i == "null" || i == "undefined" => (
+                    RSXPortingIssueKind::NullOrUndefined,
+                    lexeme.start,
+                    lexeme.end,
+                    Some("None".to_string())
+                ),
+                JSXToken::Punctuator(ref p) if p == "===" || p == "!==" => (
+                    RSXPortingIssueKind::StrictEquality,
+                    lexeme.start,
+                    lexeme.end,
+                    Some(p[..2].to_string())
+                ),
+                JSXToken::Punctuator(ref p) if p == "=>" && !callbacks.contains(&index) => {
+                    match jsx_arrow_start(lexemes, index) {
+                        Some(start) => {
+                            let parameters = jsx_arrow_parameters(source, lexemes, start, index);
+                            (
+                                RSXPortingIssueKind::ArrowFunction,
+                                lexemes[start].start,
+                                lexeme.end,
+                                Some(format!("|{}|", parameters))
+                            )
+                        }
+                        None => (RSXPortingIssueKind::ArrowFunction, lexeme.start, lexeme.end, None)
+                    }
+                }
+                JSXToken::Punctuator(ref p) if p == "&&" => {
+                    let (start, end) = match jsx_conditional_element(lexemes, index) {
+                        Some(operands) => operands,
+                        None => continue
+                    };
+                    let condition = code(lexemes[start].start, lexeme.start);
+                    let element = code(lexemes[end].start, lexemes[end].end);
+                    (
+                        RSXPortingIssueKind::ConditionalElement,
+                        lexemes[start].start,
+                        lexemes[end].end,
+                        Some(format!("if {} {{ {} }} else {{ <></> }}", condition, element))
+                    )
+                }
+                _ => continue
+            };
+            issues.push((kind, start, end, suggestion));
+        }
+
+        for (kind, start, end, suggestion) in issues {
+            let span = RSXSpan::new(
+                jsx_position(source, block_start, start),
+                jsx_position(source, block_start, end)
+            );
+            self.issues.push(RSXPortingIssue {
+                kind,
+                span,
+                suggestion
+            });
+        }
+    }
+}
+
+fn jsx_position(source: &str, block_start: RSXPosition, offset: usize) -> RSXPosition {
+    let mut position = block_start;
+    for c in source[block_start.offset..offset].chars() {
+        position.advance(c);
+    }
+    position
+}
+
+// Lexes the source between the given offsets, with the elements at the given
+// offsets as single tokens.
+fn jsx_lex(
+    source: &str,
+    start: usize,
+    end: usize,
+    holes: &[(usize, usize, String)]
+) -> Vec<JSXLexeme> {
+    let mut lexemes = vec![];
+    let mut offset = start;
+    while offset < end {
+        let rest = &source[offset..end];
+        let whitespace = parser(js_whitespace)
+            .parse(rest)
+            .map(|(_, remaining)| rest.len() - remaining.len())
+            .unwrap_or(0);
+        if whitespace > 0 {
+            offset += whitespace;
+            continue;
+        }
+
+        let hole = holes.iter().find(|&&(hole_start, hole_end, _)| {
+            hole_start == offset && hole_end > offset
+        });
+        let (token, len) = match hole {
+            Some(&(_, hole_end, ref element)) => {
+                (JSXToken::Element(element.clone()), hole_end - offset)
+            }
+            None => jsx_token(rest)
+        };
+        lexemes.push(JSXLexeme {
+            token,
+            start: offset,
+            end: offset + len
+        });
+        offset += len;
+    }
+    lexemes
+}
+
+// The source between the given offsets, trimmed, with the elements in it
+// printed instead.
+fn jsx_code(source: &str, lexemes: &[JSXLexeme], start: usize, end: usize) -> String {
+    let mut code = String::new();
+    let mut offset = start;
+    for lexeme in lexemes.iter().filter(|lexeme| lexeme.start >= start && lexeme.end <= end) {
+        if let JSXToken::Element(ref element) = lexeme.token {
+            code.push_str(&source[offset..lexeme.start]);
+            code.push_str(element);
+            offset = lexeme.end;
+        }
+    }
+    code.push_str(&source[offset..end]);
+    code.trim().to_string()
+}
+
+fn jsx_token(rest: &str) -> (JSXToken, usize) {
+    let consumed = |remaining: &str| rest.len() - remaining.len();
+    let c = rest.chars().next().unwrap();
+
+    if c == '"' {
+        if let Ok((_, remaining)) = parser(js_double_string_characters).parse(rest) {
+            return (JSXToken::String, consumed(remaining));
+        }
+    } else if c == '\'' {
+        if let Ok((value, remaining)) = parser(js_single_string_characters).parse(rest) {
+            return (JSXToken::SingleQuotedString(value.0), consumed(remaining));
+        }
+    } else if c == '`' {
+        return jsx_template_string(rest);
+    } else if c.is_digit(10) {
+        if let Ok((_, remaining)) = parser(js_number).parse(rest) {
+            return (JSXToken::Number, consumed(remaining));
+        }
+    }
+
+    let mut identifier = (parser(js_identifier_start), optional(parser(js_identifier_part)));
+    if let Ok(((start, part), remaining)) = identifier.parse(rest) {
+        let identifier = format!("{}{}", start.0, part.map_or(String::new(), |part| part.0));
+        return (JSXToken::Identifier(identifier), consumed(remaining));
+    }
+
+    for punctuator in JSX_PUNCTUATORS {
+        if rest.starts_with(punctuator) {
+            return (JSXToken::Punctuator(punctuator.to_string()), punctuator.len());
+        }
+    }
+    (JSXToken::Punctuator(c.to_string()), c.len_utf8())
+}
+
+// Suggests a `format!` call for the template string, or a plain string when
+// nothing is interpolated. Runs to the end when it's not terminated.
+fn jsx_template_string(rest: &str) -> (JSXToken, usize) {
+    let mut format = String::new();
+    let mut arguments = vec![];
+    let mut end = rest.len();
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '`' => {
+                end = index + 1;
+                break;
+            }
+            '\\' => match chars.next() {
+                Some((_, escaped)) if escaped == '`' || escaped == '$' => format.push(escaped),
+                // Braces needn't be escaped in JavaScript, but are still doubled
+                // in the format string.
+                Some((_, '{')) => format.push_str("{{"),
+                Some((_, '}')) => format.push_str("}}"),
+                Some((index, 'u')) => match jsx_unicode_escape(&rest[index + 1..]) {
+                    Some((escape, len)) => {
+                        for _ in 0..len {
+                            chars.next();
+                        }
+                        format.push_str(&escape);
+                    }
+                    None => format.push_str("\\u")
+                },
+                Some((index, 'x')) => match jsx_hex2(&rest[index + 1..]) {
+                    Some(code) => {
+                        chars.next();
+                        chars.next();
+                        format.push_str(&format!("\\u{{{:X}}}", code));
+                    }
+                    None => format.push_str("\\x")
+                },
+                // Legacy octal escapes, of up to three digits below `\400`.
+                Some((index, digit)) if digit.is_digit(8) => {
+                    let len = if digit <= '3' { 3 } else { 2 };
+                    let digits = rest[index..]
+                        .chars()
+                        .take(len)
+                        .take_while(|c| c.is_digit(8))
+                        .collect::<String>();
+                    for _ in 1..digits.len() {
+                        chars.next();
+                    }
+                    let code = u32::from_str_radix(&digits, 8).unwrap_or_default();
+                    format.push_str(&format!("\\u{{{:X}}}", code));
+                }
+                // Line continuations don't add anything to the string.
+                Some((index, '\r')) => {
+                    if rest[index + 1..].starts_with('\n') {
+                        chars.next();
+                    }
+                }
+                Some((_, '\n')) | Some((_, '\u{2028}')) | Some((_, '\u{2029}')) => {}
+                Some((_, 'b')) => format.push_str("\\u{8}"),
+                Some((_, 'f')) => format.push_str("\\u{C}"),
+                Some((_, 'v')) => format.push_str("\\u{B}"),
+                Some((_, escaped)) if "nrt\\'\"".contains(escaped) => {
+                    format.push_str(&format!("\\{}", escaped))
+                }
+                // Escaping any other character is the character itself.
+                Some((_, escaped)) => format.push(escaped),
+                None => {}
+            },
+            '$' if rest[index..].starts_with("${") => {
+                chars.next();
+                let mut depth = 0;
+                let mut argument_end = rest.len();
+                while let Some((index, c)) = chars.next() {
+                    match c {
+                        '{' => depth += 1,
+                        '}' if depth == 0 => {
+                            argument_end = index;
+                            break;
+                        }
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                }
+                arguments.push(rest[index + 2..argument_end].trim());
+                format.push_str("{}");
+            }
+            '{' => format.push_str("{{"),
+            '}' => format.push_str("}}"),
+            '"' => format.push_str("\\\""),
+            '\n' => format.push_str("\\n"),
+            c => format.push(c)
+        }
+    }
+
+    let suggestion = if arguments.is_empty() {
+        format!("\"{}\"", format.replace("{{", "{").replace("}}", "}"))
+    } else {
+        format!("format!(\"{}\", {})", format, arguments.join(", "))
+    };
+    (JSXToken::TemplateString(suggestion), end)
+}
+
+// Translates the digits of a `\uXXXX` escape into a `\u{XXXX}` one, returning
+// it along with the number of characters it replaces. A surrogate pair written
+// as two escapes becomes a single one, since Rust escapes are code points, and
+// `\u{...}` escapes are the same in both languages.
+fn jsx_unicode_escape(digits: &str) -> Option<(String, usize)> {
+    if digits.starts_with('{') {
+        return digits.find('}').map(|end| {
+            let escape = &digits[..end + 1];
+            (format!("\\u{}", escape), escape.chars().count())
+        });
+    }
+    let high = match jsx_hex4(digits) {
+        Some(high) => high,
+        None => return None
+    };
+    if high >= 0xD800 && high < 0xDC00 && digits[4..].starts_with("\\u") {
+        match jsx_hex4(&digits[6..]) {
+            Some(low) if low >= 0xDC00 && low < 0xE000 => {
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return Some((format!("\\u{{{:X}}}", code), 10));
+            }
+            _ => {}
+        }
+    }
+    Some((format!("\\u{{{}}}", &digits[..4]), 4))
+}
+
+fn jsx_hex2(digits: &str) -> Option<u32> {
+    match digits.get(..2) {
+        Some(hex) if hex.chars().all(|c| c.is_digit(16)) => u32::from_str_radix(hex, 16).ok(),
+        _ => None
+    }
+}
+
+fn jsx_hex4(digits: &str) -> Option<u32> {
+    match digits.get(..4) {
+        Some(hex) if hex.chars().all(|c| c.is_digit(16)) => u32::from_str_radix(hex, 16).ok(),
+        _ => None
+    }
+}
+
+fn jsx_is_opening(lexeme: &JSXLexeme) -> bool {
+    lexeme.is_punctuator("(") || lexeme.is_punctuator("[") || lexeme.is_punctuator("{")
+}
+
+fn jsx_is_closing(lexeme: &JSXLexeme) -> bool {
+    lexeme.is_punctuator(")") || lexeme.is_punctuator("]") || lexeme.is_punctuator("}")
+}
+
+// Returns the index of the bracket closing the one at the given index.
+fn jsx_matching(lexemes: &[JSXLexeme], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, lexeme) in lexemes.iter().enumerate().skip(open) {
+        if jsx_is_opening(lexeme) {
+            depth += 1;
+        } else if jsx_is_closing(lexeme) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(index);
+            }
+        }
+        if depth == 0 {
+            return None;
+        }
+    }
+    None
+}
+
+// Returns the index of the bracket opening the one at the given index.
+fn jsx_matching_back(lexemes: &[JSXLexeme], close: usize) -> Option<usize> {
+    let mut depth = 0;
+    for index in (0..close + 1).rev() {
+        if jsx_is_closing(&lexemes[index]) {
+            depth += 1;
+        } else if jsx_is_opening(&lexemes[index]) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(index);
+            }
+        }
+        if depth == 0 {
+            return None;
+        }
+    }
+    None
+}
+
+// Returns the index where the parameters of the arrow function start, which
+// are either a single identifier, or a parenthesized list.
+fn jsx_arrow_start(lexemes: &[JSXLexeme], arrow: usize) -> Option<usize> {
+    if arrow == 0 {
+        return None;
+    }
+    match lexemes[arrow - 1].token {
+        JSXToken::Identifier(_) => Some(arrow - 1),
+        JSXToken::Punctuator(ref p) if p == ")" => jsx_matching_back(lexemes, arrow - 1),
+        _ => None
+    }
+}
+
+fn jsx_arrow_parameters(
+    source: &str,
+    lexemes: &[JSXLexeme],
+    start: usize,
+    arrow: usize
+) -> String {
+    let parameters = if lexemes[start].is_punctuator("(") {
+        &source[lexemes[start].end..lexemes[arrow - 1].start]
+    } else {
+        &source[lexemes[start].start..lexemes[arrow - 1].end]
+    };
+    parameters.trim().to_string()
+}
+
+// Returns the indices of the first token of the condition, and of the element,
+// when the `&&` at the given index is followed by nothing but an element.
+fn jsx_conditional_element(lexemes: &[JSXLexeme], and: usize) -> Option<(usize, usize)> {
+    let element = and + 1;
+    match lexemes.get(element) {
+        Some(&JSXLexeme { token: JSXToken::Element(_), .. }) => {}
+        _ => return None
+    }
+    match lexemes.get(element + 1) {
+        Some(lexeme) if !jsx_is_closing(lexeme) && !jsx_is_boundary(lexeme) => return None,
+        _ => {}
+    }
+
+    let mut depth = 0;
+    let mut start = and;
+    for index in (0..and).rev() {
+        let lexeme = &lexemes[index];
+        if jsx_is_closing(lexeme) {
+            depth += 1;
+        } else if jsx_is_opening(lexeme) {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+        } else if depth == 0 && jsx_is_boundary(lexeme) {
+            break;
+        }
+        start = index;
+    }
+
+    if start == and {
+        None
+    } else {
+        Some((start, element))
+    }
+}
+
+// Tokens which end an operand of `&&`, other than brackets.
+fn jsx_is_boundary(lexeme: &JSXLexeme) -> bool {
+    [",", ";", "?", ":", "=>", "||", "="].iter().any(|p| lexeme.is_punctuator(p))
+        || lexeme.is_identifier("return")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use combine::combinator::parser;
+
+    use parse_options_types::RSXParseOptions;
+    use parse_rsx::rsx_element_ignoring_ws;
+    use parse_span_types::RSXSourceStream;
+
+    fn convert(source: &str) -> RSXJsxConversion {
+        let options = RSXParseOptions {
+            lex_code_blocks: false,
+            ..RSXParseOptions::default()
+        };
+        let (element, rest) = parser(rsx_element_ignoring_ws)
+            .parse(RSXSourceStream::with_options(source, options))
+            .unwrap();
+        rsx_convert_jsx(source, element, &rest.span_table()).unwrap()
+    }
+
+    fn suggestions(conversion: &RSXJsxConversion) -> Vec<(RSXPortingIssueKind, Option<&str>)> {
+        conversion
+            .issues
+            .iter()
+            .map(|issue| (issue.kind, issue.suggestion.as_ref().map(|s| s.as_str())))
+            .collect()
+    }
+
+    #[test]
+    pub fn test_rsx_convert_jsx_attributes() {
+        let conversion =
+            convert("<label className='a' htmlFor='b' onClick={f}>{<input tabIndex={1}/>}</label>");
+        assert_eq!(
+            rsx_print(&conversion.element, &RSXPrintOptions::default()),
+            "<label class='a' for='b' onClick={f}>{<input tabindex={1}/>}</label>"
+        );
+        assert_eq!(conversion.issues, vec![]);
+    }
+
+    #[test]
+    pub fn test_rsx_convert_jsx_issues() {
+        let conversion =
+            convert("<div onClick={(e) => go(e)}>{show && <b/>}{a === null}{'x'}</div>");
+        assert_eq!(
+            suggestions(&conversion),
+            vec![
+                (RSXPortingIssueKind::ArrowFunction, Some("|e|")),
+                (RSXPortingIssueKind::ConditionalElement, Some("if show { <b/> } else { <></> }")),
+                (RSXPortingIssueKind::StrictEquality, Some("==")),
+                (RSXPortingIssueKind::NullOrUndefined, Some("None")),
+                (RSXPortingIssueKind::SingleQuotedString, Some("\"x\"")),
+            ]
+        );
+
+        let span = conversion.issues[0].span;
+        assert_eq!((span.start.line, span.start.column), (1, 15));
+        assert_eq!((span.end.line, span.end.column), (1, 21));
+    }
+
+    #[test]
+    pub fn test_rsx_convert_jsx_map_callbacks() {
+        let conversion = convert("<ul>{items.map(item => <li>{`#${item.id}: ${item}`}</li>)}</ul>");
+        assert_eq!(
+            suggestions(&conversion),
+            vec![
+                (
+                    RSXPortingIssueKind::MapCallback,
+                    Some(
+                        ".iter().map(|item| <li>{`#${item.id}: ${item}`}</li>).collect::<Vec<_>>()"
+                    )
+                ),
+                (RSXPortingIssueKind::TemplateString, Some("format!(\"#{}: {}\", item.id, item)")),
+            ]
+        );
+
+        let conversion =
+            convert("<ul>{xs.map(x => <li className='a'>{x}</li>)}{on && <i tabIndex={1}/>}</ul>");
+        assert_eq!(
+            suggestions(&conversion),
+            vec![
+                (
+                    RSXPortingIssueKind::MapCallback,
+                    Some(".iter().map(|x| <li class='a'>{x}</li>).collect::<Vec<_>>()")
+                ),
+                (
+                    RSXPortingIssueKind::ConditionalElement,
+                    Some("if on { <i tabindex={1}/> } else { <></> }")
+                ),
+            ]
+        );
+
+        let conversion = convert("<p>{`a {b}`}{items.map(render)}</p>");
+        assert_eq!(
+            suggestions(&conversion),
+            vec![
+                (RSXPortingIssueKind::TemplateString, Some("\"a {b}\"")),
+                (RSXPortingIssueKind::MapCallback, Some(".iter().map(render).collect::<Vec<_>>()")),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_convert_jsx_template_escapes() {
+        let template = |source: &str| match jsx_template_string(source).0 {
+            JSXToken::TemplateString(suggestion) => suggestion,
+            token => panic!("{:?}", token)
+        };
+        assert_eq!(template("`\\{${a}\\}`"), "format!(\"{{{}}}\", a)");
+        assert_eq!(template("`\\{a\\}`"), "\"{a}\"");
+        assert_eq!(template("`caf\\u00e9 ${a}`"), "format!(\"caf\\u{00e9} {}\", a)");
+        assert_eq!(template("`\\uD83D\\uDE00`"), "\"\\u{1F600}\"");
+        assert_eq!(template("`\\u{1F600}${a}\\u12`"), "format!(\"\\u{1F600}{}\\u12\", a)");
+        assert_eq!(template("`\\v\\xff\\x41`"), "\"\\u{B}\\u{FF}\\u{41}\"");
+        assert_eq!(template("`\\b\\f\\n\\t\\'\\\\`"), "\"\\u{8}\\u{C}\\n\\t\\'\\\\\"");
+        assert_eq!(template("`\\0\\1\\101\\477\\a`"), "\"\\u{0}\\u{1}\\u{41}\\u{27}7a\"");
+        assert_eq!(template("`a\\\nb\\\r\nc`"), "\"abc\"");
+    }
+}
@@ -0,0 +1,71 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::fmt;
+
+use parse_elements_types::RSXElement;
+use parse_span_types::RSXSpan;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RSXPortingIssueKind {
+    // `` `Hello ${name}` ``
+    TemplateString,
+    // `cond && <x/>`, which is `false` instead of an element when `cond` is.
+    ConditionalElement,
+    // `items.map(item => <li/>)`, which needs an iterator and a collection.
+    MapCallback,
+    ArrowFunction,
+    StrictEquality,
+    // Which is a char literal, or a lifetime, in Rust.
+    SingleQuotedString,
+    NullOrUndefined
+}
+
+// JavaScript in a code block which isn't Rust, and needs porting by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSXPortingIssue {
+    pub kind: RSXPortingIssueKind,
+    pub span: RSXSpan,
+    // The Rust code to replace the source in the span with, when it's obvious.
+    pub suggestion: Option<String>
+}
+
+impl RSXPortingIssue {
+    pub fn description(&self) -> &str {
+        match self.kind {
+            RSXPortingIssueKind::TemplateString => "template string",
+            RSXPortingIssueKind::ConditionalElement => "element rendered conditionally with `&&`",
+            RSXPortingIssueKind::MapCallback => "array mapped with `.map()`",
+            RSXPortingIssueKind::ArrowFunction => "arrow function",
+            RSXPortingIssueKind::StrictEquality => "strict equality operator",
+            RSXPortingIssueKind::SingleQuotedString => "single quoted string",
+            RSXPortingIssueKind::NullOrUndefined => "`null` or `undefined`"
+        }
+    }
+}
+
+impl fmt::Display for RSXPortingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.span.start, self.description())?;
+        if let Some(ref suggestion) = self.suggestion {
+            write!(f, ", try `{}`", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+// React attributes are renamed in the element, while JavaScript in code blocks
+// is left as is, with an issue reported for everything that needs porting.
+#[derive(Debug, PartialEq)]
+pub struct RSXJsxConversion {
+    pub element: RSXElement,
+    pub issues: Vec<RSXPortingIssue>
+}
@@ -18,47 +18,251 @@ extern crate combine;
 extern crate itertools;
 #[macro_use]
 extern crate quote;
-extern crate rand;
 extern crate rsx_shared;
 extern crate self_tokenize_macro;
 extern crate self_tokenize_trait;
+extern crate syn;
 
+mod html_import;
+mod html_render;
+mod html_types;
+mod jsx_convert;
+mod jsx_types;
 mod parse_attributes_types;
 mod parse_attributes;
 mod parse_children_types;
 mod parse_children;
+mod parse_cst_types;
+mod parse_cst;
 mod parse_elements_types;
 mod parse_elements;
-mod parse_external_placeholders;
+mod parse_error_types;
+mod parse_error;
 mod parse_external_types;
 mod parse_external;
+mod parse_html_entities_table;
+mod parse_html_entities;
 mod parse_js_types;
 mod parse_js;
 mod parse_misc;
+mod parse_options_types;
+mod parse_recovering;
+mod parse_references_types;
+mod parse_references;
 mod parse_rsx;
 mod parse_rust_types;
 mod parse_rust;
+mod parse_span_types;
+mod parse_span;
+mod parse_validate;
+mod parse_whitespace;
+mod print_rsx;
+mod print_types;
 mod tokenize_attributes;
 mod tokenize_children;
 mod tokenize_elements;
+mod tokenize_error;
 mod tokenize_external;
+mod tokenize_hoisting;
+mod tokenize_names_table;
+mod tokenize_names;
+mod tokenize_types;
 
 #[cfg(test)]
 mod test_helpers;
+#[cfg(test)]
+mod tokenize_names_generator;
 
 pub mod types {
+    pub use html_types::*;
+    pub use jsx_types::*;
     pub use parse_attributes_types::*;
     pub use parse_children_types::*;
+    pub use parse_cst_types::*;
     pub use parse_elements_types::*;
-    pub use parse_external_placeholders::*;
+    pub use parse_error_types::*;
     pub use parse_external_types::*;
     pub use parse_js_types::*;
+    pub use parse_options_types::*;
+    pub use parse_references_types::*;
     pub use parse_rust_types::*;
+    pub use parse_span_types::*;
+    pub use print_types::*;
+    pub use tokenize_types::*;
 }
 
-use combine::{ParseError, Parser};
+use combine::Parser;
 use combine::combinator::parser;
 
-pub fn parse(s: &str) -> Result<(types::RSXElement, &str), ParseError<&str>> {
-    parser(parse_rsx::rsx_element_ignoring_ws).parse(s)
+pub fn parse(s: &str) -> Result<(types::RSXElement, &str), types::RSXParseError> {
+    parse_with_options(s, types::RSXParseOptions::default())
+}
+
+pub fn parse_with_options(
+    s: &str,
+    options: types::RSXParseOptions
+) -> Result<(types::RSXElement, &str), types::RSXParseError> {
+    parse_with_spans_and_options(s, options).map(|(element, _, remaining)| (element, remaining))
+}
+
+// Like `parse`, but also returns the spans of the parsed nodes, like every
+// other parse function does. Look them up with `RSXSpans::new(&element, &table)`.
+pub fn parse_with_spans(
+    s: &str
+) -> Result<(types::RSXElement, types::RSXSpanTable, &str), types::RSXParseError> {
+    parse_with_spans_and_options(s, types::RSXParseOptions::default())
+}
+
+pub fn parse_with_spans_and_options(
+    s: &str,
+    options: types::RSXParseOptions
+) -> Result<(types::RSXElement, types::RSXSpanTable, &str), types::RSXParseError> {
+    parser(parse_rsx::rsx_element_ignoring_ws)
+        .parse(types::RSXSourceStream::with_options(s, options))
+        .map_err(|error| parse_error::rsx_parse_error(s, error))
+        .and_then(|(element, remaining)| {
+            let table = remaining.span_table();
+            validate_with_options(element, &table, options)
+                .map(|element| (element, table, remaining.as_str()))
+        })
+}
+
+pub fn parse_complete(
+    s: &str
+) -> Result<(types::RSXElement, types::RSXSpanTable), types::RSXParseError> {
+    parse_complete_with_options(s, types::RSXParseOptions::default())
+}
+
+pub fn parse_complete_with_options(
+    s: &str,
+    options: types::RSXParseOptions
+) -> Result<(types::RSXElement, types::RSXSpanTable), types::RSXParseError> {
+    parser(parse_rsx::rsx_element_ignoring_ws)
+        .parse(types::RSXSourceStream::with_options(s, options))
+        .map_err(|error| parse_error::rsx_parse_error(s, error))
+        .and_then(|(element, remaining)| {
+            if remaining.as_str().is_empty() {
+                let table = remaining.span_table();
+                validate_with_options(element, &table, options).map(|element| (element, table))
+            } else {
+                Err(parse_error::rsx_trailing_input_error(&remaining))
+            }
+        })
+}
+
+pub fn parse_recovering(
+    s: &str
+) -> (types::RSXElement, types::RSXSpanTable, Vec<types::RSXDiagnostic>) {
+    parse_recovering::rsx_recovering_root(s)
+}
+
+pub fn parse_cst(s: &str) -> Result<types::RSXCstNode, types::RSXParseError> {
+    parse_cst_with_options(s, types::RSXParseOptions::default())
+}
+
+// Nodes parsed with options other than the default need the same options
+// again when lowering them, see `RSXCstNode::lower_with_options`.
+pub fn parse_cst_with_options(
+    s: &str,
+    options: types::RSXParseOptions
+) -> Result<types::RSXCstNode, types::RSXParseError> {
+    parser(parse_rsx::rsx_element_ignoring_ws)
+        .parse(types::RSXSourceStream::with_options(s, options).recording_cst())
+        .map_err(|error| parse_error::rsx_parse_error(s, error))
+        .and_then(|(element, remaining)| {
+            if remaining.as_str().is_empty() {
+                validate_with_options(element, &remaining.span_table(), options)
+                    .map(|_| parse_cst::rsx_cst_root(s, &remaining.cst_entries()))
+            } else {
+                Err(parse_error::rsx_trailing_input_error(&remaining))
+            }
+        })
+}
+
+// Lists what every code block reads, in source order.
+pub fn references(
+    element: &types::RSXElement,
+    spans: &types::RSXSpans
+) -> Vec<types::RSXExpressionReferences> {
+    parse_references::rsx_element_references(element, spans)
+}
+
+pub fn validate(element: &types::RSXElement, spans: &types::RSXSpans) -> Vec<types::RSXDiagnostic> {
+    parse_validate::rsx_validate_element(element, spans)
+}
+
+fn validate_with_options(
+    element: types::RSXElement,
+    table: &types::RSXSpanTable,
+    options: types::RSXParseOptions
+) -> Result<types::RSXElement, types::RSXParseError> {
+    if options.validate_expressions {
+        let spans = types::RSXSpans::new(&element, table)?;
+        let diagnostic = validate(&element, &spans).into_iter().next();
+        if let Some(diagnostic) = diagnostic {
+            return Err(diagnostic.error);
+        }
+    }
+    Ok(element)
+}
+
+pub fn print(element: &types::RSXElement, options: &types::RSXPrintOptions) -> String {
+    print_rsx::rsx_print(element, options)
+}
+
+// Fails on the first code block or spread attribute, since there's nothing to
+// evaluate them with. See `render_html_with`. Spans are only used to point
+// errors at the source, so trees which weren't parsed render without them.
+pub fn render_html(
+    element: &types::RSXElement,
+    spans: Option<&types::RSXSpans>
+) -> Result<String, types::RSXHtmlError> {
+    html_render::rsx_render_html(element, spans, &mut |_| None)
+}
+
+// Calls `evaluate` with every code block and spread attribute, in source order,
+// and renders what it returns. Returning `None` fails rendering.
+pub fn render_html_with<F>(
+    element: &types::RSXElement,
+    spans: Option<&types::RSXSpans>,
+    mut evaluate: F
+) -> Result<String, types::RSXHtmlError>
+where
+    F: FnMut(&types::RSXParsedExpression) -> Option<String>
+{
+    html_render::rsx_render_html(element, spans, &mut evaluate)
+}
+
+// Converts an HTML document or fragment into RSX, which `print` turns into
+// source, along with a diagnostic for every tag or attribute name which can't
+// be written in RSX. See `html_to_rsx`.
+pub fn import_html(source: &str) -> (types::RSXElement, Vec<types::RSXDiagnostic>) {
+    html_import::rsx_import_html(source)
+}
+
+pub fn html_to_rsx(
+    source: &str,
+    options: &types::RSXPrintOptions
+) -> (String, Vec<types::RSXDiagnostic>) {
+    let (element, diagnostics) = import_html(source);
+    (print(&element, options), diagnostics)
+}
+
+// Parses JSX, which is close enough to RSX, renaming React attributes and
+// reporting JavaScript in code blocks which needs porting by hand. Code blocks
+// aren't lexed, since JavaScript isn't made of Rust tokens.
+pub fn convert_jsx(source: &str) -> Result<types::RSXJsxConversion, types::RSXParseError> {
+    let options = types::RSXParseOptions {
+        lex_code_blocks: false,
+        ..types::RSXParseOptions::default()
+    };
+    parse_complete_with_options(source, options)
+        .and_then(|(element, table)| jsx_convert::rsx_convert_jsx(source, element, &table))
+}
+
+pub fn tokenize_with_backend(
+    element: &types::RSXElement,
+    backend: &types::CodegenBackend
+) -> self_tokenize_trait::Tokens {
+    tokenize_elements::rsx_element_tokens(element, backend)
 }
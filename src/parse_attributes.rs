@@ -9,8 +9,19 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
-use combine::{ParseResult, Parser, Stream};
-use combine::combinator::{between, parser, token, try, many1};
+use combine::{ParseResult, Parser};
+use combine::combinator::{
+    any,
+    between,
+    env_parser,
+    look_ahead,
+    optional,
+    parser,
+    token,
+    try,
+    many1
+};
+use combine::primitives::Consumed;
 
 use parse_attributes_types::{
     RSXAttribute,
@@ -21,8 +32,13 @@ use parse_attributes_types::{
     RSXAttributeValue,
     RSXAttributes
 };
+use parse_cst::{rsx_punctuation, rsx_record_cst, rsx_whitespace};
+use parse_cst_types::RSXCstKind;
 use parse_elements::{rsx_element, rsx_identifier, rsx_namespaced_name};
+use parse_error::rsx_error_hint;
+use parse_error_types::{RSXParseError, RSXParseErrorKind};
 use parse_external::{rsx_code_block, rsx_spread_code_block};
+use parse_html_entities::rsx_decode_entities;
 use parse_js::{
     js_boolean,
     js_double_string_characters,
@@ -30,137 +46,243 @@ use parse_js::{
     js_single_string_characters,
     js_whitespace
 };
+use parse_span::rsx_record_span;
+use parse_span_types::RSXStream;
 
 pub fn rsx_attributes<I>(input: I) -> ParseResult<RSXAttributes, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    many1(parser(rsx_attribute).skip(parser(js_whitespace))).parse_stream(input)
+    many1(parser(rsx_attribute).skip(parser(rsx_whitespace))).parse_stream(input)
 }
 
 pub fn rsx_attribute<I>(input: I) -> ParseResult<RSXAttribute, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    choice!(
-        try(parser(rsx_spread_attribute)),
-        try(parser(rsx_custom_attribute)),
-        parser(rsx_default_attribute)
-    ).parse_stream(input)
+    choice!(parser(rsx_spread_attribute), parser(rsx_named_attribute)).parse_stream(input)
 }
 
 pub fn rsx_spread_attribute<I>(input: I) -> ParseResult<RSXAttribute, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    parser(rsx_spread_code_block).map(RSXAttribute::Spread).parse_stream(input)
+    let start = input.source_position();
+    let result = parser(rsx_spread_code_block).map(RSXAttribute::Spread).parse_stream(input);
+    let result = rsx_record_cst(RSXCstKind::CodeBlock, start, result);
+    rsx_record_cst(RSXCstKind::SpreadAttribute, start, result)
 }
 
-pub fn rsx_custom_attribute<I>(input: I) -> ParseResult<RSXAttribute, I>
+pub fn rsx_named_attribute<I>(input: I) -> ParseResult<RSXAttribute, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    (
-        parser(rsx_attribute_complex_name).skip(parser(js_whitespace)),
-        token('=').skip(parser(js_whitespace)),
-        parser(rsx_attribute_value)
-    ).map(|(n, _, v)| RSXAttribute::Named(n, v))
-        .parse_stream(input)
+    let start = input.source_position();
+    let equals = try(parser(rsx_whitespace).with(env_parser('=', rsx_punctuation)));
+    let result = (
+        parser(rsx_attribute_complex_name),
+        optional(
+            equals
+                .skip(parser(rsx_whitespace))
+                .with(parser(rsx_assigned_attribute_value))
+        )
+    ).map(|(n, v)| RSXAttribute::Named(n, v.unwrap_or(RSXAttributeValue::Default)))
+        .parse_stream(input);
+    rsx_record_cst(RSXCstKind::Attribute, start, result)
 }
 
-pub fn rsx_default_attribute<I>(input: I) -> ParseResult<RSXAttribute, I>
+pub fn rsx_assigned_attribute_value<I>(input: I) -> ParseResult<RSXAttributeValue, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    parser(rsx_attribute_complex_name)
-        .map(|n| RSXAttribute::Named(n, RSXAttributeValue::Default))
-        .parse_stream(input)
+    let position = input.source_position();
+    parser(rsx_attribute_value).parse_stream(input).map_err(|error| {
+        let hint = RSXParseError::new(RSXParseErrorKind::InvalidAttributeValue, position);
+        rsx_error_hint(error, hint)
+    })
 }
 
 pub fn rsx_attribute_complex_name<I>(input: I) -> ParseResult<RSXAttributeName, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    choice!(
+    let start = input.source_position();
+    let result = choice!(
         try(parser(rsx_namespaced_name).map(|(ns, n)| RSXAttributeName::NamedspacedName(ns, n))),
         parser(rsx_identifier).map(RSXAttributeName::Name)
-    ).parse_stream(input)
+    ).parse_stream(input);
+    rsx_record_cst(RSXCstKind::AttributeName, start, result)
 }
 
 pub fn rsx_attribute_value<I>(input: I) -> ParseResult<RSXAttributeValue, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
+    let start = input.source_position();
     choice!(
+        try(parser(rsx_attribute_literal_value)),
+        parser(rsx_element).map(RSXAttributeValue::Element)
+    ).and_then(move |value| match value {
+        RSXAttributeValue::Number(ref n) if !n.0.fits_rust_type() => {
+            let kind = RSXParseErrorKind::InvalidAttributeValue;
+            Err(RSXParseError::new(kind, start)
+                .with_expected(format!("{} literal", n.0.rust_type()))
+                .with_found(n.0.lexeme()))
+        }
+        value => Ok(value)
+    })
+        .parse_stream(input)
+}
+
+// Element values are recorded as nodes of their own instead of tokens.
+fn rsx_attribute_literal_value<I>(input: I) -> ParseResult<RSXAttributeValue, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = choice!(
         try(parser(rsx_bracketed_attribute_bool).map(RSXAttributeValue::Boolean)),
         try(parser(rsx_bracketed_attribute_number).map(RSXAttributeValue::Number)),
         try(parser(rsx_bracketed_string_characters).map(RSXAttributeValue::Str)),
-        try(parser(rsx_code_block).map(RSXAttributeValue::CodeBlock)),
-        parser(rsx_element).map(RSXAttributeValue::Element)
-    ).parse_stream(input)
+        parser(rsx_code_block).map(RSXAttributeValue::CodeBlock)
+    ).parse_stream(input);
+    rsx_record_cst(RSXCstKind::AttributeValue, start, result)
 }
 
 pub fn rsx_bracketed_attribute_bool<I>(input: I) -> ParseResult<RSXAttributeBoolean, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    choice!(
+    let start = input.source_position();
+    let result = choice!(
         parser(rsx_attribute_bool),
         between(
             token('{').skip(parser(js_whitespace)),
             token('}'),
             parser(rsx_attribute_bool).skip(parser(js_whitespace))
         )
-    ).parse_stream(input)
+    ).parse_stream(input);
+    rsx_record_span(start, result)
 }
 
 pub fn rsx_attribute_bool<I>(input: I) -> ParseResult<RSXAttributeBoolean, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     parser(js_boolean).map(RSXAttributeBoolean::from).parse_stream(input)
 }
 
 pub fn rsx_bracketed_attribute_number<I>(input: I) -> ParseResult<RSXAttributeNumber, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    choice!(
-        parser(rsx_attribute_number),
+    let start = input.source_position();
+    let result = choice!(
+        parser(rsx_unbracketed_attribute_number),
         between(
             token('{').skip(parser(js_whitespace)),
             token('}'),
             parser(rsx_attribute_number).skip(parser(js_whitespace))
         )
-    ).parse_stream(input)
+    ).parse_stream(input);
+    rsx_record_span(start, result)
+}
+
+// Numbers without brackets end where the attribute does, so that e.g. `1.5u32`
+// doesn't read as the number `1.5` followed by a `u32` attribute.
+fn rsx_unbracketed_attribute_number<I>(input: I) -> ParseResult<RSXAttributeNumber, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    (parser(rsx_attribute_number), optional(look_ahead(any())))
+        .and_then(move |(number, next)| match next {
+            Some(c) if !c.is_whitespace() && c != '/' && c != '>' => {
+                let kind = RSXParseErrorKind::InvalidAttributeValue;
+                Err(RSXParseError::new(kind, start)
+                    .with_expected("whitespace")
+                    .with_expected("/")
+                    .with_expected(">")
+                    .with_found(c.to_string()))
+            }
+            _ => Ok(number)
+        })
+        .parse_stream(input)
 }
 
 pub fn rsx_attribute_number<I>(input: I) -> ParseResult<RSXAttributeNumber, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     parser(js_number).map(RSXAttributeNumber::from).parse_stream(input)
 }
 
 pub fn rsx_bracketed_string_characters<I>(input: I) -> ParseResult<RSXAttributeString, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     choice!(
-        parser(rsx_string_characters),
-        between(token('{').skip(parser(js_whitespace)), token('}'), parser(rsx_string_characters))
+        parser(rsx_spanned_string_characters),
+        parser(rsx_braced_string_characters)
     ).parse_stream(input)
 }
 
+fn rsx_spanned_string_characters<I>(input: I) -> ParseResult<RSXAttributeString, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = parser(rsx_quoted_string_characters).parse_stream(input);
+    rsx_record_span(start, result)
+}
+
+fn rsx_braced_string_characters<I>(input: I) -> ParseResult<RSXAttributeString, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = between(
+        token('{').skip(parser(js_whitespace)),
+        token('}'),
+        parser(rsx_string_characters)
+    ).parse_stream(input);
+    rsx_record_span(start, result)
+}
+
 pub fn rsx_string_characters<I>(input: I) -> ParseResult<RSXAttributeString, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
+    parser(rsx_quoted_string_characters)
+        .skip(parser(js_whitespace))
+        .parse_stream(input)
+}
+
+fn rsx_quoted_string_characters<I>(input: I) -> ParseResult<RSXAttributeString, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let options = input.options();
     choice!(
-        try(parser(js_double_string_characters).map(RSXAttributeString::DoubleQuoted)),
+        parser(js_double_string_characters).map(RSXAttributeString::DoubleQuoted),
         parser(js_single_string_characters).map(RSXAttributeString::SingleQuoted)
-    ).skip(parser(js_whitespace))
+    ).map(|s| if options.decode_entities {
+        let value = rsx_decode_entities(s.value());
+        s.with_value(value)
+    } else {
+        s
+    })
         .parse_stream(input)
+        .map_err(|error| match error {
+            // Once the opening quote is consumed, strings can only fail when
+            // running out of input before finding the closing quote.
+            Consumed::Consumed(_) => {
+                let hint = RSXParseError::new(RSXParseErrorKind::UnterminatedString, start);
+                rsx_error_hint(error, hint)
+            }
+            Consumed::Empty(_) => error
+        })
 }
 
 #[cfg(test)]
@@ -169,6 +291,8 @@ mod tests {
 
     use super::*;
 
+    use parse_js_types::JSNumberKind;
+
     #[test]
     pub fn test_rsx_attributes_tokenize() {
         let value = parser(rsx_attributes)
@@ -231,6 +355,14 @@ mod tests {
                    attribute={-1e3}
                    attribute={-1e-3}
 
+                   attribute=0.05
+                   attribute=0x1F
+                   attribute={0o17}
+                   attribute=0b1010
+                   attribute=1_000
+                   attribute=10u32
+                   attribute={1.5f32}
+
                    attribute=<bar/>
                    attribute={<bar/>}
 
@@ -250,233 +382,265 @@ mod tests {
 
         let tokens = quote! {
             vec![
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(true)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(true)
+                )),
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("c")
+                )),
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("s")
+                )),
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar")
+                )),
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar")
+                )),
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar\"baz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("c")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar\"baz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("s")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar'baz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar\nbaz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar'baz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar\"baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar'baz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar\"baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar\"baz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar'baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar\nbaz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar\nbaz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("c")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar'baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("s")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar'baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar\"baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from("bar")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar\nbaz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(true)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("c")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(false)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("s")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(true)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(false)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from("bar")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1i64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(true)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1.2f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(false)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1.2e3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(true)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1.2e-3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(false)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1e3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(1f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1e-3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(1.2f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1i64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(1200f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1.2f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(0.0012f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1.2e3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(1000f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1.2e-3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(0.001f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1e3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-1f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1e-3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-1.2f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1i64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-1200f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1.2f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-0.0012f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1.2e3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-1000f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1.2e-3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-0.001f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1e3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(1f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1e-3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(1.2f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1i64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(1200f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1.2f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(0.0012f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1.2e3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(1000f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1.2e-3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(0.001f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1e3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-1f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(-1e-3f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-1.2f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(0.05f64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-1200f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(0x1Fi64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-0.0012f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(0o17i64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-1000f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(0b1010i64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(-0.001f64)
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1_000i64)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from(DOMNode::from(DOMTagName::from("bar")))
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(10u32)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from({ DOMNode::from(DOMTagName::from("bar")) })
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(1.5f32)
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from({
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from(
+                        ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("bar"))
+                    )
+                )),
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from({
+                        ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("bar"))
+                    })
+                )),
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from({
                         {
                             'c'
                         }
                     })
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from({
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from({
                         {
                             "s"
                         }
                     })
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from({ 1 + 2 + 3 })
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from({ 1 + 2 + 3 })
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("attribute"),
-                    DOMAttributeValue::from({
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("attribute"),
+                    ::rsx_dom::types::DOMAttributeValue::from({
                         {
                             1 + { 2 + { 3 } }
                         }
                     })
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from("foo-bar"),
-                    DOMAttributeValue::from("baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from("foo-bar"),
+                    ::rsx_dom::types::DOMAttributeValue::from("baz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from(("foo", "bar")),
-                    DOMAttributeValue::from("baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from(("foo", "bar")),
+                    ::rsx_dom::types::DOMAttributeValue::from("baz")
                 )),
-                DOMAttribute::from((
-                    DOMAttributeName::from(("foo-a", "bar-b")),
-                    DOMAttributeValue::from("baz")
+                ::rsx_dom::types::DOMAttribute::from((
+                    ::rsx_dom::types::DOMAttributeName::from(("foo-a", "bar-b")),
+                    ::rsx_dom::types::DOMAttributeValue::from("baz")
                 )),
             ]
         };
@@ -588,6 +752,18 @@ mod tests {
             parser(rsx_attribute_value).parse(r#"'bar'"#).unwrap(),
             (("bar", '"').into(), "")
         );
+        assert_eq!(
+            parser(rsx_attribute_value).parse("1.5 u32").unwrap(),
+            (RSXAttributeValue::Number(("1.5", JSNumberKind::Float).into()), " u32")
+        );
+        assert_eq!(parser(rsx_attribute_value).parse("1.5u32").is_err(), true);
+        assert_eq!(parser(rsx_attribute_value).parse("1x").is_err(), true);
+        assert_eq!(parser(rsx_attribute_value).parse("0b1f32").is_err(), true);
+        assert_eq!(parser(rsx_attribute_value).parse("0o7f64").is_err(), true);
+        assert_eq!(
+            parser(rsx_attribute_value).parse("{1.5}u32").unwrap(),
+            (RSXAttributeValue::Number(("1.5", JSNumberKind::Float).into()), "u32")
+        );
     }
 
     #[test]
@@ -9,6 +9,7 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
+use std::fmt;
 use std::iter::FromIterator;
 
 use rsx_shared::types::KnownAttributeName;
@@ -16,8 +17,10 @@ use self_tokenize_macro::DefaultQuote;
 use self_tokenize_trait::ToCustomTokens;
 
 use parse_elements_types::{RSXElement, RSXIdentifier};
+use parse_error_types::RSXErrorNode;
 use parse_external_types::RSXParsedExpression;
 use parse_js_types::{JSBool, JSDoubleStringCharacters, JSNumber, JSSingleStringCharacters};
+use parse_span_types::{RSXSpan, RSXSpans};
 
 #[derive(Default, Debug, PartialEq, DefaultQuote)]
 pub struct RSXAttributes(pub Box<[RSXAttribute]>);
@@ -43,7 +46,19 @@ impl FromIterator<RSXAttribute> for RSXAttributes {
 #[derive(Debug, PartialEq, DefaultQuote)]
 pub enum RSXAttribute {
     Named(RSXAttributeName, RSXAttributeValue),
-    Spread(RSXParsedExpression)
+    Spread(RSXParsedExpression),
+    Error(RSXErrorNode)
+}
+
+impl RSXAttribute {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        match self {
+            &RSXAttribute::Named(ref n, RSXAttributeValue::Default) => n.span(spans),
+            &RSXAttribute::Named(ref n, ref v) => n.span(spans).join(&v.span(spans)),
+            &RSXAttribute::Spread(ref expression) => expression.span(spans),
+            &RSXAttribute::Error(ref error) => error.span()
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, DefaultQuote)]
@@ -53,6 +68,51 @@ pub enum RSXAttributeName {
     NamedspacedName(RSXIdentifier, RSXIdentifier)
 }
 
+impl RSXAttributeName {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        match self {
+            &RSXAttributeName::Name(ref n) => n.span(spans),
+            &RSXAttributeName::NamedspacedName(ref ns, ref n) => {
+                ns.span(spans).join(&n.span(spans))
+            }
+            &RSXAttributeName::KnownName(_) => RSXSpan::default()
+        }
+    }
+}
+
+impl fmt::Display for RSXAttributeName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::RSXAttributeName::*;
+        match self {
+            &Name(ref n) => write!(f, "{}", n.0),
+            &NamedspacedName(ref ns, ref n) => write!(f, "{}:{}", ns.0, n.0),
+            &KnownName(ref n) => write!(f, "{}", known_attribute_name(n))
+        }
+    }
+}
+
+fn known_attribute_name(name: &KnownAttributeName) -> &'static str {
+    use self::KnownAttributeName::*;
+    match name {
+        &Accesskey => "accesskey",
+        &Class => "class",
+        &CntEditable => "contenteditable",
+        &Contextmenu => "contextmenu",
+        &Dir => "dir",
+        &Draggable => "draggable",
+        &Dropzone => "dropzone",
+        &Hidden => "hidden",
+        &Id => "id",
+        &Lang => "lang",
+        &Spellcheck => "spellcheck",
+        &Src => "src",
+        &Style => "style",
+        &Tabindex => "tabindex",
+        &Title => "title",
+        &Translate => "translate"
+    }
+}
+
 #[derive(Debug, PartialEq, DefaultQuote)]
 pub enum RSXAttributeValue {
     Default,
@@ -63,9 +123,28 @@ pub enum RSXAttributeValue {
     CodeBlock(RSXParsedExpression)
 }
 
+impl RSXAttributeValue {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        match self {
+            &RSXAttributeValue::Default => RSXSpan::default(),
+            &RSXAttributeValue::Boolean(ref boolean) => boolean.span(spans),
+            &RSXAttributeValue::Number(ref number) => number.span(spans),
+            &RSXAttributeValue::Str(ref string) => string.span(spans),
+            &RSXAttributeValue::Element(ref element) => element.span(spans),
+            &RSXAttributeValue::CodeBlock(ref expression) => expression.span(spans)
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, DefaultQuote)]
 pub struct RSXAttributeBoolean(pub bool);
 
+impl RSXAttributeBoolean {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+}
+
 impl From<JSBool> for RSXAttributeBoolean {
     fn from(v: JSBool) -> Self {
         RSXAttributeBoolean(v.0)
@@ -73,16 +152,53 @@ impl From<JSBool> for RSXAttributeBoolean {
 }
 
 #[derive(Debug, PartialEq, DefaultQuote)]
-pub struct RSXAttributeNumber(pub f64);
+pub struct RSXAttributeNumber(pub JSNumber);
+
+impl RSXAttributeNumber {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+}
 
 impl From<JSNumber> for RSXAttributeNumber {
     fn from(n: JSNumber) -> Self {
-        RSXAttributeNumber(n.0)
+        RSXAttributeNumber(n)
     }
 }
 
-#[derive(Debug, PartialEq, DefaultQuote)]
+#[derive(Debug, DefaultQuote)]
 pub enum RSXAttributeString {
     SingleQuoted(JSSingleStringCharacters),
     DoubleQuoted(JSDoubleStringCharacters)
 }
+
+impl RSXAttributeString {
+    pub fn value(&self) -> &str {
+        match self {
+            &RSXAttributeString::SingleQuoted(ref chars) => &chars.0,
+            &RSXAttributeString::DoubleQuoted(ref chars) => &chars.0
+        }
+    }
+
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+
+    pub fn with_value(self, value: String) -> Self {
+        match self {
+            RSXAttributeString::SingleQuoted(_) => {
+                RSXAttributeString::SingleQuoted(JSSingleStringCharacters(value))
+            }
+            RSXAttributeString::DoubleQuoted(_) => {
+                RSXAttributeString::DoubleQuoted(JSDoubleStringCharacters(value))
+            }
+        }
+    }
+}
+
+// The quote style is only a matter of presentation.
+impl PartialEq for RSXAttributeString {
+    fn eq(&self, other: &RSXAttributeString) -> bool {
+        self.value() == other.value()
+    }
+}
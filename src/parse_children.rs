@@ -9,49 +9,125 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
+use std::any::TypeId;
+
 use combine::{ParseResult, Parser, Stream};
 use combine::char::spaces;
-use combine::combinator::{look_ahead, none_of, one_of, optional, parser, try, many1};
+use combine::combinator::{look_ahead, none_of, optional, parser, try, many1};
 
 use parse_children_types::{RSXChild, RSXChildren, RSXText, RSXTextCharacter};
+use parse_cst::{rsx_record_cst, rsx_whitespace};
+use parse_cst_types::RSXCstKind;
 use parse_elements::rsx_element;
 use parse_external::rsx_code_block;
-use parse_js::js_whitespace;
+use parse_external_types::RSXParsedExpression;
+use parse_html_entities::rsx_decode_entities;
+use parse_js::{js_comment, js_whitespace};
+use parse_misc::open_tag;
+use parse_span_types::{RSXSpan, RSXStream};
+use parse_whitespace::rsx_normalize_whitespace;
 
 pub fn rsx_children<I>(input: I) -> ParseResult<RSXChildren, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    many1(parser(rsx_child).skip(parser(js_whitespace))).parse_stream(input)
+    many1(parser(rsx_child).skip(optional(parser(rsx_child_comments))))
+        .map(|children: Vec<RSXChild>| {
+            // Text that's only insignificant whitespace isn't a child.
+            children
+                .into_iter()
+                .filter(|child| match child {
+                    &RSXChild::Text(ref text) => !text.0.is_empty(),
+                    _ => true
+                })
+                .collect()
+        })
+        .parse_stream(input)
 }
 
 pub fn rsx_child<I>(input: I) -> ParseResult<RSXChild, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     choice!(
-        try(parser(rsx_code_block).map(RSXChild::CodeBlock)),
-        try(parser(rsx_element).map(RSXChild::Element)),
+        parser(rsx_child_code_block).map(RSXChild::CodeBlock),
+        try(look_ahead(parser(rsx_child_element_open)))
+            .with(parser(rsx_element))
+            .map(RSXChild::Element),
         parser(rsx_text).map(RSXChild::Text)
     ).parse_stream(input)
 }
 
+// Comments following a child are skipped along with the whitespace around
+// them. Comments inside text are part of the text.
+pub fn rsx_child_comments<I>(input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    try(look_ahead((spaces(), parser(js_comment))))
+        .with(parser(rsx_whitespace))
+        .parse_stream(input)
+}
+
+pub fn rsx_child_code_block<I>(input: I) -> ParseResult<RSXParsedExpression, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = parser(rsx_code_block).parse_stream(input);
+    rsx_record_cst(RSXCstKind::CodeBlock, start, result)
+}
+
+// Anything starting with `<` that isn't a closing tag must be an element, so
+// commit to parsing it and don't swallow its errors.
+pub fn rsx_child_element_open<I>(input: I) -> ParseResult<char, I>
+where
+    I: RSXStream
+{
+    (parser(open_tag), parser(js_whitespace), none_of("/".chars()))
+        .map(|(_, _, c)| c)
+        .parse_stream(input)
+}
+
 pub fn rsx_text<I>(input: I) -> ParseResult<RSXText, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    many1(parser(rsx_text_character)).parse_stream(input)
+    let options = input.options();
+    let start = input.source_position();
+    many1(parser(rsx_text_character))
+        .map(|text: RSXText| {
+            let text = rsx_normalize_whitespace(&text.0, options.whitespace);
+            if options.decode_entities {
+                RSXText(rsx_decode_entities(&text))
+            } else {
+                RSXText(text)
+            }
+        })
+        .parse_stream(input)
+        .map(|(text, consumed)| {
+            // Dropped whitespace-only text mustn't leave an entry in the span
+            // table, and is only trivia in the CST.
+            let recorded = !text.0.is_empty();
+            let consumed = consumed.map(|mut input: I| {
+                let span = RSXSpan::new(start, input.source_position());
+                if recorded {
+                    input.record_span(TypeId::of::<RSXText>(), span);
+                    input.record_cst(RSXCstKind::Text, span);
+                } else {
+                    input.record_cst(RSXCstKind::Whitespace, span);
+                }
+                input
+            });
+            (text, consumed)
+        })
 }
 
 pub fn rsx_text_character<I>(input: I) -> ParseResult<RSXTextCharacter, I>
 where
     I: Stream<Item = char>
 {
-    let invalid = "{}<>";
-    none_of(invalid.chars())
-        .skip(optional(try(spaces().with(look_ahead(one_of(invalid.chars()))))))
-        .map(RSXTextCharacter)
-        .parse_stream(input)
+    none_of("{}<>".chars()).map(RSXTextCharacter).parse_stream(input)
 }
 
 #[cfg(test)]
@@ -59,6 +135,7 @@ mod tests {
     extern crate syn;
 
     use super::*;
+    use parse_elements_types::RSXElement;
 
     #[test]
     pub fn test_rsx_children_tokenize() {
@@ -77,10 +154,12 @@ mod tests {
 
         let tokens = quote! {
             vec![
-                DOMNode::from("foo\n                   foo bar baz\n                   123"),
-                DOMNode::from(DOMTagName::from("foo")),
-                DOMNode::from({ DOMNode::from(DOMTagName::from("foo")) }),
-                DOMNode::from({
+                ::rsx_dom::types::DOMNode::from("foo foo bar baz 123"),
+                ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("foo")),
+                ::rsx_dom::types::DOMNode::from({
+                    ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("foo"))
+                }),
+                ::rsx_dom::types::DOMNode::from({
                     {
                         1 + { 2 + { 3 } }
                     }
@@ -117,7 +196,40 @@ mod tests {
         );
         assert_eq!(
             parser(rsx_children).parse("\"foo\"\n\"bar\"\n\"baz\"").unwrap(),
-            (RSXChildren::from(vec!["\"foo\"\n\"bar\"\n\"baz\"".into()]), "")
+            (RSXChildren::from(vec!["\"foo\" \"bar\" \"baz\"".into()]), "")
+        );
+        assert_eq!(
+            parser(rsx_children).parse("\n  foo\n  <bar/>\n").unwrap(),
+            (
+                RSXChildren::from(vec![
+                    "foo".into(),
+                    RSXChild::Element(RSXElement::SelfClosing("bar".into())),
+                ]),
+                ""
+            )
+        );
+        assert_eq!(
+            parser(rsx_children).parse("foo <bar/> baz").unwrap(),
+            (
+                RSXChildren::from(vec![
+                    "foo ".into(),
+                    RSXChild::Element(RSXElement::SelfClosing("bar".into())),
+                    " baz".into(),
+                ]),
+                ""
+            )
+        );
+        assert_eq!(
+            parser(rsx_children).parse("<foo/> /* a */ <bar/> // b\n <baz/> c /* d */").unwrap(),
+            (
+                RSXChildren::from(vec![
+                    RSXChild::Element(RSXElement::SelfClosing("foo".into())),
+                    RSXChild::Element(RSXElement::SelfClosing("bar".into())),
+                    RSXChild::Element(RSXElement::SelfClosing("baz".into())),
+                    " c /* d */".into(),
+                ]),
+                ""
+            )
         );
     }
 
@@ -126,26 +238,33 @@ mod tests {
         assert_eq!(parser(rsx_child).parse("").is_err(), true);
         assert_eq!(parser(rsx_child).parse(" ").unwrap(), (" ".into(), ""));
         assert_eq!(parser(rsx_child).parse("foo").unwrap(), ("foo".into(), ""));
+        assert_eq!(parser(rsx_child).parse("</foo>").is_err(), true);
+        assert_eq!(parser(rsx_child).parse("<foo").is_err(), true);
         assert_eq!(
             parser(rsx_child).parse("\"foo\" \"bar\" \"baz\"").unwrap(),
             ("\"foo\" \"bar\" \"baz\"".into(), "")
         );
         assert_eq!(
             parser(rsx_child).parse("\"foo\"\n\"bar\"\n\"baz\"").unwrap(),
-            ("\"foo\"\n\"bar\"\n\"baz\"".into(), "")
+            ("\"foo\" \"bar\" \"baz\"".into(), "")
         );
     }
 
     #[test]
     pub fn test_rsx_text() {
         assert_eq!(parser(rsx_text).parse("").is_err(), true);
-        assert_eq!(parser(rsx_text).parse("foo < bar").unwrap(), ("foo".into(), "< bar"));
-        assert_eq!(parser(rsx_text).parse("foo > bar").unwrap(), ("foo".into(), "> bar"));
+        assert_eq!(parser(rsx_text).parse("foo < bar").unwrap(), ("foo ".into(), "< bar"));
+        assert_eq!(parser(rsx_text).parse("foo > bar").unwrap(), ("foo ".into(), "> bar"));
         assert_eq!(parser(rsx_text).parse("foo bar baz").unwrap(), ("foo bar baz".into(), ""));
         assert_eq!(
             parser(rsx_text).parse("foo { bar } baz").unwrap(),
-            ("foo".into(), "{ bar } baz")
+            ("foo ".into(), "{ bar } baz")
+        );
+        assert_eq!(
+            parser(rsx_text).parse("\n  foo  \n  bar  \n<baz/>").unwrap(),
+            ("foo bar".into(), "<baz/>")
         );
+        assert_eq!(parser(rsx_text).parse("\n  <baz/>").unwrap(), ("".into(), "<baz/>"));
     }
 
     #[test]
@@ -15,11 +15,19 @@ use self_tokenize_macro::DefaultQuote;
 use self_tokenize_trait::ToCustomTokens;
 
 use parse_elements_types::RSXElement;
+use parse_error_types::RSXErrorNode;
 use parse_external_types::RSXParsedExpression;
+use parse_span_types::{RSXSpan, RSXSpans};
 
 #[derive(Default, Debug, PartialEq, DefaultQuote)]
 pub struct RSXChildren(pub Box<[RSXChild]>);
 
+impl RSXChildren {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+}
+
 impl From<Option<RSXChildren>> for RSXChildren {
     fn from(children: Option<RSXChildren>) -> Self {
         children.unwrap_or_default()
@@ -42,12 +50,30 @@ impl FromIterator<RSXChild> for RSXChildren {
 pub enum RSXChild {
     Element(RSXElement),
     Text(RSXText),
-    CodeBlock(RSXParsedExpression)
+    CodeBlock(RSXParsedExpression),
+    Error(RSXErrorNode)
+}
+
+impl RSXChild {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        match self {
+            &RSXChild::Element(ref element) => element.span(spans),
+            &RSXChild::Text(ref text) => text.span(spans),
+            &RSXChild::CodeBlock(ref expression) => expression.span(spans),
+            &RSXChild::Error(ref error) => error.span()
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, DefaultQuote)]
 pub struct RSXText(pub String);
 
+impl RSXText {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+}
+
 impl FromIterator<RSXTextCharacter> for RSXText {
     fn from_iter<I: IntoIterator<Item = RSXTextCharacter>>(iter: I) -> Self {
         RSXText(iter.into_iter().map(|c| c.0).collect())
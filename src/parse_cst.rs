@@ -0,0 +1,524 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::any::TypeId;
+use std::cmp::Reverse;
+
+use combine::{ParseResult, Parser};
+use combine::char::space;
+use combine::combinator::{parser, skip_many, skip_many1, token, try};
+
+use parse_attributes::{rsx_attribute_complex_name, rsx_attribute_value};
+use parse_attributes_types::{RSXAttribute, RSXAttributeValue};
+use parse_children::rsx_text;
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_cst_types::{RSXCstChild, RSXCstKind, RSXCstNode, RSXCstToken};
+use parse_elements::rsx_element_name;
+use parse_elements_types::{RSXElement, RSXNormalElement, RSXSelfClosingElement};
+use parse_error::{rsx_parse_error, rsx_trailing_input_error};
+use parse_error_types::{RSXErrorNode, RSXParseError, RSXParseErrorKind};
+use parse_external::{rsx_code_block, rsx_spread_code_block};
+use parse_js::js_comment;
+use parse_options_types::RSXParseOptions;
+use parse_span_types::{RSXPosition, RSXSourceStream, RSXSpan, RSXSpanTable, RSXStream};
+use parse_whitespace::rsx_children_options;
+
+// The concrete syntax tree is recorded by the regular grammar as it parses:
+// every terminal (`<`, `</`, `=`, names, attribute values, text and code
+// blocks), every run of whitespace and every comment is recorded as a token,
+// and elements and attributes as nodes. Empty source records nothing.
+pub fn rsx_record_cst<O, I>(
+    kind: RSXCstKind,
+    start: RSXPosition,
+    result: ParseResult<O, I>
+) -> ParseResult<O, I>
+where
+    I: RSXStream
+{
+    result.map(|(output, consumed)| {
+        let consumed = consumed.map(|mut input: I| {
+            let end = input.source_position();
+            if end.offset > start.offset {
+                input.record_cst(kind, RSXSpan::new(start, end));
+            }
+            input
+        });
+        (output, consumed)
+    })
+}
+
+pub fn rsx_punctuation<I>(c: char, input: I) -> ParseResult<char, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = token(c).parse_stream(input);
+    rsx_record_cst(RSXCstKind::Punctuation, start, result)
+}
+
+// Like `js_whitespace`, but keeps the whitespace and comments as trivia.
+pub fn rsx_whitespace<I>(input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    skip_many(choice!(try(parser(rsx_comment_trivia)), parser(rsx_space_trivia)))
+        .parse_stream(input)
+}
+
+fn rsx_comment_trivia<I>(input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = parser(js_comment).parse_stream(input);
+    rsx_record_cst(RSXCstKind::Comment, start, result)
+}
+
+fn rsx_space_trivia<I>(input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = skip_many1(space()).parse_stream(input);
+    rsx_record_cst(RSXCstKind::Whitespace, start, result)
+}
+
+// Parsers finish nodes after their contents, so the recorded entries are
+// sorted by where they start, outer ones first. Entries inside a token, e.g.
+// elements inside a code block, are part of the token's text instead. Source
+// which nothing was recorded for can only be left over by a broken grammar,
+// and is kept as an error token, so that the tree still prints the source.
+pub fn rsx_cst_root(source: &str, entries: &[(RSXCstKind, RSXSpan)]) -> RSXCstNode {
+    let mut end = RSXPosition::start();
+    for c in source.chars() {
+        end.advance(c);
+    }
+
+    let mut entries = entries.to_vec();
+    entries.sort_by_key(|&(kind, span)| {
+        (span.start.offset, Reverse(span.end.offset), !kind.is_node())
+    });
+
+    let root = RSXSpan::new(RSXPosition::start(), end);
+    let mut builder = RSXCstBuilder {
+        source,
+        stack: vec![RSXCstBuilderNode::new(RSXCstKind::Root, root)]
+    };
+    for &(kind, span) in entries.iter() {
+        builder.entry(kind, span);
+    }
+    builder.finish()
+}
+
+struct RSXCstBuilderNode {
+    node: RSXCstNode,
+    position: RSXPosition
+}
+
+impl RSXCstBuilderNode {
+    fn new(kind: RSXCstKind, span: RSXSpan) -> Self {
+        RSXCstBuilderNode {
+            node: RSXCstNode {
+                kind,
+                span,
+                children: vec![]
+            },
+            position: span.start
+        }
+    }
+}
+
+struct RSXCstBuilder<'a> {
+    source: &'a str,
+    stack: Vec<RSXCstBuilderNode>
+}
+
+impl<'a> RSXCstBuilder<'a> {
+    fn entry(&mut self, kind: RSXCstKind, span: RSXSpan) {
+        while self.stack.len() > 1 && self.top().node.span.end.offset <= span.start.offset {
+            self.close();
+        }
+        if self.top().position.offset > span.start.offset {
+            return;
+        }
+
+        self.gap(span.start);
+        if kind.is_node() {
+            self.stack.push(RSXCstBuilderNode::new(kind, span));
+        } else {
+            let text = self.source[span.start.offset..span.end.offset].to_string();
+            self.push(RSXCstChild::Token(RSXCstToken { kind, span, text }), span.end);
+        }
+    }
+
+    fn finish(mut self) -> RSXCstNode {
+        while self.stack.len() > 1 {
+            self.close();
+        }
+        let end = self.top().node.span.end;
+        self.gap(end);
+        self.stack.pop().unwrap().node
+    }
+
+    fn top(&mut self) -> &mut RSXCstBuilderNode {
+        self.stack.last_mut().unwrap()
+    }
+
+    fn close(&mut self) {
+        let end = self.top().node.span.end;
+        self.gap(end);
+        let node = self.stack.pop().unwrap().node;
+        self.push(RSXCstChild::Node(node), end);
+    }
+
+    fn push(&mut self, child: RSXCstChild, end: RSXPosition) {
+        let top = self.top();
+        top.node.children.push(child);
+        top.position = end;
+    }
+
+    fn gap(&mut self, end: RSXPosition) {
+        let start = self.top().position;
+        if start.offset < end.offset {
+            let text = self.source[start.offset..end.offset].to_string();
+            let kind = RSXCstKind::Error;
+            let span = RSXSpan::new(start, end);
+            self.push(RSXCstChild::Token(RSXCstToken { kind, span, text }), end);
+        }
+    }
+}
+
+// Lowering follows the structure of the tree, and only parses the leaf tokens
+// (names, attribute values, text and code blocks) again, so that edits to them
+// are picked up. Positions are counted from the start of the node again, since
+// edits may have moved things around, and the span table is built up in the
+// same order the grammar records it in.
+pub fn rsx_cst_lower(
+    node: &RSXCstNode,
+    options: RSXParseOptions
+) -> Result<(RSXElement, RSXSpanTable), RSXParseError> {
+    let node = rsx_cst_respan(node, &mut RSXPosition::start());
+    let source = node.to_string();
+    let mut lowering = RSXCstLowering {
+        source: &source,
+        options,
+        table: vec![]
+    };
+    let element = lowering.element(&node)?;
+    Ok((element, RSXSpanTable(lowering.table)))
+}
+
+fn rsx_cst_respan(node: &RSXCstNode, position: &mut RSXPosition) -> RSXCstNode {
+    let start = *position;
+    let mut children = vec![];
+    for child in node.children.iter() {
+        children.push(match child {
+            &RSXCstChild::Node(ref node) => RSXCstChild::Node(rsx_cst_respan(node, position)),
+            &RSXCstChild::Token(ref token) => {
+                let start = *position;
+                for c in token.text.chars() {
+                    position.advance(c);
+                }
+                RSXCstChild::Token(RSXCstToken {
+                    kind: token.kind,
+                    span: RSXSpan::new(start, *position),
+                    text: token.text.clone()
+                })
+            }
+        });
+    }
+    RSXCstNode {
+        kind: node.kind,
+        span: RSXSpan::new(start, *position),
+        children
+    }
+}
+
+struct RSXCstLowering<'a> {
+    source: &'a str,
+    options: RSXParseOptions,
+    table: Vec<(TypeId, RSXSpan)>
+}
+
+impl<'a> RSXCstLowering<'a> {
+    fn element(&mut self, node: &RSXCstNode) -> Result<RSXElement, RSXParseError> {
+        match node.kind {
+            RSXCstKind::Root => {
+                let element = node.children.iter().find(|child| !child.kind().is_trivia());
+                match element {
+                    Some(&RSXCstChild::Node(ref node)) => self.element(node),
+                    Some(&RSXCstChild::Token(ref token)) => Err(rsx_cst_error(token.span.start)),
+                    None => {
+                        let error = RSXParseErrorKind::UnexpectedEof;
+                        Err(RSXParseError::new(error, node.span.end).with_found("end of input"))
+                    }
+                }
+            }
+            RSXCstKind::Element => self.normal_or_self_closing_element(node),
+            RSXCstKind::Fragment => {
+                let children = self.children(&node.children)?;
+                self.table.push((TypeId::of::<RSXChildren>(), node.span));
+                Ok(RSXElement::Fragment(children))
+            }
+            RSXCstKind::Error => Ok(RSXElement::Error(RSXErrorNode(node.span))),
+            _ => Err(rsx_cst_error(node.span.start))
+        }
+    }
+
+    // Self-closing elements end with a `/>` token. The children of normal
+    // elements are everything between the `>` ending the opening tag and the
+    // `</` starting the closing tag.
+    fn normal_or_self_closing_element(
+        &mut self,
+        node: &RSXCstNode
+    ) -> Result<RSXElement, RSXParseError> {
+        let mut name = None;
+        let mut attributes = vec![];
+        let mut opening = None;
+        for (index, child) in node.children.iter().enumerate() {
+            match child {
+                &RSXCstChild::Token(ref token) if token.kind == RSXCstKind::ElementName => {
+                    name = Some(self.leaf(token, rsx_element_name)?);
+                }
+                &RSXCstChild::Token(ref token) if token.kind == RSXCstKind::Punctuation => {
+                    if token.text.starts_with('/') || token.text == ">" {
+                        opening = Some((index, token));
+                        break;
+                    }
+                }
+                &RSXCstChild::Node(ref node) => attributes.push(self.attribute(node)?),
+                &RSXCstChild::Token(ref token) if token.kind == RSXCstKind::Error => {
+                    attributes.push(RSXAttribute::Error(RSXErrorNode(token.span)));
+                }
+                _ => {}
+            }
+        }
+
+        let (name, (index, opening)) = match (name, opening) {
+            (Some(name), Some(opening)) => (name, opening),
+            _ => return Err(rsx_cst_error(node.span.start))
+        };
+        if opening.text.starts_with('/') {
+            self.table.push((TypeId::of::<RSXSelfClosingElement>(), node.span));
+            let element = RSXSelfClosingElement(name, attributes.into());
+            return Ok(RSXElement::SelfClosing(element));
+        }
+
+        let closing = node.children.iter().rposition(|child| match child {
+            &RSXCstChild::Token(ref token) => {
+                token.kind == RSXCstKind::Punctuation && token.text.starts_with('<')
+            }
+            _ => false
+        });
+        let closing = match closing {
+            Some(closing) if closing > index => closing,
+            _ => return Err(rsx_cst_error(opening.span.end))
+        };
+
+        let options = self.options;
+        self.options = rsx_children_options(&name, options);
+        let children = self.children(&node.children[index + 1..closing]);
+        self.options = options;
+
+        let children_span = RSXSpan::new(opening.span.end, node.children[closing].span().start);
+        self.table.push((TypeId::of::<RSXChildren>(), children_span));
+        self.table.push((TypeId::of::<RSXNormalElement>(), node.span));
+        Ok(RSXElement::Normal(RSXNormalElement(name, attributes.into(), children?)))
+    }
+
+    fn attribute(&mut self, node: &RSXCstNode) -> Result<RSXAttribute, RSXParseError> {
+        let mut name = None;
+        let mut value = RSXAttributeValue::Default;
+        for child in node.children.iter() {
+            match child {
+                &RSXCstChild::Token(ref token) => match token.kind {
+                    RSXCstKind::AttributeName => {
+                        name = Some(self.leaf(token, rsx_attribute_complex_name)?);
+                    }
+                    RSXCstKind::AttributeValue => value = self.leaf(token, rsx_attribute_value)?,
+                    RSXCstKind::CodeBlock if node.kind == RSXCstKind::SpreadAttribute => {
+                        let expression = self.leaf(token, rsx_spread_code_block)?;
+                        return Ok(RSXAttribute::Spread(expression));
+                    }
+                    _ => {}
+                },
+                &RSXCstChild::Node(ref element) => {
+                    value = RSXAttributeValue::Element(self.element(element)?);
+                }
+            }
+        }
+
+        match (node.kind, name) {
+            (RSXCstKind::Attribute, Some(name)) => Ok(RSXAttribute::Named(name, value)),
+            (RSXCstKind::Error, _) => Ok(RSXAttribute::Error(RSXErrorNode(node.span))),
+            _ => Err(rsx_cst_error(node.span.start))
+        }
+    }
+
+    // Whitespace tokens between children are text which is only insignificant
+    // whitespace, so they're skipped like the trivia they are.
+    fn children(&mut self, children: &[RSXCstChild]) -> Result<RSXChildren, RSXParseError> {
+        let mut lowered = vec![];
+        for child in children.iter() {
+            match child {
+                &RSXCstChild::Node(ref node) if node.kind == RSXCstKind::Error => {
+                    lowered.push(RSXChild::Error(RSXErrorNode(node.span)));
+                }
+                &RSXCstChild::Node(ref node) => {
+                    lowered.push(RSXChild::Element(self.element(node)?));
+                }
+                &RSXCstChild::Token(ref token) => match token.kind {
+                    RSXCstKind::Text => {
+                        let text = self.leaf(token, rsx_text)?;
+                        if !text.0.is_empty() {
+                            lowered.push(RSXChild::Text(text));
+                        }
+                    }
+                    RSXCstKind::CodeBlock => {
+                        lowered.push(RSXChild::CodeBlock(self.leaf(token, rsx_code_block)?));
+                    }
+                    RSXCstKind::Error => lowered.push(RSXChild::Error(RSXErrorNode(token.span))),
+                    _ => {}
+                }
+            }
+        }
+        Ok(RSXChildren::from(lowered))
+    }
+
+    // Leaf tokens have to be parsed whole by the parser which recorded them.
+    fn leaf<O, F>(&mut self, token: &RSXCstToken, parse: F) -> Result<O, RSXParseError>
+    where
+        F: FnMut(RSXSourceStream<'a>) -> ParseResult<O, RSXSourceStream<'a>>
+    {
+        let source: &'a str = self.source;
+        let source = &source[..token.span.end.offset];
+        let text = &source[token.span.start.offset..];
+        let input = RSXSourceStream::at(text, token.span.start, self.options);
+        match parser(parse).parse(input) {
+            Ok((_, ref rest)) if !rest.as_str().is_empty() => Err(rsx_trailing_input_error(rest)),
+            Ok((output, rest)) => {
+                self.table.extend(rest.span_table().0);
+                Ok(output)
+            }
+            Err(error) => Err(rsx_parse_error(source, error))
+        }
+    }
+}
+
+// Trees only fail to lower when they've been edited into something the
+// grammar can't have recorded, e.g. an element without a name.
+fn rsx_cst_error(position: RSXPosition) -> RSXParseError {
+    RSXParseError::new(RSXParseErrorKind::UnexpectedToken, position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use parse_rsx::rsx_element_ignoring_ws;
+
+    fn parse_cst(source: &str) -> RSXCstNode {
+        let (_, rest) = parser(rsx_element_ignoring_ws)
+            .parse(RSXSourceStream::new(source).recording_cst())
+            .unwrap();
+        rsx_cst_root(source, &rest.cst_entries())
+    }
+
+    #[test]
+    pub fn test_rsx_cst_round_trip() {
+        let sources = [
+            "<div/>",
+            "  <div  />  ",
+            "<div // comment\n  foo = \"bar\"   baz /* other */ >text  </ div >",
+            "<div {...props} a={1} b={ 'c' } c={<span/>}>\n  {foo}\n  <br/>\n</div>",
+            "<>  <a-b.c/>  </>",
+            "<Fragment>x</Fragment>",
+            "<a - b :c d='e' />",
+            "<a><b/> /* x */ {c} // y\n <i/></a>"
+        ];
+        for source in sources.iter() {
+            let cst = parse_cst(source);
+            assert_eq!(cst.to_string(), *source);
+            let errors = cst.tokens().into_iter().filter(|t| t.kind == RSXCstKind::Error).count();
+            assert_eq!(errors, 0);
+        }
+    }
+
+    #[test]
+    pub fn test_rsx_cst_structure() {
+        let cst = parse_cst("<div /* a */ foo='bar'>text</div>");
+        assert_eq!(cst.kind, RSXCstKind::Root);
+
+        let kinds = cst.tokens().iter().map(|t| t.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                RSXCstKind::Punctuation,
+                RSXCstKind::ElementName,
+                RSXCstKind::Whitespace,
+                RSXCstKind::Comment,
+                RSXCstKind::Whitespace,
+                RSXCstKind::AttributeName,
+                RSXCstKind::Punctuation,
+                RSXCstKind::AttributeValue,
+                RSXCstKind::Punctuation,
+                RSXCstKind::Text,
+                RSXCstKind::Punctuation,
+                RSXCstKind::ElementName,
+                RSXCstKind::Punctuation
+            ]
+        );
+
+        let attributes = cst.nodes()
+            .into_iter()
+            .filter(|n| n.kind == RSXCstKind::Attribute)
+            .collect::<Vec<_>>();
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes[0].to_string(), "foo='bar'");
+    }
+
+    #[test]
+    pub fn test_rsx_cst_lower() {
+        let source = "\n<div a = {1} {...b}>\n  x {y}<i/><pre> </pre>\n</div>";
+        let (element, rest) = parser(rsx_element_ignoring_ws)
+            .parse(RSXSourceStream::new(source).recording_cst())
+            .unwrap();
+        let cst = rsx_cst_root(source, &rest.cst_entries());
+        assert_eq!(cst.lower(), Ok((element, rest.span_table())));
+
+        let cst = RSXCstNode {
+            kind: RSXCstKind::Fragment,
+            span: RSXSpan::default(),
+            children: vec![
+                RSXCstChild::Token(RSXCstToken {
+                    kind: RSXCstKind::Error,
+                    span: RSXSpan::default(),
+                    text: "<".to_string()
+                }),
+            ]
+        };
+        let children = match cst.lower().unwrap().0 {
+            RSXElement::Fragment(children) => children,
+            _ => panic!("Expected a fragment")
+        };
+        let end = RSXPosition {
+            offset: 1,
+            line: 1,
+            column: 2
+        };
+        match children.0[0] {
+            RSXChild::Error(ref node) => {
+                assert_eq!(node.span(), RSXSpan::new(RSXPosition::start(), end))
+            }
+            _ => panic!("Expected an error child")
+        }
+    }
+}
@@ -0,0 +1,170 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::fmt;
+
+use parse_cst::rsx_cst_lower;
+use parse_elements_types::RSXElement;
+use parse_error_types::RSXParseError;
+use parse_options_types::RSXParseOptions;
+use parse_span_types::{RSXSpan, RSXSpanTable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RSXCstKind {
+    // Nodes
+    Root,
+    Element,
+    Fragment,
+    Attribute,
+    SpreadAttribute,
+    Error,
+
+    // Tokens
+    Whitespace,
+    Comment,
+    Punctuation,
+    ElementName,
+    AttributeName,
+    AttributeValue,
+    Text,
+    CodeBlock
+}
+
+impl RSXCstKind {
+    pub fn is_node(&self) -> bool {
+        match *self {
+            RSXCstKind::Root |
+            RSXCstKind::Element |
+            RSXCstKind::Fragment |
+            RSXCstKind::Attribute |
+            RSXCstKind::SpreadAttribute |
+            RSXCstKind::Error => true,
+            _ => false
+        }
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        match *self {
+            RSXCstKind::Whitespace | RSXCstKind::Comment => true,
+            _ => false
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RSXCstChild {
+    Node(RSXCstNode),
+    Token(RSXCstToken)
+}
+
+impl RSXCstChild {
+    pub fn kind(&self) -> RSXCstKind {
+        match self {
+            &RSXCstChild::Node(ref node) => node.kind,
+            &RSXCstChild::Token(ref token) => token.kind
+        }
+    }
+
+    pub fn span(&self) -> RSXSpan {
+        match self {
+            &RSXCstChild::Node(ref node) => node.span,
+            &RSXCstChild::Token(ref token) => token.span
+        }
+    }
+}
+
+impl fmt::Display for RSXCstChild {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &RSXCstChild::Node(ref node) => write!(f, "{}", node),
+            &RSXCstChild::Token(ref token) => write!(f, "{}", token)
+        }
+    }
+}
+
+// Concatenating the text of all the tokens in a node, in order, yields the
+// exact source the node was parsed from, including all whitespace and comments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSXCstNode {
+    pub kind: RSXCstKind,
+    pub span: RSXSpan,
+    pub children: Vec<RSXCstChild>
+}
+
+impl RSXCstNode {
+    pub fn tokens(&self) -> Vec<&RSXCstToken> {
+        let mut tokens = vec![];
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a RSXCstToken>) {
+        for child in &self.children {
+            match child {
+                &RSXCstChild::Node(ref node) => node.collect_tokens(tokens),
+                &RSXCstChild::Token(ref token) => tokens.push(token)
+            }
+        }
+    }
+
+    pub fn nodes(&self) -> Vec<&RSXCstNode> {
+        let mut nodes = vec![];
+        self.collect_nodes(&mut nodes);
+        nodes
+    }
+
+    fn collect_nodes<'a>(&'a self, nodes: &mut Vec<&'a RSXCstNode>) {
+        nodes.push(self);
+        for child in &self.children {
+            if let &RSXCstChild::Node(ref node) = child {
+                node.collect_nodes(nodes);
+            }
+        }
+    }
+
+    // Only meaningful for root, element and fragment nodes. Returns the AST
+    // along with its spans, which are counted from the start of this node, so
+    // for the root they're positions in the source.
+    pub fn lower(&self) -> Result<(RSXElement, RSXSpanTable), RSXParseError> {
+        self.lower_with_options(RSXParseOptions::default())
+    }
+
+    // Leaf tokens are parsed again with the given options, which should be the
+    // ones the node was parsed with.
+    pub fn lower_with_options(
+        &self,
+        options: RSXParseOptions
+    ) -> Result<(RSXElement, RSXSpanTable), RSXParseError> {
+        rsx_cst_lower(self, options)
+    }
+}
+
+impl fmt::Display for RSXCstNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            write!(f, "{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSXCstToken {
+    pub kind: RSXCstKind,
+    pub span: RSXSpan,
+    pub text: String
+}
+
+impl fmt::Display for RSXCstToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
@@ -9,12 +9,26 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
+use std::any::TypeId;
+
 use combine::{ParseResult, Parser, Stream};
-use combine::combinator::{env_parser, look_ahead, optional, parser, token, tokens, try, sep_by1};
+use combine::char::string;
+use combine::combinator::{
+    env_parser,
+    look_ahead,
+    optional,
+    parser,
+    token,
+    tokens,
+    try,
+    value,
+    sep_by1
+};
 use itertools::Itertools;
 
 use parse_attributes::rsx_attributes;
 use parse_children::rsx_children;
+use parse_children_types::RSXChildren;
 use parse_elements_types::{
     RSXClosingElement,
     RSXElement,
@@ -24,59 +38,170 @@ use parse_elements_types::{
     RSXOpeningElement,
     RSXSelfClosingElement
 };
-use parse_js::{js_identifier_part, js_identifier_start, js_whitespace};
+use parse_cst::{rsx_record_cst, rsx_whitespace};
+use parse_cst_types::RSXCstKind;
+use parse_error::rsx_error_hint;
+use parse_error_types::{RSXParseError, RSXParseErrorKind};
+use parse_js::{js_identifier_part, js_identifier_start};
 use parse_misc::{close_tag, closing_element_open_tag, open_tag, self_closing_element_close_tag};
+use parse_span::{rsx_position, rsx_record_span};
+use parse_span_types::{RSXSpan, RSXStream};
+use parse_whitespace::rsx_children_options;
 
 pub fn rsx_element<I>(input: I) -> ParseResult<RSXElement, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     choice!(
+        try(parser(rsx_fragment).map(RSXElement::Fragment)),
         try(parser(rsx_self_closing_element).map(RSXElement::SelfClosing)),
         parser(rsx_normal_element).map(RSXElement::Normal)
     ).parse_stream(input)
 }
 
+pub fn rsx_fragment<I>(input: I) -> ParseResult<RSXChildren, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = choice!(
+        try((
+            parser(rsx_fragment_opening_element),
+            optional(parser(rsx_children)),
+            parser(rsx_fragment_closing_element)
+        )),
+        (
+            parser(rsx_named_fragment_opening_element),
+            optional(parser(rsx_children)),
+            parser(rsx_named_fragment_closing_element)
+        )
+    ).map(|(_, c, _)| RSXChildren::from(c))
+        .parse_stream(input);
+    let result = rsx_record_cst(RSXCstKind::Fragment, start, result);
+    rsx_record_span(start, result)
+}
+
+pub fn rsx_fragment_opening_element<I>(input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    (parser(open_tag).skip(parser(rsx_whitespace)), parser(close_tag))
+        .with(value(()))
+        .parse_stream(input)
+}
+
+pub fn rsx_fragment_closing_element<I>(input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    (parser(closing_element_open_tag).skip(parser(rsx_whitespace)), parser(close_tag))
+        .with(value(()))
+        .parse_stream(input)
+}
+
+pub fn rsx_named_fragment_opening_element<I>(input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    (
+        parser(open_tag).skip(parser(rsx_whitespace)),
+        parser(rsx_fragment_name).skip(parser(rsx_whitespace)),
+        parser(close_tag)
+    ).with(value(()))
+        .parse_stream(input)
+}
+
+pub fn rsx_named_fragment_closing_element<I>(input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    (
+        parser(closing_element_open_tag).skip(parser(rsx_whitespace)),
+        parser(rsx_fragment_name).skip(parser(rsx_whitespace)),
+        parser(close_tag)
+    ).with(value(()))
+        .parse_stream(input)
+}
+
+pub fn rsx_fragment_name<I>(input: I) -> ParseResult<&'static str, I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let result = string("Fragment").parse_stream(input);
+    rsx_record_cst(RSXCstKind::ElementName, start, result)
+}
+
 pub fn rsx_element_open<I>(input: I) -> ParseResult<RSXElementName, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    parser(open_tag).skip(parser(js_whitespace)).with(parser(rsx_element_name)).parse_stream(input)
+    parser(open_tag).skip(parser(rsx_whitespace)).with(parser(rsx_element_name)).parse_stream(input)
 }
 
 pub fn rsx_self_closing_element<I>(input: I) -> ParseResult<RSXSelfClosingElement, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    (
-        parser(rsx_element_open).skip(parser(js_whitespace)),
-        optional(parser(rsx_attributes).skip(parser(js_whitespace))),
+    let start = input.source_position();
+    let result = (
+        parser(rsx_element_open).skip(parser(rsx_whitespace)),
+        optional(parser(rsx_attributes).skip(parser(rsx_whitespace))),
         parser(self_closing_element_close_tag)
     ).map(|(n, a, _)| RSXSelfClosingElement(n, a.into()))
-        .parse_stream(input)
+        .parse_stream(input);
+    let result = rsx_record_cst(RSXCstKind::Element, start, result);
+    rsx_record_span(start, result)
 }
 
+// The children's span is everything between the opening and closing tags,
+// even when there are no children.
 pub fn rsx_normal_element<I>(input: I) -> ParseResult<RSXNormalElement, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     look_ahead(parser(rsx_element_open)).parse_stream(input).and_then(|(name, consumed)| {
-        (
-            parser(rsx_opening_element).skip(parser(js_whitespace)),
-            optional(parser(rsx_children).skip(parser(js_whitespace))),
-            env_parser(&name, rsx_closing_element)
-        ).map(|(RSXOpeningElement(n, a), c, _)| RSXNormalElement(n, a, c.into()))
-            .parse_stream(consumed.into_inner())
+        let input = consumed.into_inner();
+        let start = input.source_position();
+        (parser(rsx_opening_element), parser(rsx_position))
+            .parse_stream(input)
+            .and_then(|((RSXOpeningElement(n, a), opening_end), consumed)| {
+                let opening = RSXSpan::new(start, opening_end);
+                consumed.combine(|mut input| {
+                    // Children may be parsed with different options than the
+                    // element itself, e.g. preserving whitespace inside `<pre>`.
+                    let options = input.options();
+                    input.set_options(rsx_children_options(&name, options));
+                    (
+                        optional(parser(rsx_children)),
+                        parser(rsx_position),
+                        env_parser((&name, opening), rsx_matching_closing_element),
+                        parser(rsx_position)
+                    ).parse_stream(input)
+                        .map(|((c, children_end, _, end), consumed)| {
+                            let consumed = consumed.map(|mut input: I| {
+                                input.set_options(options);
+                                let children = RSXSpan::new(opening_end, children_end);
+                                input.record_span(TypeId::of::<RSXChildren>(), children);
+                                let element = RSXSpan::new(start, end);
+                                input.record_span(TypeId::of::<RSXNormalElement>(), element);
+                                input.record_cst(RSXCstKind::Element, element);
+                                input
+                            });
+                            (RSXNormalElement(n, a, c.into()), consumed)
+                        })
+                })
+            })
     })
 }
 
 pub fn rsx_opening_element<I>(input: I) -> ParseResult<RSXOpeningElement, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     (
-        parser(rsx_element_open).skip(parser(js_whitespace)),
-        optional(parser(rsx_attributes).skip(parser(js_whitespace))),
+        parser(rsx_element_open).skip(parser(rsx_whitespace)),
+        optional(parser(rsx_attributes).skip(parser(rsx_whitespace))),
         parser(close_tag)
     ).map(|(n, a, _)| RSXOpeningElement(n, a.into()))
         .parse_stream(input)
@@ -84,33 +209,77 @@ where
 
 pub fn rsx_closing_element<I>(name: &RSXElementName, input: I) -> ParseResult<RSXClosingElement, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     let chars = name.to_string();
     let chars_ws = chars.split('-').join(" - ");
     let expected = chars.clone().into();
     let expected_ws = chars_ws.clone().into();
     let cmp_ignore_ws = |l: char, r: char| l == r || l.is_whitespace() && r.is_whitespace();
+    let (_, consumed) = parser(closing_element_open_tag)
+        .skip(parser(rsx_whitespace))
+        .parse_stream(input)?;
+    consumed.combine(|input| {
+        let start = input.source_position();
+        let result = choice!(
+            try(tokens(&cmp_ignore_ws, expected_ws, chars_ws.chars())),
+            tokens(&cmp_ignore_ws, expected, chars.chars())
+        ).parse_stream(input);
+        rsx_record_cst(RSXCstKind::ElementName, start, result)
+    }).and_then(|(_, consumed)| {
+        consumed.combine(|input| {
+            (parser(rsx_whitespace), parser(close_tag))
+                .map(|_| RSXClosingElement(name))
+                .parse_stream(input)
+        })
+    })
+}
+
+pub fn rsx_matching_closing_element<'a, I>(
+    (name, opening): (&'a RSXElementName, RSXSpan),
+    input: I
+) -> ParseResult<RSXClosingElement<'a>, I>
+where
+    I: RSXStream
+{
+    let position = input.source_position();
+    env_parser(name, rsx_closing_element).parse_stream(input.clone()).map_err(|error| {
+        match parser(rsx_closing_element_name).parse_stream(input) {
+            Ok((found, _)) => {
+                let kind = RSXParseErrorKind::MismatchedClosingTag(opening);
+                let hint = RSXParseError::new(kind, position)
+                    .with_expected(format!("</{}>", name))
+                    .with_found(format!("</{}>", found));
+                rsx_error_hint(error, hint)
+            }
+            Err(_) => error
+        }
+    })
+}
+
+pub fn rsx_closing_element_name<I>(input: I) -> ParseResult<RSXElementName, I>
+where
+    I: RSXStream
+{
     (
-        parser(closing_element_open_tag).skip(parser(js_whitespace)),
-        choice!(
-            try(tokens(&cmp_ignore_ws, expected_ws, chars_ws.chars()).skip(parser(js_whitespace))),
-            tokens(&cmp_ignore_ws, expected, chars.chars()).skip(parser(js_whitespace))
-        ),
+        parser(closing_element_open_tag).skip(parser(rsx_whitespace)),
+        parser(rsx_element_name).skip(parser(rsx_whitespace)),
         parser(close_tag)
-    ).map(|_| RSXClosingElement(name))
+    ).map(|(_, n, _)| n)
         .parse_stream(input)
 }
 
 pub fn rsx_element_name<I>(input: I) -> ParseResult<RSXElementName, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    choice!(
+    let start = input.source_position();
+    let result = choice!(
         try(parser(rsx_member_expression).map(RSXElementName::MemberExpression)),
         try(parser(rsx_namespaced_name).map(|(ns, n)| RSXElementName::NamedspacedName(ns, n))),
         parser(rsx_identifier).map(RSXElementName::Name)
-    ).parse_stream(input)
+    ).parse_stream(input);
+    rsx_record_cst(RSXCstKind::ElementName, start, result)
 }
 
 pub fn rsx_identifier_simple<I>(input: I) -> ParseResult<RSXIdentifier, I>
@@ -123,30 +292,38 @@ where
         .parse_stream(input)
 }
 
+// Dashes, dots and colons may be preceded by whitespace, and dashes followed by
+// it too, but whitespace after the last part isn't part of the name.
 pub fn rsx_identifier<I>(input: I) -> ParseResult<RSXIdentifier, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    sep_by1(
-        parser(rsx_identifier_simple).skip(parser(js_whitespace)),
-        token('-').skip(parser(js_whitespace))
-    ).parse_stream(input)
+    let start = input.source_position();
+    let result = sep_by1(
+        parser(rsx_identifier_simple),
+        try(parser(rsx_whitespace).with(token('-'))).skip(parser(rsx_whitespace))
+    ).parse_stream(input);
+    rsx_record_span(start, result)
 }
 
 pub fn rsx_namespaced_name<I>(input: I) -> ParseResult<(RSXIdentifier, RSXIdentifier), I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    (parser(rsx_identifier), token(':'), parser(rsx_identifier))
-        .map(|(l, _, r)| (l, r))
+    (
+        parser(rsx_identifier),
+        try(parser(rsx_whitespace).with(token(':'))),
+        parser(rsx_identifier)
+    ).map(|(l, _, r)| (l, r))
         .parse_stream(input)
 }
 
 pub fn rsx_member_expression<I>(input: I) -> ParseResult<Box<[RSXIdentifier]>, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    (parser(rsx_identifier), token('.'), sep_by1(parser(rsx_identifier), token('.')))
+    let dot = || try(parser(rsx_whitespace).with(token('.')));
+    (parser(rsx_identifier), dot(), sep_by1(parser(rsx_identifier), dot()))
         .map(|(i, _, mut v): (_, _, Vec<_>)| {
             v.insert(0, i);
             v.into_boxed_slice()
@@ -190,22 +367,40 @@ mod tests {
             .0;
 
         let tokens = quote! {
-            DOMNode::from((
-                DOMTagName::from("root"),
-                DOMChildren::from(vec![
-                    DOMNode::from(DOMTagName::from("foo")),
-                    DOMNode::from(DOMTagName::from(box ["foo", "member", "bar",])),
-                    DOMNode::from(DOMTagName::from("foo-bar")),
-                    DOMNode::from(DOMTagName::from("foo-bar")),
-                    DOMNode::from(DOMTagName::from(box ["foo-bar", "member",])),
-                    DOMNode::from(DOMTagName::from(box ["foo-bar", "member",])),
-                    DOMNode::from(DOMTagName::from(box ["foo-bar", "member", "bar-baz",])),
-                    DOMNode::from(DOMTagName::from(box ["foo-bar", "member", "bar-baz",])),
-                    DOMNode::from(DOMTagName::from(("foo", "bar"))),
-                    DOMNode::from(DOMTagName::from(("foo-a", "bar-b"))),
-                    DOMNode::from(DOMTagName::from(("foo-a", "bar-b"))),
-                    DOMNode::from(DOMTagName::from("foo")),
-                    DOMNode::from(DOMTagName::from("x-foo-bar")),
+            ::rsx_dom::types::DOMNode::from((
+                ::rsx_dom::types::DOMTagName::from("root"),
+                ::rsx_dom::types::DOMChildren::from(vec![
+                    ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("foo")),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from(box ["foo", "member", "bar",])
+                    ),
+                    ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("foo-bar")),
+                    ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("foo-bar")),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from(box ["foo-bar", "member",])
+                    ),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from(box ["foo-bar", "member",])
+                    ),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from(box ["foo-bar", "member", "bar-baz",])
+                    ),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from(box ["foo-bar", "member", "bar-baz",])
+                    ),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from(("foo", "bar"))
+                    ),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from(("foo-a", "bar-b"))
+                    ),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from(("foo-a", "bar-b"))
+                    ),
+                    ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("foo")),
+                    ::rsx_dom::types::DOMNode::from(
+                        ::rsx_dom::types::DOMTagName::from("x-foo-bar")
+                    ),
                 ])
             ))
         };
@@ -275,6 +470,139 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_rsx_fragment_tokenize() {
+        let value = parser(rsx_element)
+            .parse(
+                r#"<>
+                     <foo/>
+                     <Fragment><bar/></Fragment>
+                     { <><baz/></> }
+                     <qux value=<></>/>
+                   </>
+                "#
+            )
+            .unwrap()
+            .0;
+
+        let tokens = quote! {
+            ::rsx_dom::types::DOMNode::from((
+                ::rsx_dom::types::DOMTagName::from(::rsx_dom::types::KnownElementName::Fragment),
+                ::rsx_dom::types::DOMChildren::from(vec![
+                    ::rsx_dom::types::DOMNode::from(::rsx_dom::types::DOMTagName::from("foo")),
+                    ::rsx_dom::types::DOMNode::from((
+                        ::rsx_dom::types::DOMTagName::from(
+                            ::rsx_dom::types::KnownElementName::Fragment
+                        ),
+                        ::rsx_dom::types::DOMChildren::from(
+                            vec![
+                                ::rsx_dom::types::DOMNode::from(
+                                    ::rsx_dom::types::DOMTagName::from("bar")
+                                ),
+                            ]
+                        )
+                    )),
+                    ::rsx_dom::types::DOMNode::from({
+                        ::rsx_dom::types::DOMNode::from((
+                            ::rsx_dom::types::DOMTagName::from(
+                                ::rsx_dom::types::KnownElementName::Fragment
+                            ),
+                            ::rsx_dom::types::DOMChildren::from(
+                                vec![
+                                    ::rsx_dom::types::DOMNode::from(
+                                        ::rsx_dom::types::DOMTagName::from("baz")
+                                    ),
+                                ]
+                            )
+                        ))
+                    }),
+                    ::rsx_dom::types::DOMNode::from((
+                        ::rsx_dom::types::DOMTagName::from("qux"),
+                        ::rsx_dom::types::DOMAttributes::from(vec![
+                            ::rsx_dom::types::DOMAttribute::from((
+                                ::rsx_dom::types::DOMAttributeName::from("value"),
+                                ::rsx_dom::types::DOMAttributeValue::from(
+                                    ::rsx_dom::types::DOMNode::from(
+                                        ::rsx_dom::types::DOMTagName::from(
+                                            ::rsx_dom::types::KnownElementName::Fragment
+                                        )
+                                    )
+                                )
+                            )),
+                        ])
+                    )),
+                ])
+            ))
+        };
+
+        println!("{}", quote! { #value }.as_str());
+        assert_eq!(syn::parse_expr(quote! { #value }.as_str()), syn::parse_expr(tokens.as_str()));
+    }
+
+    #[test]
+    pub fn test_rsx_fragment() {
+        assert_eq!(parser(rsx_fragment).parse("").is_err(), true);
+        assert_eq!(parser(rsx_fragment).parse(" ").is_err(), true);
+        assert_eq!(parser(rsx_fragment).parse("<>").is_err(), true);
+        assert_eq!(parser(rsx_fragment).parse("</>").is_err(), true);
+        assert_eq!(parser(rsx_fragment).parse("<foo></foo>").is_err(), true);
+        assert_eq!(parser(rsx_fragment).parse("<Fragment bar></Fragment>").is_err(), true);
+        assert_eq!(parser(rsx_fragment).parse("<></Fragment>").is_err(), true);
+        assert_eq!(parser(rsx_fragment).parse("<Fragment></>").is_err(), true);
+
+        assert_eq!(parser(rsx_fragment).parse("<></>").unwrap(), (RSXChildren::from(vec![]), ""));
+        assert_eq!(parser(rsx_fragment).parse("< ></ >").unwrap(), (RSXChildren::from(vec![]), ""));
+        assert_eq!(
+            parser(rsx_fragment).parse("<Fragment></Fragment>").unwrap(),
+            (RSXChildren::from(vec![]), "")
+        );
+        assert_eq!(
+            parser(rsx_fragment).parse("<>hello<foo/></>").unwrap(),
+            (
+                RSXChildren::from(vec![
+                    RSXChild::Text(RSXText("hello".into())),
+                    RSXChild::Element(RSXElement::SelfClosing("foo".into())),
+                ]),
+                ""
+            )
+        );
+        assert_eq!(
+            parser(rsx_fragment).parse("< Fragment >hello<foo/></ Fragment >").unwrap(),
+            (
+                RSXChildren::from(vec![
+                    RSXChild::Text(RSXText("hello".into())),
+                    RSXChild::Element(RSXElement::SelfClosing("foo".into())),
+                ]),
+                ""
+            )
+        );
+
+        assert_eq!(
+            parser(rsx_element).parse("<><></></>").unwrap(),
+            (
+                RSXElement::Fragment(RSXChildren::from(vec![
+                    RSXChild::Element(RSXElement::Fragment(RSXChildren::from(vec![]))),
+                ])),
+                ""
+            )
+        );
+        assert_eq!(
+            parser(rsx_element).parse("<Fragments></Fragments>").unwrap(),
+            (RSXElement::Normal("Fragments".into()), "")
+        );
+        assert_eq!(
+            parser(rsx_element).parse("<Fragment key='foo'></Fragment>").unwrap(),
+            (
+                RSXElement::Normal(RSXNormalElement(
+                    RSXElementName::Name("Fragment".into()),
+                    RSXAttributes::from(vec![("key", "foo").into()]),
+                    RSXChildren::from(vec![])
+                )),
+                ""
+            )
+        );
+    }
+
     #[test]
     pub fn test_rsx_normal_element() {
         assert_eq!(parser(rsx_normal_element).parse("").is_err(), true);
@@ -19,19 +19,48 @@ use self_tokenize_trait::ToCustomTokens;
 
 use parse_attributes_types::RSXAttributes;
 use parse_children_types::RSXChildren;
+use parse_error_types::RSXErrorNode;
+use parse_span_types::{RSXSpan, RSXSpans};
 
 #[derive(Debug, PartialEq, DefaultQuote)]
 pub enum RSXElement {
     SelfClosing(RSXSelfClosingElement),
-    Normal(RSXNormalElement)
+    Normal(RSXNormalElement),
+    Fragment(RSXChildren),
+    Error(RSXErrorNode)
+}
+
+impl RSXElement {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        match self {
+            &RSXElement::SelfClosing(ref element) => element.span(spans),
+            &RSXElement::Normal(ref element) => element.span(spans),
+            // Fragments don't have an element of their own, so their children
+            // have the span of the whole `<>...</>` construct.
+            &RSXElement::Fragment(ref children) => children.span(spans),
+            &RSXElement::Error(ref error) => error.span()
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, DefaultQuote)]
 pub struct RSXSelfClosingElement(pub RSXElementName, pub RSXAttributes);
 
+impl RSXSelfClosingElement {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+}
+
 #[derive(Debug, PartialEq, DefaultQuote)]
 pub struct RSXNormalElement(pub RSXElementName, pub RSXAttributes, pub RSXChildren);
 
+impl RSXNormalElement {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct RSXOpeningElement(pub RSXElementName, pub RSXAttributes);
 
@@ -46,6 +75,20 @@ pub enum RSXElementName {
     MemberExpression(Box<[RSXIdentifier]>)
 }
 
+impl RSXElementName {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        match self {
+            &RSXElementName::Name(ref n) => n.span(spans),
+            &RSXElementName::NamedspacedName(ref ns, ref n) => ns.span(spans).join(&n.span(spans)),
+            &RSXElementName::MemberExpression(ref e) => match (e.first(), e.last()) {
+                (Some(first), Some(last)) => first.span(spans).join(&last.span(spans)),
+                _ => RSXSpan::default()
+            },
+            &RSXElementName::KnownName(_) => RSXSpan::default()
+        }
+    }
+}
+
 impl fmt::Display for RSXElementName {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::RSXElementName::*;
@@ -53,16 +96,102 @@ impl fmt::Display for RSXElementName {
             &Name(ref n) => write!(f, "{}", n.0),
             &NamedspacedName(ref ns, ref n) => write!(f, "{}:{}", ns.0, n.0),
             &MemberExpression(ref e) => write!(f, "{}", e.iter().map(|v| &v.0).join(".")),
-            &KnownName(_) => {
-                panic!("Unsupported operation: Known names only created during tokenization")
-            }
+            &KnownName(ref n) => write!(f, "{}", known_element_name(n))
         }
     }
 }
 
+#[allow(unknown_lints, cyclomatic_complexity)]
+fn known_element_name(name: &KnownElementName) -> &'static str {
+    use self::KnownElementName::*;
+    match name {
+        &Address => "address",
+        &Article => "article",
+        &Aside => "aside",
+        &Footer => "footer",
+        &Header => "header",
+        &Nav => "nav",
+        &Section => "section",
+        &Hgroup => "hgroup",
+        &H1 => "h1",
+        &H2 => "h2",
+        &H3 => "h3",
+        &H4 => "h4",
+        &H5 => "h5",
+        &H6 => "h6",
+        &Main => "main",
+        &Div => "div",
+        &Span => "span",
+        &P => "p",
+        &Ol => "ol",
+        &Ul => "ul",
+        &Li => "li",
+        &Dl => "dl",
+        &Dt => "dt",
+        &Dd => "dd",
+        &Figure => "figure",
+        &Figcaption => "figcaption",
+        &Hr => "hr",
+        &Pre => "pre",
+        &Blockquote => "blockquote",
+        &A => "a",
+        &Bold => "b",
+        &Italic => "i",
+        &Underline => "u",
+        &Strikethrough => "s",
+        &Emphasis => "em",
+        &Mark => "mark",
+        &Quotation => "q",
+        &Citation => "cite",
+        &Code => "code",
+        &Data => "data",
+        &Time => "time",
+        &Sub => "sub",
+        &Sup => "sup",
+        &Br => "br",
+        &Wbr => "wbr",
+        &Image => "img",
+        &Area => "area",
+        &Map => "map",
+        &Audio => "audio",
+        &Video => "video",
+        &Track => "track",
+        &Button => "button",
+        &Datalist => "datalist",
+        &Fieldset => "fieldset",
+        &Form => "form",
+        &Input => "input",
+        &Label => "label",
+        &Legend => "legend",
+        &Meter => "meter",
+        &Optgroup => "optgroup",
+        &Option => "option",
+        &Output => "output",
+        &Progress => "progress",
+        &Select => "select",
+        &Textarea => "textarea",
+        &Fragment => "Fragment",
+        &View => "View",
+        &Text => "Text",
+        &TextInput => "TextInput",
+        &ScrollView => "ScrollView",
+        &Picker => "Picker",
+        &Slider => "Slider",
+        &Switch => "Switch",
+        &FlatList => "FlatList",
+        &SectionList => "SectionList"
+    }
+}
+
 #[derive(Debug, PartialEq, DefaultQuote)]
 pub struct RSXIdentifier(pub String);
 
+impl RSXIdentifier {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+}
+
 impl FromIterator<RSXIdentifier> for RSXIdentifier {
     fn from_iter<I: IntoIterator<Item = RSXIdentifier>>(iter: I) -> Self {
         RSXIdentifier(iter.into_iter().map(|v| v.0).join("-"))
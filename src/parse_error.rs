@@ -0,0 +1,91 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use combine::ParseError;
+use combine::primitives::{Consumed, Error};
+use itertools::Itertools;
+
+use parse_error_types::{RSXParseError, RSXParseErrorKind};
+use parse_span_types::{RSXSourceStream, RSXStream};
+
+// Parsers can't return anything but combine errors, so the more specific
+// errors are smuggled through as `Error::Other` hints, and picked up again
+// when converting the final error. Inner hints are added first, and win.
+pub fn rsx_error_hint<I>(
+    error: Consumed<ParseError<I>>,
+    hint: RSXParseError
+) -> Consumed<ParseError<I>>
+where
+    I: RSXStream
+{
+    error.map(|mut error| {
+        error.add_error(Error::Other(Box::new(hint)));
+        error
+    })
+}
+
+pub fn rsx_parse_error<'a>(
+    source: &'a str,
+    error: ParseError<RSXSourceStream<'a>>
+) -> RSXParseError {
+    let mut expected = vec![];
+    let mut found = None;
+    let mut hint = None;
+
+    for e in &error.errors {
+        match e {
+            &Error::Expected(ref info) => expected.push(info.to_string()),
+            &Error::Unexpected(ref info) => found = found.or_else(|| Some(info.to_string())),
+            &Error::Other(ref other) => {
+                hint = hint.or_else(|| other.downcast_ref::<RSXParseError>().cloned())
+            }
+            &Error::Message(_) => {}
+        }
+    }
+
+    let expected = expected.into_iter().unique().collect::<Vec<_>>();
+    let found = found.unwrap_or_else(|| match source[error.position.offset..].chars().next() {
+        Some(c) => c.to_string(),
+        None => "end of input".to_string()
+    });
+
+    match hint {
+        Some(mut hint) => {
+            if hint.expected.is_empty() {
+                hint.expected = expected;
+            }
+            if hint.found.is_empty() {
+                hint.found = found;
+            }
+            hint
+        }
+        None => {
+            let kind = if error.position.offset >= source.len() {
+                RSXParseErrorKind::UnexpectedEof
+            } else {
+                RSXParseErrorKind::UnexpectedToken
+            };
+            RSXParseError {
+                kind,
+                position: error.position,
+                expected,
+                found
+            }
+        }
+    }
+}
+
+pub fn rsx_trailing_input_error(remaining: &RSXSourceStream) -> RSXParseError {
+    let found = remaining.as_str().lines().next().unwrap_or_default().trim_right();
+    RSXParseError::new(RSXParseErrorKind::TrailingInput, remaining.source_position())
+        .with_expected("end of input")
+        .with_found(found)
+}
@@ -0,0 +1,156 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::error::Error;
+use std::fmt;
+
+use itertools::Itertools;
+use self_tokenize_macro::DefaultQuote;
+use self_tokenize_trait::ToCustomTokens;
+
+use parse_span_types::{RSXPosition, RSXSpan, RSXSpanMismatch};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RSXParseErrorKind {
+    // Carries the span of the opening tag which wasn't closed properly.
+    MismatchedClosingTag(RSXSpan),
+    UnterminatedString,
+    UnbalancedCodeBlock,
+    UnexpectedEof,
+    InvalidAttributeValue,
+    InvalidEscape,
+    // Reported for code blocks that aren't made of Rust tokens, and for ones
+    // that aren't Rust expressions when validating them.
+    InvalidExpression,
+    // Reported for HTML tag and attribute names which can't be written in RSX
+    // when importing HTML.
+    InvalidName,
+    TrailingInput,
+    UnexpectedToken,
+    // Reported when the spans recorded while parsing don't match the tree,
+    // which has no position to point at.
+    SpanMismatch
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSXParseError {
+    pub kind: RSXParseErrorKind,
+    pub position: RSXPosition,
+    pub expected: Vec<String>,
+    pub found: String
+}
+
+impl RSXParseError {
+    pub fn new(kind: RSXParseErrorKind, position: RSXPosition) -> Self {
+        RSXParseError {
+            kind,
+            position,
+            expected: vec![],
+            found: String::new()
+        }
+    }
+
+    pub fn with_expected<S>(mut self, expected: S) -> Self
+    where
+        S: Into<String>
+    {
+        self.expected.push(expected.into());
+        self
+    }
+
+    pub fn with_found<S>(mut self, found: S) -> Self
+    where
+        S: Into<String>
+    {
+        self.found = found.into();
+        self
+    }
+
+    pub fn line(&self) -> usize {
+        self.position.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.position.column
+    }
+
+    pub fn opening_span(&self) -> Option<RSXSpan> {
+        match self.kind {
+            RSXParseErrorKind::MismatchedClosingTag(span) => Some(span),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for RSXParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.description())?;
+        if let Some(span) = self.opening_span() {
+            write!(f, " (opening tag at {})", span.start)?;
+        }
+        if !self.expected.is_empty() {
+            let expected = self.expected.iter().map(|e| format!("`{}`", e)).join(" or ");
+            write!(f, ", expected {}", expected)?;
+        }
+        if !self.found.is_empty() {
+            write!(f, ", found `{}`", self.found)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for RSXParseError {
+    fn description(&self) -> &str {
+        match self.kind {
+            RSXParseErrorKind::MismatchedClosingTag(_) => "mismatched closing tag",
+            RSXParseErrorKind::UnterminatedString => "unterminated string",
+            RSXParseErrorKind::UnbalancedCodeBlock => "unbalanced code block",
+            RSXParseErrorKind::UnexpectedEof => "unexpected end of input",
+            RSXParseErrorKind::InvalidAttributeValue => "invalid attribute value",
+            RSXParseErrorKind::InvalidEscape => "invalid escape sequence",
+            RSXParseErrorKind::InvalidExpression => "invalid Rust expression",
+            RSXParseErrorKind::InvalidName => "invalid name",
+            RSXParseErrorKind::TrailingInput => "unexpected trailing input",
+            RSXParseErrorKind::UnexpectedToken => "unexpected token",
+            RSXParseErrorKind::SpanMismatch => "span table doesn't match the tree"
+        }
+    }
+}
+
+impl From<RSXSpanMismatch> for RSXParseError {
+    fn from(_: RSXSpanMismatch) -> Self {
+        RSXParseError::new(RSXParseErrorKind::SpanMismatch, RSXPosition::default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSXDiagnostic {
+    pub error: RSXParseError,
+    pub span: RSXSpan
+}
+
+impl fmt::Display for RSXDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+// Placeholder for source that couldn't be parsed, created when parsing in
+// recovering mode or lowering CST error nodes. Its span covers the skipped
+// source.
+#[derive(Debug, Clone, Copy, PartialEq, DefaultQuote)]
+pub struct RSXErrorNode(pub RSXSpan);
+
+impl RSXErrorNode {
+    pub fn span(&self) -> RSXSpan {
+        self.0
+    }
+}
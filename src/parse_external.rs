@@ -11,11 +11,45 @@ specific language governing permissions and limitations under the License.
 
 use combine::{ParseResult, Parser, Stream};
 use combine::char::string;
-use combine::combinator::{between, many, none_of, optional, parser, token, try, value, many1};
+use combine::combinator::{
+    between,
+    env_parser,
+    look_ahead,
+    many,
+    none_of,
+    optional,
+    parser,
+    token,
+    try,
+    value,
+    many1
+};
+use syn::{self, DelimToken, Delimited, Token, TokenTree};
 
 use parse_elements::rsx_element;
-use parse_external_types::{RSXParsedExpression, RSXRawCodeFragment};
-use parse_rust::{rs_char, rs_comment, rs_string, rs_whitespace};
+use parse_error::rsx_error_hint;
+use parse_error_types::{RSXParseError, RSXParseErrorKind};
+use parse_external_types::{
+    RSXCodeGap,
+    RSXCodeTokenTree,
+    RSXParsedExpression,
+    RSXRawCode,
+    RSXRawCodeFragment
+};
+use parse_options_types::RSXParseOptions;
+use parse_rust::{
+    rs_byte_char,
+    rs_byte_string,
+    rs_c_string,
+    rs_char,
+    rs_comment,
+    rs_lifetime,
+    rs_raw_string,
+    rs_string,
+    rs_whitespace
+};
+use parse_span::rsx_record_span;
+use parse_span_types::{RSXPosition, RSXStream};
 
 pub fn rsx_code_block_begin<I>(input: I) -> ParseResult<(), I>
 where
@@ -31,43 +65,223 @@ where
     token('}').with(value(())).parse_stream(input)
 }
 
+pub fn rsx_code_block_end_matching<I>(start: RSXPosition, input: I) -> ParseResult<(), I>
+where
+    I: RSXStream
+{
+    parser(rsx_code_block_end).parse_stream(input).map_err(|error| {
+        let hint = RSXParseError::new(RSXParseErrorKind::UnbalancedCodeBlock, start);
+        rsx_error_hint(error, hint.with_expected("}"))
+    })
+}
+
 pub fn rsx_code_block<I>(input: I) -> ParseResult<RSXParsedExpression, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
+{
+    let start = input.source_position();
+    let options = input.options();
+    let result = parser(rsx_code_block_contents)
+        .and_then(move |raw| rsx_parsed_expression(raw, start, options))
+        .parse_stream(input);
+    rsx_record_span(start, result)
+}
+
+// Nested code blocks are merged into the enclosing code, so they don't get a
+// span of their own.
+pub fn rsx_code_block_contents<I>(input: I) -> ParseResult<RSXRawCode, I>
+where
+    I: RSXStream
 {
+    let start = input.source_position();
     between(
         parser(rsx_code_block_begin),
-        parser(rsx_code_block_end),
+        env_parser(start, rsx_code_block_end_matching),
         many(parser(rsx_code_block_fragment))
     ).parse_stream(input)
 }
 
 pub fn rsx_spread_code_block<I>(input: I) -> ParseResult<RSXParsedExpression, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    between(
+    let start = input.source_position();
+    let options = input.options();
+    let result = between(
         parser(rsx_code_block_begin),
-        parser(rsx_code_block_end),
+        env_parser(start, rsx_code_block_end_matching),
         (optional(parser(rs_whitespace)), string("..."))
             .with(many1(parser(rsx_code_block_fragment)))
-    ).parse_stream(input)
+    ).and_then(move |raw| rsx_parsed_expression(raw, start, options))
+        .parse_stream(input);
+    rsx_record_span(start, result)
+}
+
+fn rsx_parsed_expression(
+    raw: RSXRawCode,
+    start: RSXPosition,
+    options: RSXParseOptions
+) -> Result<RSXParsedExpression, RSXParseError> {
+    let token_trees = if options.lex_code_blocks {
+        rsx_lex_code(&raw.lexable, &raw.gaps).map_err(|message| {
+            RSXParseError::new(RSXParseErrorKind::InvalidExpression, start).with_found(message)
+        })?
+    } else {
+        vec![]
+    };
+    Ok(RSXParsedExpression {
+        token_trees,
+        elements: raw.elements,
+        code: raw.code
+    })
+}
+
+// Lexes code split around gaps into Rust token trees, with a hole for every
+// element and a literal for every literal syn can't lex. The pieces are joined
+// with an identifier that appears in none of them, which is then swapped for
+// the gaps in order.
+pub fn rsx_lex_code(
+    code: &[String],
+    gaps: &[RSXCodeGap]
+) -> Result<Vec<RSXCodeTokenTree>, String> {
+    let mut hole = "__rsx_hole".to_string();
+    while code.iter().any(|piece| piece.contains(hole.as_str())) {
+        hole.push('_');
+    }
+    let joined = code.join(format!(" {} ", hole).as_str());
+    let mut gaps = gaps.iter();
+    let mut index = 0;
+    syn::parse_token_trees(&joined)
+        .map(|trees| rsx_code_token_trees(trees, &hole, &mut gaps, &mut index))
+}
+
+fn rsx_code_token_trees(
+    trees: Vec<TokenTree>,
+    hole: &str,
+    gaps: &mut Iterator<Item = &RSXCodeGap>,
+    index: &mut usize
+) -> Vec<RSXCodeTokenTree> {
+    trees
+        .into_iter()
+        .map(|tree| match tree {
+            TokenTree::Token(Token::Ident(ref ident)) if ident.as_ref() == hole => {
+                match gaps.next() {
+                    Some(&RSXCodeGap::Literal(ref literal)) => {
+                        RSXCodeTokenTree::Literal(literal.clone())
+                    }
+                    _ => {
+                        *index += 1;
+                        RSXCodeTokenTree::Hole(*index - 1)
+                    }
+                }
+            }
+            TokenTree::Token(token) => RSXCodeTokenTree::Token(token),
+            TokenTree::Delimited(Delimited { delim, tts }) => {
+                RSXCodeTokenTree::Delimited(delim, rsx_code_token_trees(tts, hole, gaps, index))
+            }
+        })
+        .collect()
+}
+
+// Code is analyzed by parsing it with syn, so every element is swapped for an
+// identifier made of the element's index and a prefix that appears nowhere
+// else in the code. When the code has literals syn can't lex, it's rebuilt
+// from its token trees with an empty string for each of them instead.
+// Returns the code and the prefix.
+pub fn rsx_code_with_holes(expression: &RSXParsedExpression) -> (String, String) {
+    let mut prefix = "__rsx_hole_".to_string();
+    while expression.code.iter().any(|piece| piece.contains(prefix.as_str())) {
+        prefix.push('_');
+    }
+
+    if !rsx_code_has_literals(&expression.token_trees) {
+        let mut index = 0;
+        let code = expression.replace_elements(|_| {
+            let hole = format!(" {}{} ", prefix, index);
+            index += 1;
+            hole
+        });
+        return (code, prefix);
+    }
+
+    let mut code = String::new();
+    rsx_code_with_holes_from(&expression.token_trees, &prefix, &mut code);
+    (code, prefix)
+}
+
+fn rsx_code_has_literals(trees: &[RSXCodeTokenTree]) -> bool {
+    trees.iter().any(|tree| match tree {
+        &RSXCodeTokenTree::Literal(_) => true,
+        &RSXCodeTokenTree::Delimited(_, ref trees) => rsx_code_has_literals(trees),
+        _ => false
+    })
+}
+
+fn rsx_code_with_holes_from(trees: &[RSXCodeTokenTree], prefix: &str, code: &mut String) {
+    for tree in trees {
+        match tree {
+            &RSXCodeTokenTree::Token(Token::DocComment(ref doc)) => {
+                code.push_str(&format!("{}\n", doc));
+            }
+            &RSXCodeTokenTree::Token(ref token) => {
+                let tree = TokenTree::Token(token.clone());
+                code.push_str(quote! { #tree }.as_str());
+                code.push(' ');
+            }
+            &RSXCodeTokenTree::Delimited(ref delim, ref trees) => {
+                let (open, close) = match delim {
+                    &DelimToken::Paren => ("(", ")"),
+                    &DelimToken::Bracket => ("[", "]"),
+                    &DelimToken::Brace => ("{", "}")
+                };
+                code.push_str(open);
+                rsx_code_with_holes_from(trees, prefix, code);
+                code.push_str(close);
+            }
+            &RSXCodeTokenTree::Literal(_) => code.push_str(" \"\" "),
+            &RSXCodeTokenTree::Hole(index) => code.push_str(&format!(" {}{} ", prefix, index))
+        }
+    }
 }
 
 pub fn rsx_code_block_fragment<I>(input: I) -> ParseResult<RSXRawCodeFragment, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
     choice!(
-        try(parser(rsx_code_block).map(RSXRawCodeFragment::ParsedExpression)),
+        try(parser(rsx_code_block_contents).map(RSXRawCodeFragment::Block)),
         try(parser(rsx_element).map(RSXRawCodeFragment::Element)),
-        try(parser(rs_comment).map(|_| RSXRawCodeFragment::Empty)),
-        try(parser(rs_char).map(|v| RSXRawCodeFragment::Tokens(format!("'{}'", v.0)))),
-        try(parser(rs_string).map(|v| RSXRawCodeFragment::Tokens(format!("\"{}\"", v.0)))),
+        try(parser(rsx_code_block_comment)),
+        // Lifetimes and literals are parsed whole, so that quotes and braces inside
+        // them don't affect where the block ends.
+        try(parser(rs_lifetime).map(|v| RSXRawCodeFragment::Tokens(v.to_string()))),
+        try(parser(rs_char).map(|v| RSXRawCodeFragment::Tokens(v.to_string()))),
+        try(parser(rs_string).map(|v| RSXRawCodeFragment::Tokens(v.to_string()))),
+        try(parser(rs_raw_string).map(|v| RSXRawCodeFragment::Tokens(v.to_string()))),
+        try(parser(rs_byte_char).map(|v| RSXRawCodeFragment::Tokens(v.to_string()))),
+        try(parser(rs_byte_string).map(|v| RSXRawCodeFragment::Tokens(v.to_string()))),
+        // syn can't lex C strings, so they're kept whole as opaque literals.
+        try(parser(rs_c_string).map(|v| RSXRawCodeFragment::Literal(v.to_string()))),
         none_of("}".chars()).map(RSXRawCodeFragment::Token)
     ).parse_stream(input)
 }
 
+// Doc comments are kept when they're attached to something, since they're
+// attributes of the item following them. Any other comment is dropped.
+pub fn rsx_code_block_comment<I>(input: I) -> ParseResult<RSXRawCodeFragment, I>
+where
+    I: Stream<Item = char>
+{
+    (
+        parser(rs_comment),
+        look_ahead(parser(rs_whitespace).with(optional(parser(rsx_code_block_end))))
+    ).map(|(comment, end)| match (comment.is_doc(), end) {
+        (true, None) => RSXRawCodeFragment::Tokens(comment.to_string()),
+        _ => RSXRawCodeFragment::Empty
+    })
+        .parse_stream(input)
+}
+
 #[cfg(test)]
 mod tests {
     extern crate syn;
@@ -77,8 +291,8 @@ mod tests {
     use parse_attributes_types::RSXAttributes;
     use parse_children_types::{RSXChild, RSXChildren};
     use parse_elements_types::{RSXElement, RSXElementName, RSXNormalElement, RSXSelfClosingElement};
-    use parse_external_placeholders::RSXElementPlaceholder;
-    use parse_external_types::RSXParsedExpression;
+    use parse_external_types::{RSXCodeTokenTree, RSXParsedExpression};
+    use parse_span_types::RSXSourceStream;
 
     #[test]
     pub fn test_rsx_code_block_tokenize() {
@@ -119,37 +333,53 @@ mod tests {
         let tokens = quote! {
             {
                 if foo {
-                    DOMNode::from((
-                        DOMTagName::from("bar"),
-                        vec![DOMAttribute::from({ props }),],
+                    ::rsx_dom::types::DOMNode::from((
+                        ::rsx_dom::types::DOMTagName::from("bar"),
+                        vec![::rsx_dom::types::DOMAttribute::from({ props }),],
                         vec![
-                            DOMNode::from("hello"),
-                            DOMNode::from({ name(DOMNode::from(DOMTagName::from("world"))) }),
+                            ::rsx_dom::types::DOMNode::from("hello"),
+                            ::rsx_dom::types::DOMNode::from({
+                                name(
+                                    ::rsx_dom::types::DOMNode::from(
+                                        ::rsx_dom::types::DOMTagName::from("world")
+                                    )
+                                )
+                            }),
                         ]
                     ))
                 } else {
-                    DOMNode::from((
-                        DOMTagName::from("baz"),
-                        vec![DOMAttribute::from({ props }),],
+                    ::rsx_dom::types::DOMNode::from((
+                        ::rsx_dom::types::DOMTagName::from("baz"),
+                        vec![::rsx_dom::types::DOMAttribute::from({ props }),],
                         vec![
-                            DOMNode::from("goodbye"),
-                            DOMNode::from({ name(DOMNode::from(DOMTagName::from("blue-sky"))) }),
+                            ::rsx_dom::types::DOMNode::from("goodbye"),
+                            ::rsx_dom::types::DOMNode::from({
+                                name(
+                                    ::rsx_dom::types::DOMNode::from(
+                                        ::rsx_dom::types::DOMTagName::from("blue-sky")
+                                    )
+                                )
+                            }),
                         ]
                     ))
                 }
-                DOMNode::from((
-                    DOMTagName::from("what"),
-                    DOMAttributes::from(vec![
-                        DOMAttribute::from({
+                ::rsx_dom::types::DOMNode::from((
+                    ::rsx_dom::types::DOMTagName::from("what"),
+                    ::rsx_dom::types::DOMAttributes::from(vec![
+                        ::rsx_dom::types::DOMAttribute::from({
                             if happy_and_you_know_it {
-                                DOMNode::from((
-                                    DOMTagName::from("clap"),
-                                    DOMAttributes::from(vec![DOMAttribute::from({ props }),])
+                                ::rsx_dom::types::DOMNode::from((
+                                    ::rsx_dom::types::DOMTagName::from("clap"),
+                                    ::rsx_dom::types::DOMAttributes::from(
+                                        vec![::rsx_dom::types::DOMAttribute::from({ props }),]
+                                    )
                                 ))
                             } else {
-                                DOMNode::from((
-                                    DOMTagName::from(box ["dont", "clap",]),
-                                    DOMAttributes::from(vec![DOMAttribute::from({ props }),])
+                                ::rsx_dom::types::DOMNode::from((
+                                    ::rsx_dom::types::DOMTagName::from(box ["dont", "clap",]),
+                                    ::rsx_dom::types::DOMAttributes::from(
+                                        vec![::rsx_dom::types::DOMAttribute::from({ props }),]
+                                    )
                                 ))
                             }
                         }),
@@ -169,140 +399,66 @@ mod tests {
         assert_eq!(parser(rsx_code_block).parse("foo").is_err(), true);
         assert_eq!(
             parser(rsx_code_block).parse("{}").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: "".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(""), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{{}}").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: "{}".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from("{}"), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{foo}").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: "foo".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from("foo"), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{ foo }").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " foo ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" foo "), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{{foo}}").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: "{foo}".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from("{foo}"), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{ { foo } }").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " { foo } ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" { foo } "), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{ foo bar baz }").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " foo bar baz ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" foo bar baz "), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{ if foo { bar } else { baz } }").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " if foo { bar } else { baz } ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" if foo { bar } else { baz } "), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{ if foo { \"bar\" } else { \"baz\" } }").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " if foo { \"bar\" } else { \"baz\" } ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" if foo { \"bar\" } else { \"baz\" } "), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{ if foo { \"{bar}\" } else { \"{baz}\" } }").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " if foo { \"{bar}\" } else { \"{baz}\" } ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" if foo { \"{bar}\" } else { \"{baz}\" } "), "")
         );
         assert_eq!(
             parser(rsx_code_block)
                 .parse("{ if foo { /* {bar} */ } else { /* {baz} */ } }")
                 .unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " if foo {  } else {  } ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" if foo {  } else {  } "), "")
         );
         assert_eq!(
             parser(rsx_code_block).parse("{ if foo { <bar/> } else { <baz/> } }").unwrap(),
             (
-                RSXParsedExpression {
-                    tokens: " if foo { /* rsx:15848556381555908996 */ } else { /* \
-                             rsx:13987966085338848396 */ } "
-                        .into(),
-                    elements: vec![
-                        (
-                            RSXElementPlaceholder::dummy(),
-                            RSXElement::SelfClosing(RSXSelfClosingElement(
-                                RSXElementName::Name("bar".into()),
-                                RSXAttributes::from(vec![])
-                            ))
-                        ),
-                        (
-                            RSXElementPlaceholder::dummy(),
-                            RSXElement::SelfClosing(RSXSelfClosingElement(
-                                RSXElementName::Name("baz".into()),
-                                RSXAttributes::from(vec![])
-                            ))
-                        ),
+                RSXParsedExpression::from((
+                    vec![" if foo { ", " } else { ", " } "],
+                    vec![
+                        RSXElement::SelfClosing(RSXSelfClosingElement(
+                            RSXElementName::Name("bar".into()),
+                            RSXAttributes::from(vec![])
+                        )),
+                        RSXElement::SelfClosing(RSXSelfClosingElement(
+                            RSXElementName::Name("baz".into()),
+                            RSXAttributes::from(vec![])
+                        )),
                     ]
-                },
+                )),
                 ""
             )
         );
@@ -311,39 +467,25 @@ mod tests {
                 .parse("{ if foo { <bar>{ hello }</bar> } else { <baz>{ world }</baz> } }")
                 .unwrap(),
             (
-                RSXParsedExpression {
-                    tokens: " if foo { /* rsx:6801744144136471498 */ } else { /* \
-                             rsx:4005265977620077421 */ } "
-                        .into(),
-                    elements: vec![
-                        (
-                            RSXElementPlaceholder::dummy(),
-                            RSXElement::Normal(RSXNormalElement(
-                                RSXElementName::Name("bar".into()),
-                                RSXAttributes::from(vec![]),
-                                RSXChildren::from(vec![
-                                    RSXChild::CodeBlock(RSXParsedExpression {
-                                        tokens: " hello ".into(),
-                                        elements: vec![]
-                                    }),
-                                ])
-                            ))
-                        ),
-                        (
-                            RSXElementPlaceholder::dummy(),
-                            RSXElement::Normal(RSXNormalElement(
-                                RSXElementName::Name("baz".into()),
-                                RSXAttributes::from(vec![]),
-                                RSXChildren::from(vec![
-                                    RSXChild::CodeBlock(RSXParsedExpression {
-                                        tokens: " world ".into(),
-                                        elements: vec![]
-                                    }),
-                                ])
-                            ))
-                        ),
+                RSXParsedExpression::from((
+                    vec![" if foo { ", " } else { ", " } "],
+                    vec![
+                        RSXElement::Normal(RSXNormalElement(
+                            RSXElementName::Name("bar".into()),
+                            RSXAttributes::from(vec![]),
+                            RSXChildren::from(vec![
+                                RSXChild::CodeBlock(RSXParsedExpression::from(" hello ")),
+                            ])
+                        )),
+                        RSXElement::Normal(RSXNormalElement(
+                            RSXElementName::Name("baz".into()),
+                            RSXAttributes::from(vec![]),
+                            RSXChildren::from(vec![
+                                RSXChild::CodeBlock(RSXParsedExpression::from(" world ")),
+                            ])
+                        )),
                     ]
-                },
+                )),
                 ""
             )
         );
@@ -352,55 +494,41 @@ mod tests {
                 .parse("{ if foo { <bar>{ <hello/> }</bar> } else { <baz>{ <world/> }</baz> } }")
                 .unwrap(),
             (
-                RSXParsedExpression {
-                    tokens: " if foo { /* rsx:13195988079190323012 */ } else { /* \
-                             rsx:5146857450694500275 */ } "
-                        .into(),
-                    elements: vec![
-                        (
-                            RSXElementPlaceholder::dummy(),
-                            RSXElement::Normal(RSXNormalElement(
-                                RSXElementName::Name("bar".into()),
-                                RSXAttributes::from(vec![]),
-                                RSXChildren::from(vec![
-                                    RSXChild::CodeBlock(RSXParsedExpression {
-                                        tokens: " /* rsx:16730135874920933484 */ ".into(),
-                                        elements: vec![
-                                            (
-                                                RSXElementPlaceholder::dummy(),
-                                                RSXElement::SelfClosing(RSXSelfClosingElement(
-                                                    RSXElementName::Name("hello".into()),
-                                                    RSXAttributes::from(vec![])
-                                                ))
-                                            ),
-                                        ]
-                                    }),
-                                ])
-                            ))
-                        ),
-                        (
-                            RSXElementPlaceholder::dummy(),
-                            RSXElement::Normal(RSXNormalElement(
-                                RSXElementName::Name("baz".into()),
-                                RSXAttributes::from(vec![]),
-                                RSXChildren::from(vec![
-                                    RSXChild::CodeBlock(RSXParsedExpression {
-                                        tokens: " /* rsx:11802923454833793349 */ ".into(),
-                                        elements: vec![
-                                            (
-                                                RSXElementPlaceholder::dummy(),
-                                                RSXElement::SelfClosing(RSXSelfClosingElement(
-                                                    RSXElementName::Name("world".into()),
-                                                    RSXAttributes::from(vec![])
-                                                ))
-                                            ),
-                                        ]
-                                    }),
-                                ])
-                            ))
-                        ),
+                RSXParsedExpression::from((
+                    vec![" if foo { ", " } else { ", " } "],
+                    vec![
+                        RSXElement::Normal(RSXNormalElement(
+                            RSXElementName::Name("bar".into()),
+                            RSXAttributes::from(vec![]),
+                            RSXChildren::from(vec![
+                                RSXChild::CodeBlock(RSXParsedExpression::from((
+                                    vec![" ", " "],
+                                    vec![
+                                        RSXElement::SelfClosing(RSXSelfClosingElement(
+                                            RSXElementName::Name("hello".into()),
+                                            RSXAttributes::from(vec![])
+                                        )),
+                                    ]
+                                ))),
+                            ])
+                        )),
+                        RSXElement::Normal(RSXNormalElement(
+                            RSXElementName::Name("baz".into()),
+                            RSXAttributes::from(vec![]),
+                            RSXChildren::from(vec![
+                                RSXChild::CodeBlock(RSXParsedExpression::from((
+                                    vec![" ", " "],
+                                    vec![
+                                        RSXElement::SelfClosing(RSXSelfClosingElement(
+                                            RSXElementName::Name("world".into()),
+                                            RSXAttributes::from(vec![])
+                                        )),
+                                    ]
+                                ))),
+                            ])
+                        )),
                     ]
-                },
+                )),
                 ""
             )
         );
@@ -412,60 +540,209 @@ mod tests {
                 )
                 .unwrap(),
             (
-                RSXParsedExpression {
-                    tokens: " if foo { /* rsx:17291863876572781215 */ } else { /* \
-                             rsx:10717465471001048421 */ } "
-                        .into(),
-                    elements: vec![
-                        (
-                            RSXElementPlaceholder::dummy(),
-                            RSXElement::Normal(RSXNormalElement(
-                                RSXElementName::Name("bar".into()),
-                                RSXAttributes::from(vec![]),
-                                RSXChildren::from(vec![
-                                    RSXChild::CodeBlock(RSXParsedExpression {
-                                        tokens: " lorem(/* rsx:14589965171469706430 */) ".into(),
-                                        elements: vec![
-                                            (
-                                                RSXElementPlaceholder::dummy(),
-                                                RSXElement::SelfClosing(RSXSelfClosingElement(
-                                                    RSXElementName::Name("hello".into()),
-                                                    RSXAttributes::from(vec![])
-                                                ))
-                                            ),
-                                        ]
-                                    }),
-                                ])
-                            ))
-                        ),
-                        (
-                            RSXElementPlaceholder::dummy(),
-                            RSXElement::Normal(RSXNormalElement(
-                                RSXElementName::Name("baz".into()),
-                                RSXAttributes::from(vec![]),
-                                RSXChildren::from(vec![
-                                    RSXChild::CodeBlock(RSXParsedExpression {
-                                        tokens: " ipsum(/* rsx:6790293794189608791 */) ".into(),
-                                        elements: vec![
-                                            (
-                                                RSXElementPlaceholder::dummy(),
-                                                RSXElement::SelfClosing(RSXSelfClosingElement(
-                                                    RSXElementName::Name("world".into()),
-                                                    RSXAttributes::from(vec![])
-                                                ))
-                                            ),
-                                        ]
-                                    }),
-                                ])
-                            ))
-                        ),
+                RSXParsedExpression::from((
+                    vec![" if foo { ", " } else { ", " } "],
+                    vec![
+                        RSXElement::Normal(RSXNormalElement(
+                            RSXElementName::Name("bar".into()),
+                            RSXAttributes::from(vec![]),
+                            RSXChildren::from(vec![
+                                RSXChild::CodeBlock(RSXParsedExpression::from((
+                                    vec![" lorem(", ") "],
+                                    vec![
+                                        RSXElement::SelfClosing(RSXSelfClosingElement(
+                                            RSXElementName::Name("hello".into()),
+                                            RSXAttributes::from(vec![])
+                                        )),
+                                    ]
+                                ))),
+                            ])
+                        )),
+                        RSXElement::Normal(RSXNormalElement(
+                            RSXElementName::Name("baz".into()),
+                            RSXAttributes::from(vec![]),
+                            RSXChildren::from(vec![
+                                RSXChild::CodeBlock(RSXParsedExpression::from((
+                                    vec![" ipsum(", ") "],
+                                    vec![
+                                        RSXElement::SelfClosing(RSXSelfClosingElement(
+                                            RSXElementName::Name("world".into()),
+                                            RSXAttributes::from(vec![])
+                                        )),
+                                    ]
+                                ))),
+                            ])
+                        )),
                     ]
-                },
+                )),
                 ""
             )
         );
     }
 
+    #[test]
+    pub fn test_rsx_code_block_holes() {
+        let expression = parser(rsx_code_block).parse("{ f({ <a/> }, <b/>, { <c/> }) }").unwrap().0;
+        assert_eq!(expression.code, vec![" f({ ", " }, ", ", { ", " }) "]);
+        assert_eq!(expression.elements.len(), 3);
+        assert_eq!(expression.replace_elements(|_| "e".to_string()), " f({ e }, e, { e }) ");
+
+        let expression = parser(rsx_code_block).parse(r#"{ ("__rsx_hole", <a/>) }"#).unwrap().0;
+        assert_eq!(expression.code, vec![r#" ("__rsx_hole", "#, ") "]);
+
+        let tokens = quote! { #expression };
+        assert_eq!(tokens.as_str().contains(r#""__rsx_hole""#), true);
+        assert_eq!(tokens.as_str().contains("__rsx_hole_"), false);
+    }
+
+    #[test]
+    pub fn test_rsx_code_block_token_trees() {
+        use syn::{BinOpToken, DelimToken, Token};
+        use parse_external_types::RSXCodeTokenTree::{Delimited, Hole};
+
+        let expression = parser(rsx_code_block).parse("{ f(<a/>, [x, <b/>]) }").unwrap().0;
+        assert_eq!(
+            expression.token_trees,
+            vec![
+                RSXCodeTokenTree::Token(Token::Ident("f".into())),
+                Delimited(
+                    DelimToken::Paren,
+                    vec![
+                        Hole(0),
+                        RSXCodeTokenTree::Token(Token::Comma),
+                        Delimited(
+                            DelimToken::Bracket,
+                            vec![
+                                RSXCodeTokenTree::Token(Token::Ident("x".into())),
+                                RSXCodeTokenTree::Token(Token::Comma),
+                                Hole(1),
+                            ]
+                        ),
+                    ]
+                ),
+            ]
+        );
+
+        let holes = expression.holes().collect::<Vec<_>>();
+        assert_eq!(holes.len(), 2);
+        assert_eq!(holes[0], &expression.elements[0]);
+        assert_eq!(holes[1], &expression.elements[1]);
+
+        let expression = parser(rsx_code_block).parse("{ __rsx_hole + <a/> }").unwrap().0;
+        assert_eq!(
+            expression.token_trees,
+            vec![
+                RSXCodeTokenTree::Token(Token::Ident("__rsx_hole".into())),
+                RSXCodeTokenTree::Token(Token::BinOp(BinOpToken::Plus)),
+                Hole(0),
+            ]
+        );
+
+        let expression = parser(rsx_code_block).parse(r#"{ f(c"x", <a/>) }"#).unwrap().0;
+        assert_eq!(
+            expression.token_trees,
+            vec![
+                RSXCodeTokenTree::Token(Token::Ident("f".into())),
+                Delimited(
+                    DelimToken::Paren,
+                    vec![
+                        RSXCodeTokenTree::Literal(r#"c"x""#.to_string()),
+                        RSXCodeTokenTree::Token(Token::Comma),
+                        Hole(0),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(expression.code, vec![r#" f(c"x", "#, ") "]);
+    }
+
+    #[test]
+    pub fn test_rsx_code_block_unlexable() {
+        assert_eq!(parser(rsx_code_block).parse("{ a \\ b }").is_err(), true);
+        assert_eq!(parser(rsx_code_block).parse("{ (<a/>] }").is_err(), true);
+        assert_eq!(parser(rsx_spread_code_block).parse("{...a \\ b}").is_err(), true);
+
+        let options = RSXParseOptions {
+            lex_code_blocks: false,
+            ..RSXParseOptions::default()
+        };
+        let expression = parser(rsx_code_block)
+            .parse(RSXSourceStream::with_options("{ `${a}` }", options))
+            .unwrap()
+            .0;
+        assert_eq!(expression.token_trees, vec![]);
+        assert_eq!(expression.code, vec![" `${a}` "]);
+    }
+
+    #[test]
+    pub fn test_rsx_code_block_literals() {
+        let code = |source: &str| parser(rsx_code_block).parse(source).unwrap().0.code.concat();
+        assert_eq!(code("{'}'}"), "'}'");
+        assert_eq!(code(r#"{"}"}"#), r#""}""#);
+        assert_eq!(code(r##"{r"}\"}"##), r##"r"}\""##);
+        assert_eq!(code(r###"{r#"}"{"#}"###), r###"r#"}"{"#"###);
+        assert_eq!(code(r###"{r##"}"#"##}"###), r###"r##"}"#"##"###);
+        assert_eq!(code(r#"{b'{'}"#), "b'{'");
+        assert_eq!(code(r#"{b'\x7b'}"#), "b'{'");
+        assert_eq!(code(r#"{b'\''}"#), r#"b'\''"#);
+        assert_eq!(code(r#"{b"{\xff"}"#), r#"b"{\xff""#);
+        assert_eq!(code(r##"{br#"}"#}"##), r##"br#"}"#"##);
+        assert_eq!(code(r#"{c"}\u{e9}"}"#), "c\"}\u{e9}\"");
+        assert_eq!(code(r##"{cr#"}"#}"##), r##"cr#"}"#"##);
+        assert_eq!(code(r#"{foo(b"}", r"{")}"#), r#"foo(b"}", r"{")"#);
+        assert_eq!(code("{bar}"), "bar");
+        assert_eq!(code("{ref_b}"), "ref_b");
+    }
+
+    #[test]
+    pub fn test_rsx_code_block_lifetimes() {
+        let code = |source: &str| parser(rsx_code_block).parse(source).unwrap().0.code.concat();
+        let sources = [
+            "{ items.iter().map(|x: &'a Item| x.name) }",
+            "{ 'outer: for x in xs { if x == '}' { break 'outer; } } }",
+            "{ fn f<'a, 'b: 'a>(x: &'a str, y: &'b str) -> &'a str { x } }",
+            "{ Foo::<'static, '_> { c: 'c', d: '{', s: \"'\" } }",
+            "{ impl<'a> Iterator for Iter<'a> { type Item = &'a T; } }",
+            "{ |c| c == 'a' || c == '\\'' || c == '\\n' }",
+            "{ '_loop2: loop { let _x: &'static [char] = &['}', 'x']; } }"
+        ];
+        for &source in sources.iter() {
+            assert_eq!(code(source), source[1..source.len() - 1]);
+        }
+    }
+
+    #[test]
+    pub fn test_rsx_code_block_lifetimes_elements() {
+        let expression = parser(rsx_code_block)
+            .parse("{ items.iter().map(|x: &'a Item| <li key='a'>{ x.name }</li>) }")
+            .unwrap()
+            .0;
+        assert_eq!(expression.elements.len(), 1);
+        assert_eq!(expression.code[0], " items.iter().map(|x: &'a Item| ");
+    }
+
+    #[test]
+    pub fn test_rsx_code_block_comments() {
+        let code = |source: &str| parser(rsx_code_block).parse(source).unwrap().0.code.concat();
+        assert_eq!(code("{ a /* outer /* inner */ still comment */ b }"), " a  b ");
+        assert_eq!(code("{ a /* } /* { */ } */ b }"), " a  b ");
+        assert_eq!(code("{ a /* ** / * **/ b }"), " a  b ");
+        assert_eq!(code("{ a // }\n}"), " a ");
+        assert_eq!(code("{ //// not doc\n /***/ a /**/ }"), "  a  ");
+        assert_eq!(code("{ a /// trailing doc\n }"), " a  ");
+        assert_eq!(code("{ a /** trailing doc */ /* } */ }"), " a   ");
+
+        let sources = [
+            "{ /// doc\n fn f() {} f() }",
+            "{ //! inner doc\n 1 }",
+            "{ /** doc */ struct A; /*! inner /* nested */ doc */ A }",
+            "{ /// first\n /// second\n #[derive(Debug)] struct A; A }"
+        ];
+        for &source in sources.iter() {
+            assert_eq!(code(source), source[1..source.len() - 1]);
+        }
+    }
+
     #[test]
     pub fn test_rsx_spread_code_block() {
         assert_eq!(parser(rsx_spread_code_block).parse("").is_err(), true);
@@ -474,43 +751,19 @@ mod tests {
         assert_eq!(parser(rsx_spread_code_block).parse("{...}").is_err(), true);
         assert_eq!(
             parser(rsx_spread_code_block).parse("{...foo}").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: "foo".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from("foo"), "")
         );
         assert_eq!(
             parser(rsx_spread_code_block).parse("{ ... foo }").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " foo ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" foo "), "")
         );
         assert_eq!(
             parser(rsx_spread_code_block).parse("{...{foo}}").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: "{foo}".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from("{foo}"), "")
         );
         assert_eq!(
             parser(rsx_spread_code_block).parse("{ ... { foo } }").unwrap(),
-            (
-                RSXParsedExpression {
-                    tokens: " { foo } ".into(),
-                    elements: vec![]
-                },
-                ""
-            )
+            (RSXParsedExpression::from(" { foo } "), "")
         );
     }
 }
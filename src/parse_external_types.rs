@@ -10,48 +10,142 @@ specific language governing permissions and limitations under the License.
 */
 
 use std::iter::FromIterator;
+use std::slice;
 
 use self_tokenize_macro::DefaultQuote;
 use self_tokenize_trait::ToCustomTokens;
+use syn::{DelimToken, Token};
 
 use parse_elements_types::RSXElement;
-use parse_external_placeholders::RSXElementPlaceholder;
+use parse_span_types::{RSXSpan, RSXSpans};
 
 #[derive(Debug, PartialEq)]
 pub enum RSXRawCodeFragment {
     Empty,
     Token(char),
     Tokens(String),
+    // A literal syn can't lex, like a C string, which is kept whole.
+    Literal(String),
     Element(RSXElement),
-    ParsedExpression(RSXParsedExpression)
+    Block(RSXRawCode)
 }
 
-#[derive(Debug, PartialEq, DefaultQuote)]
-pub struct RSXParsedExpression {
-    pub tokens: String,
-    pub elements: Vec<(RSXElementPlaceholder, RSXElement)>
+// What the code for lexing is split around, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RSXCodeGap {
+    Element,
+    Literal(String)
 }
 
-impl FromIterator<RSXRawCodeFragment> for RSXParsedExpression {
+// Code as written, split around the elements inside it, so there's always one
+// more piece of code than there are elements. The code for lexing is split
+// around literals syn can't lex as well.
+#[derive(Debug, PartialEq)]
+pub struct RSXRawCode {
+    pub code: Vec<String>,
+    pub elements: Vec<RSXElement>,
+    pub lexable: Vec<String>,
+    pub gaps: Vec<RSXCodeGap>
+}
+
+impl RSXRawCode {
+    fn push_str(&mut self, s: &str) {
+        rsx_push_pieces(&mut self.code, vec![s.to_string()]);
+        rsx_push_pieces(&mut self.lexable, vec![s.to_string()]);
+    }
+}
+
+// Appends the first piece to the last one, and the others after it.
+fn rsx_push_pieces(pieces: &mut Vec<String>, other: Vec<String>) {
+    let mut other = other.into_iter();
+    if let (Some(last), Some(first)) = (pieces.last_mut(), other.next()) {
+        last.push_str(&first);
+    }
+    pieces.extend(other);
+}
+
+// Nested code blocks are merged into the enclosing code, braces included.
+impl FromIterator<RSXRawCodeFragment> for RSXRawCode {
     fn from_iter<I: IntoIterator<Item = RSXRawCodeFragment>>(fragments: I) -> Self {
-        let mut expression = RSXParsedExpression {
-            tokens: String::new(),
-            elements: Vec::new()
+        let mut raw = RSXRawCode {
+            code: vec![String::new()],
+            elements: vec![],
+            lexable: vec![String::new()],
+            gaps: vec![]
         };
-        fragments.into_iter().for_each(|fragment| match fragment {
-            RSXRawCodeFragment::Empty => {}
-            RSXRawCodeFragment::Token(c) => expression.tokens.push(c),
-            RSXRawCodeFragment::Tokens(s) => expression.tokens.push_str(&s),
-            RSXRawCodeFragment::Element(element) => {
-                let placeholder = RSXElementPlaceholder::generate();
-                expression.tokens.push_str(placeholder.as_ref());
-                expression.elements.push((placeholder, element));
-            }
-            RSXRawCodeFragment::ParsedExpression(other) => {
-                expression.tokens.push_str(&format!("{}{}{}", "{", other.tokens, "}"));
-                expression.elements.extend(other.elements);
+        for fragment in fragments {
+            match fragment {
+                RSXRawCodeFragment::Empty => {}
+                RSXRawCodeFragment::Token(c) => raw.push_str(&c.to_string()),
+                RSXRawCodeFragment::Tokens(s) => raw.push_str(&s),
+                RSXRawCodeFragment::Literal(s) => {
+                    rsx_push_pieces(&mut raw.code, vec![s.clone()]);
+                    raw.lexable.push(String::new());
+                    raw.gaps.push(RSXCodeGap::Literal(s));
+                }
+                RSXRawCodeFragment::Element(element) => {
+                    raw.elements.push(element);
+                    raw.code.push(String::new());
+                    raw.lexable.push(String::new());
+                    raw.gaps.push(RSXCodeGap::Element);
+                }
+                RSXRawCodeFragment::Block(other) => {
+                    raw.push_str("{");
+                    rsx_push_pieces(&mut raw.code, other.code);
+                    rsx_push_pieces(&mut raw.lexable, other.lexable);
+                    raw.push_str("}");
+                    raw.elements.extend(other.elements);
+                    raw.gaps.extend(other.gaps);
+                }
             }
-        });
-        expression
+        }
+        raw
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RSXCodeTokenTree {
+    Token(Token),
+    Delimited(DelimToken, Vec<RSXCodeTokenTree>),
+    // A literal syn can't lex, like a C string, as written.
+    Literal(String),
+    // An element, as an index into the expression's elements.
+    Hole(usize)
+}
+
+// Code blocks are lexed once while parsing. The code as written is kept too,
+// since token trees don't remember whitespace, for printing it back.
+#[derive(Debug, PartialEq, DefaultQuote)]
+pub struct RSXParsedExpression {
+    // Comments other than doc comments are dropped. Empty when code blocks
+    // aren't lexed, see `RSXParseOptions::lex_code_blocks`.
+    pub token_trees: Vec<RSXCodeTokenTree>,
+    pub elements: Vec<RSXElement>,
+    pub code: Vec<String>
+}
+
+impl RSXParsedExpression {
+    pub fn span(&self, spans: &RSXSpans) -> RSXSpan {
+        spans.get(self)
+    }
+
+    // Iterates the elements inside the code block, in the order of their holes.
+    pub fn holes(&self) -> slice::Iter<RSXElement> {
+        self.elements.iter()
+    }
+
+    // Rebuilds the code as written, with every element replaced by the given
+    // output.
+    pub fn replace_elements<F>(&self, mut f: F) -> String
+    where
+        F: FnMut(&RSXElement) -> String
+    {
+        let mut code = self.code.iter();
+        let mut result = code.next().cloned().unwrap_or_default();
+        for (element, piece) in self.elements.iter().zip(code) {
+            result.push_str(&f(element));
+            result.push_str(piece);
+        }
+        result
     }
 }
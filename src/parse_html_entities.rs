@@ -0,0 +1,114 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::char;
+
+use parse_html_entities_table::HTML_ENTITIES;
+
+// Longest named reference is `&CounterClockwiseContourIntegral;`.
+const MAX_REFERENCE_LEN: usize = 40;
+
+// Anything that looks like a reference but isn't a known or valid one is kept
+// verbatim, including the leading `&`.
+pub fn rsx_decode_entities(s: &str) -> String {
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(index) = rest.find('&') {
+        decoded.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        match rsx_decode_entity(rest) {
+            Some((value, len)) => {
+                decoded.push_str(&value);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+// Returns the decoded value of the reference at the start of the given string,
+// together with the length of the reference, including the `&` and `;`.
+fn rsx_decode_entity(s: &str) -> Option<(String, usize)> {
+    // Only the start of the string can hold the reference, so the search for
+    // its end stops there instead of scanning the rest of the text.
+    let end = s.char_indices()
+        .take(MAX_REFERENCE_LEN + 1)
+        .find(|&(_, c)| c == ';')
+        .map(|(index, _)| index);
+    let end = match end {
+        Some(end) if end <= MAX_REFERENCE_LEN => end,
+        _ => return None
+    };
+    let reference = &s[1..end];
+
+    let code = if reference.starts_with("#x") || reference.starts_with("#X") {
+        rsx_parse_code(&reference[2..], 16)
+    } else if reference.starts_with('#') {
+        rsx_parse_code(&reference[1..], 10)
+    } else {
+        return HTML_ENTITIES
+            .binary_search_by(|&(name, _)| name.cmp(reference))
+            .ok()
+            .map(|index| (HTML_ENTITIES[index].1.to_string(), end + 1));
+    };
+
+    code.and_then(char::from_u32).map(|c| (c.to_string(), end + 1))
+}
+
+fn rsx_parse_code(digits: &str, radix: u32) -> Option<u32> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    match u32::from_str_radix(digits, radix) {
+        Ok(0) | Err(_) => None,
+        Ok(code) => Some(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_rsx_decode_entities() {
+        assert_eq!(rsx_decode_entities(""), "");
+        assert_eq!(rsx_decode_entities("foo"), "foo");
+        assert_eq!(rsx_decode_entities("&amp;"), "&");
+        assert_eq!(rsx_decode_entities("a &lt;b&gt; c"), "a <b> c");
+        assert_eq!(rsx_decode_entities("&nbsp;&copy;&AElig;"), "\u{a0}\u{a9}\u{c6}");
+        assert_eq!(rsx_decode_entities("&NotEqualTilde;"), "\u{2242}\u{338}");
+        assert_eq!(rsx_decode_entities("&#169;&#x27;&#X41;"), "\u{a9}'A");
+    }
+
+    #[test]
+    pub fn test_rsx_decode_entities_invalid() {
+        assert_eq!(rsx_decode_entities("&"), "&");
+        assert_eq!(rsx_decode_entities("& foo;"), "& foo;");
+        assert_eq!(rsx_decode_entities("&amp"), "&amp");
+        assert_eq!(rsx_decode_entities("&foo;"), "&foo;");
+        assert_eq!(rsx_decode_entities("&&amp;"), "&&");
+        assert_eq!(rsx_decode_entities("&#;&#x;&#xg;&#0;"), "&#;&#x;&#xg;&#0;");
+        assert_eq!(rsx_decode_entities("&#xD800;&#x110000;"), "&#xD800;&#x110000;");
+
+        let long = format!("&{};", "\u{e9}".repeat(MAX_REFERENCE_LEN));
+        assert_eq!(rsx_decode_entities(&long), long);
+        let text = format!("& {}&amp;", "a".repeat(1000));
+        assert_eq!(rsx_decode_entities(&text), format!("& {}&", "a".repeat(1000)));
+    }
+}
@@ -11,7 +11,19 @@ specific language governing permissions and limitations under the License.
 
 use combine::{ParseResult, Parser, Stream};
 use combine::char::{alpha_num, letter, space, string};
-use combine::combinator::{between, many, none_of, parser, skip_many, token, try, value, many1};
+use combine::combinator::{
+    between,
+    env_parser,
+    many,
+    many1,
+    none_of,
+    optional,
+    parser,
+    skip_many,
+    token,
+    try,
+    value
+};
 
 use parse_js_types::{
     JSBool,
@@ -20,10 +32,20 @@ use parse_js_types::{
     JSIdentifierPart,
     JSIdentifierStart,
     JSNumber,
+    JSNumberKind,
     JSSingleStringCharacter,
     JSSingleStringCharacters
 };
-use parse_misc::{escaped_character, float_exp, float_simple, identifier_non_alpha_numeric, integer};
+use parse_misc::{
+    decimal,
+    escaped_character,
+    identifier_non_alpha_numeric,
+    line_continuation,
+    number_suffix,
+    prefixed_integer,
+    sign
+};
+use parse_span_types::RSXStream;
 
 pub fn js_identifier_start<I>(input: I) -> ParseResult<JSIdentifierStart, I>
 where
@@ -60,43 +82,92 @@ pub fn js_number<I>(input: I) -> ParseResult<JSNumber, I>
 where
     I: Stream<Item = char>
 {
-    choice!(
-        try(parser(float_exp).map(JSNumber)),
-        try(parser(float_simple).map(JSNumber)),
-        parser(integer).map(|v| v as f64).map(JSNumber)
+    (
+        optional(parser(sign)),
+        choice!(
+            try(parser(prefixed_integer)).map(|digits: String| {
+                let radix = match &digits[1..2] {
+                    "x" | "X" => 16,
+                    "o" | "O" => 8,
+                    _ => 2
+                };
+                (digits, JSNumberKind::Integer, radix)
+            }),
+            parser(decimal).map(|digits: String| {
+                if digits.contains(|c: char| c == '.' || c == 'e' || c == 'E') {
+                    (digits, JSNumberKind::Float, 10)
+                } else {
+                    (digits, JSNumberKind::Integer, 10)
+                }
+            })
+        )
     ).parse_stream(input)
+        .and_then(|((sign, (digits, kind, radix)), consumed)| {
+            let literal = match sign {
+                Some(sign) => format!("{}{}", sign, digits),
+                None => digits
+            };
+            consumed.combine(|input| {
+                let float = kind == JSNumberKind::Float;
+                optional(try(env_parser((radix, float), number_suffix)))
+                    .parse_stream(input)
+                    .map(|(suffix, consumed)| {
+                        let kind = match suffix {
+                            Some(suffix) if suffix.starts_with('f') => JSNumberKind::Float,
+                            _ => kind
+                        };
+                        let suffix = suffix.map(String::from);
+                        (JSNumber { literal, kind, suffix }, consumed)
+                    })
+            })
+        })
 }
 
 pub fn js_double_string_character<I>(input: I) -> ParseResult<JSDoubleStringCharacter, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    choice!(try(parser(escaped_character)), none_of("\"".chars()))
+    choice!(parser(escaped_character), none_of("\\\"".chars()))
         .map(JSDoubleStringCharacter)
         .parse_stream(input)
 }
 
 pub fn js_single_string_character<I>(input: I) -> ParseResult<JSSingleStringCharacter, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    choice!(try(parser(escaped_character)), none_of("'".chars()))
+    choice!(parser(escaped_character), none_of("\\'".chars()))
         .map(JSSingleStringCharacter)
         .parse_stream(input)
 }
 
 pub fn js_double_string_characters<I>(input: I) -> ParseResult<JSDoubleStringCharacters, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    between(token('"'), token('"'), many(parser(js_double_string_character))).parse_stream(input)
+    between(
+        token('"').skip(parser(js_line_continuations)),
+        token('"'),
+        many(parser(js_double_string_character).skip(parser(js_line_continuations)))
+    ).parse_stream(input)
 }
 
 pub fn js_single_string_characters<I>(input: I) -> ParseResult<JSSingleStringCharacters, I>
+where
+    I: RSXStream
+{
+    between(
+        token('\'').skip(parser(js_line_continuations)),
+        token('\''),
+        many(parser(js_single_string_character).skip(parser(js_line_continuations)))
+    ).parse_stream(input)
+}
+
+pub fn js_line_continuations<I>(input: I) -> ParseResult<(), I>
 where
     I: Stream<Item = char>
 {
-    between(token('\''), token('\''), many(parser(js_single_string_character))).parse_stream(input)
+    skip_many(try(parser(line_continuation))).parse_stream(input)
 }
 
 pub fn js_single_line_comment<I>(input: I) -> ParseResult<(), I>
@@ -176,15 +247,137 @@ mod tests {
     pub fn test_js_number() {
         assert_eq!(parser(js_number).parse("").is_err(), true);
         assert_eq!(parser(js_number).parse(" ").is_err(), true);
-        assert_eq!(parser(js_number).parse("1").unwrap(), (1f64.into(), ""));
-        assert_eq!(parser(js_number).parse("+1").unwrap(), (1f64.into(), ""));
-        assert_eq!(parser(js_number).parse("-1").unwrap(), ((-1f64).into(), ""));
-        assert_eq!(parser(js_number).parse("1.2").unwrap(), (1.2f64.into(), ""));
-        assert_eq!(parser(js_number).parse("1e3").unwrap(), (1e3f64.into(), ""));
-        assert_eq!(parser(js_number).parse("1.2e3").unwrap(), (1.2e3f64.into(), ""));
+        assert_eq!(
+            parser(js_number).parse("1").unwrap(),
+            (("1", JSNumberKind::Integer).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("+1").unwrap(),
+            (("+1", JSNumberKind::Integer).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("-1").unwrap(),
+            (("-1", JSNumberKind::Integer).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("1.2").unwrap(),
+            (("1.2", JSNumberKind::Float).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("0.05").unwrap(),
+            (("0.05", JSNumberKind::Float).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("1e3").unwrap(),
+            (("1e3", JSNumberKind::Float).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("1.2e3").unwrap(),
+            (("1.2e3", JSNumberKind::Float).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("0x1F").unwrap(),
+            (("0x1F", JSNumberKind::Integer).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("-0o17").unwrap(),
+            (("-0o17", JSNumberKind::Integer).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("0b1010").unwrap(),
+            (("0b1010", JSNumberKind::Integer).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("1_000").unwrap(),
+            (("1_000", JSNumberKind::Integer).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("10u32").unwrap(),
+            (("10", JSNumberKind::Integer, "u32").into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("1f32").unwrap(),
+            (("1", JSNumberKind::Float, "f32").into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("1.5f32").unwrap(),
+            (("1.5", JSNumberKind::Float, "f32").into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("1.5u32").unwrap(),
+            (("1.5", JSNumberKind::Float).into(), "u32")
+        );
+        assert_eq!(
+            parser(js_number).parse("0x1f32").unwrap(),
+            (("0x1f32", JSNumberKind::Integer).into(), "")
+        );
+        assert_eq!(
+            parser(js_number).parse("0b1f32").unwrap(),
+            (("0b1", JSNumberKind::Integer).into(), "f32")
+        );
+        assert_eq!(
+            parser(js_number).parse("0o7f64").unwrap(),
+            (("0o7", JSNumberKind::Integer).into(), "f64")
+        );
+        assert_eq!(
+            parser(js_number).parse("0o7u8").unwrap(),
+            (("0o7", JSNumberKind::Integer, "u8").into(), "")
+        );
         assert_eq!(parser(js_number).parse("a").is_err(), true);
     }
 
+    #[test]
+    pub fn test_js_number_value() {
+        let value = |source: &str| parser(js_number).parse(source).unwrap().0.value();
+        assert_eq!(value("1"), 1f64);
+        assert_eq!(value("-1.2e3"), -1200f64);
+        assert_eq!(value("0.05"), 0.05f64);
+        assert_eq!(value("1_000"), 1000f64);
+        assert_eq!(value("0x1F"), 31f64);
+        assert_eq!(value("-0o17"), -15f64);
+        assert_eq!(value("0B1010"), 10f64);
+        assert_eq!(value("10u32"), 10f64);
+    }
+
+    #[test]
+    pub fn test_js_number_decimal() {
+        let decimal = |source: &str| parser(js_number).parse(source).unwrap().0.decimal();
+        assert_eq!(decimal("18446744073709551615u64"), "18446744073709551615");
+        assert_eq!(decimal("+1_000.50"), "1000.50");
+        assert_eq!(decimal("-0x1Fi32"), "-31");
+        assert_eq!(decimal("1e3f32"), "1e3");
+    }
+
+    #[test]
+    pub fn test_js_number_rust_literal() {
+        let literal = |source: &str| parser(js_number).parse(source).unwrap().0.rust_literal();
+        assert_eq!(literal("1"), "1i64");
+        assert_eq!(literal("+1.5"), "1.5f64");
+        assert_eq!(literal("-1e-3"), "-1e-3f64");
+        assert_eq!(literal("0X1F"), "0x1Fi64");
+        assert_eq!(literal("1_000u32"), "1_000u32");
+        assert_eq!(literal("1f32"), "1f32");
+    }
+
+    #[test]
+    pub fn test_js_number_fits_rust_type() {
+        let fits = |source: &str| parser(js_number).parse(source).unwrap().0.fits_rust_type();
+        assert_eq!(fits("1"), true);
+        assert_eq!(fits("-9223372036854775808"), true);
+        assert_eq!(fits("9223372036854775807"), true);
+        assert_eq!(fits("9223372036854775808"), false);
+        assert_eq!(fits("18446744073709551615u64"), true);
+        assert_eq!(fits("0xFFu8"), true);
+        assert_eq!(fits("256u8"), false);
+        assert_eq!(fits("-128i8"), true);
+        assert_eq!(fits("-129i8"), false);
+        assert_eq!(fits("-1u8"), false);
+        assert_eq!(fits("-0usize"), false);
+        assert_eq!(fits("1e308"), true);
+        assert_eq!(fits("1e400"), false);
+        assert_eq!(fits("1e39f32"), false);
+    }
+
     #[test]
     pub fn test_js_single_string_characters() {
         assert_eq!(parser(js_single_string_characters).parse("").is_err(), true);
@@ -223,6 +416,15 @@ mod tests {
             p(js_double_string_characters).parse(r#""foo'bar""#).unwrap(),
             ("foo'bar".into(), "")
         );
+        assert_eq!(
+            p(js_double_string_characters).parse(r#""foo\\bar\u{1F600}""#).unwrap(),
+            ("foo\\bar\u{1f600}".into(), "")
+        );
+        assert_eq!(
+            p(js_double_string_characters).parse("\"foo\\\nbar\\\n\"").unwrap(),
+            ("foobar".into(), "")
+        );
+        assert_eq!(p(js_double_string_characters).parse(r#""foo\qbar""#).is_err(), true);
     }
 
     #[test]
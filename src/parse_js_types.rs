@@ -9,13 +9,140 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
+use std::f64::NAN;
 use std::iter::FromIterator;
 
 #[derive(Debug, PartialEq)]
 pub struct JSBool(pub bool);
 
-#[derive(Debug, PartialEq)]
-pub struct JSNumber(pub f64);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JSNumberKind {
+    Integer,
+    Float
+}
+
+// Numbers keep their literal as written (sign, radix prefix, digits and
+// separators), so that printing them doesn't lose anything. The value is only
+// computed on demand.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JSNumber {
+    pub literal: String,
+    pub kind: JSNumberKind,
+    pub suffix: Option<String>
+}
+
+impl JSNumber {
+    pub fn lexeme(&self) -> String {
+        format!("{}{}", self.literal, self.suffix.as_ref().map_or("", |s| s.as_str()))
+    }
+
+    pub fn value(&self) -> f64 {
+        let (negative, radix, digits) = self.parts();
+        let value = if radix == 10 {
+            digits.parse::<f64>().unwrap_or(NAN)
+        } else {
+            digits
+                .chars()
+                .filter_map(|c| c.to_digit(radix))
+                .fold(0f64, |value, digit| value * f64::from(radix) + f64::from(digit))
+        };
+        if negative {
+            -value
+        } else {
+            value
+        }
+    }
+
+    // The literal written in decimal, without separators, radix prefix or
+    // suffix, and without losing precision to a float like `value` does.
+    pub fn decimal(&self) -> String {
+        let (negative, radix, digits) = self.parts();
+        let sign = if negative { "-" } else { "" };
+        if radix == 10 {
+            return format!("{}{}", sign, digits);
+        }
+        match self.magnitude() {
+            Some(magnitude) => format!("{}{}", sign, magnitude),
+            None => format!("{}", self.value())
+        }
+    }
+
+    // The absolute value of an integer literal, unless it overflows.
+    fn magnitude(&self) -> Option<u128> {
+        let (_, radix, digits) = self.parts();
+        let mut magnitude = 0u128;
+        for digit in digits.chars().filter_map(|c| c.to_digit(radix)) {
+            magnitude = magnitude
+                .checked_mul(u128::from(radix))
+                .and_then(|m| m.checked_add(u128::from(digit)))?;
+        }
+        Some(magnitude)
+    }
+
+    // The sign, radix and digits without separators or radix prefix.
+    fn parts(&self) -> (bool, u32, String) {
+        let literal = self.literal.replace('_', "");
+        let (negative, unsigned) = match literal.chars().next() {
+            Some('-') => (true, &literal[1..]),
+            Some('+') => (false, &literal[1..]),
+            _ => (false, &literal[..])
+        };
+        match unsigned.chars().take(2).collect::<String>().as_str() {
+            "0x" | "0X" => (negative, 16, unsigned[2..].to_string()),
+            "0o" | "0O" => (negative, 8, unsigned[2..].to_string()),
+            "0b" | "0B" => (negative, 2, unsigned[2..].to_string()),
+            _ => (negative, 10, unsigned.to_string())
+        }
+    }
+
+    // Literals out of range of their type don't compile, and neither do
+    // negative unsigned integers.
+    pub fn fits_rust_type(&self) -> bool {
+        let rust_type = self.rust_type();
+        if self.kind == JSNumberKind::Float {
+            return match rust_type {
+                "f32" => (self.value() as f32).is_finite(),
+                _ => self.value().is_finite()
+            };
+        }
+
+        let negative = self.parts().0;
+        let magnitude = match self.magnitude() {
+            Some(magnitude) => magnitude,
+            None => return false
+        };
+
+        let bits = match &rust_type[1..] {
+            "size" => 64,
+            bits => bits.parse::<u32>().unwrap_or(64)
+        };
+        let max = u128::max_value() >> (128 - bits);
+        match (rust_type.starts_with('u'), negative) {
+            (true, true) => false,
+            (true, false) => magnitude <= max,
+            (false, true) => magnitude <= max / 2 + 1,
+            (false, false) => magnitude <= max / 2
+        }
+    }
+
+    // Unsuffixed numbers default to the widest types.
+    pub fn rust_type(&self) -> &str {
+        match (self.suffix.as_ref(), self.kind) {
+            (Some(suffix), _) => suffix.as_str(),
+            (None, JSNumberKind::Integer) => "i64",
+            (None, JSNumberKind::Float) => "f64"
+        }
+    }
+
+    pub fn rust_literal(&self) -> String {
+        let literal = self.literal
+            .trim_left_matches('+')
+            .replacen("0X", "0x", 1)
+            .replacen("0O", "0o", 1)
+            .replacen("0B", "0b", 1);
+        format!("{}{}", literal, self.rust_type())
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub struct JSSingleStringCharacter(pub char);
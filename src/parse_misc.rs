@@ -9,36 +9,67 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
+use std::char;
+
 use combine::{ParseResult, Parser, Stream};
-use combine::char::{digit, spaces};
-use combine::combinator::{one_of, optional, parser, token, value, many1};
+use combine::char::{hex_digit, spaces, string};
+use combine::combinator::{
+    between,
+    env_parser,
+    many,
+    many1,
+    one_of,
+    optional,
+    parser,
+    satisfy,
+    token,
+    try,
+    value
+};
+use combine::primitives::Consumed;
+
+use parse_cst::rsx_record_cst;
+use parse_cst_types::RSXCstKind;
+use parse_error::rsx_error_hint;
+use parse_error_types::{RSXParseError, RSXParseErrorKind};
+use parse_span_types::{RSXPosition, RSXStream};
 
+// The tags are terminals of the grammar, each recorded as a single token of
+// the concrete syntax tree, including any whitespace inside `</` and `/>`.
 pub fn open_tag<I>(input: I) -> ParseResult<(), I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    token('<').with(value(())).parse_stream(input)
+    let start = input.source_position();
+    let result = token('<').with(value(())).parse_stream(input);
+    rsx_record_cst(RSXCstKind::Punctuation, start, result)
 }
 
 pub fn close_tag<I>(input: I) -> ParseResult<(), I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    token('>').with(value(())).parse_stream(input)
+    let start = input.source_position();
+    let result = token('>').with(value(())).parse_stream(input);
+    rsx_record_cst(RSXCstKind::Punctuation, start, result)
 }
 
 pub fn closing_element_open_tag<I>(input: I) -> ParseResult<(), I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    (token('<').skip(spaces()), token('/')).with(value(())).parse_stream(input)
+    let start = input.source_position();
+    let result = (token('<').skip(spaces()), token('/')).with(value(())).parse_stream(input);
+    rsx_record_cst(RSXCstKind::Punctuation, start, result)
 }
 
 pub fn self_closing_element_close_tag<I>(input: I) -> ParseResult<(), I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    (token('/').skip(spaces()), token('>')).with(value(())).parse_stream(input)
+    let start = input.source_position();
+    let result = (token('/').skip(spaces()), token('>')).with(value(())).parse_stream(input);
+    rsx_record_cst(RSXCstKind::Punctuation, start, result)
 }
 
 pub fn sign<I>(input: I) -> ParseResult<char, I>
@@ -48,54 +79,106 @@ where
     choice!(token('-'), token('+')).parse_stream(input)
 }
 
-pub fn frac<I>(input: I) -> ParseResult<i64, I>
+// Digits in the given radix, optionally separated by underscores. Numbers are
+// kept as written, so the separators aren't stripped.
+pub fn digits<I>(radix: u32, input: I) -> ParseResult<String, I>
 where
     I: Stream<Item = char>
 {
-    token('.')
-        .with(many1(digit()))
-        .map(|frac: String| frac.parse::<i64>().unwrap())
+    (
+        satisfy(move |c: char| c.is_digit(radix)),
+        many(satisfy(move |c: char| c == '_' || c.is_digit(radix)))
+    ).map(|(first, rest): (char, String)| format!("{}{}", first, rest))
         .parse_stream(input)
 }
 
-pub fn exp<I>(input: I) -> ParseResult<i64, I>
+pub fn frac<I>(input: I) -> ParseResult<String, I>
 where
     I: Stream<Item = char>
 {
-    choice!(token('e'), token('E')).with(parser(integer)).parse_stream(input)
+    token('.').with(env_parser(10, digits)).parse_stream(input)
 }
 
-pub fn integer<I>(input: I) -> ParseResult<i64, I>
+pub fn exp<I>(input: I) -> ParseResult<String, I>
 where
     I: Stream<Item = char>
 {
-    (optional(parser(sign)), many1(digit()))
-        .map(|(sign, digits): (Option<_>, String)| {
-            format!("{}{}", sign.unwrap_or('+'), digits).parse::<i64>().unwrap()
+    (one_of("eE".chars()), parser(integer))
+        .map(|(e, integer)| format!("{}{}", e, integer))
+        .parse_stream(input)
+}
+
+pub fn integer<I>(input: I) -> ParseResult<String, I>
+where
+    I: Stream<Item = char>
+{
+    (optional(parser(sign)), env_parser(10, digits))
+        .map(|(sign, digits): (Option<char>, String)| match sign {
+            Some(sign) => format!("{}{}", sign, digits),
+            None => digits
         })
         .parse_stream(input)
 }
 
-pub fn float_simple<I>(input: I) -> ParseResult<f64, I>
+pub fn decimal<I>(input: I) -> ParseResult<String, I>
 where
     I: Stream<Item = char>
 {
-    (parser(integer), parser(frac))
-        .map(|(int, frac)| format!("{}.{}", int, frac).parse::<f64>().unwrap())
+    (
+        env_parser(10, digits),
+        optional(try(parser(frac))),
+        optional(try(parser(exp)))
+    ).map(|(int, frac, exp): (String, Option<String>, Option<String>)| {
+        let frac = frac.map(|frac| format!(".{}", frac)).unwrap_or_default();
+        format!("{}{}{}", int, frac, exp.unwrap_or_default())
+    })
         .parse_stream(input)
 }
 
-pub fn float_exp<I>(input: I) -> ParseResult<f64, I>
+pub fn prefixed_integer<I>(input: I) -> ParseResult<String, I>
 where
     I: Stream<Item = char>
 {
-    (parser(integer), optional(parser(frac)), parser(exp))
-        .map(|(int, frac, exp)| {
-            format!("{}.{}e{}", int, frac.unwrap_or(0), exp).parse::<f64>().unwrap()
-        })
+    choice!(
+        try((token('0'), one_of("xX".chars()), env_parser(16, digits))),
+        try((token('0'), one_of("oO".chars()), env_parser(8, digits))),
+        (token('0'), one_of("bB".chars()), env_parser(2, digits))
+    ).map(|(zero, prefix, digits)| format!("{}{}{}", zero, prefix, digits))
         .parse_stream(input)
 }
 
+// Rust type suffixes, given the number's radix and whether it's a float. Floats
+// can only have a float suffix, while decimal integers can have any suffix,
+// turning them into floats in the case of `f32` and `f64`. Integers in other
+// radixes can't be floats, so they only get integer suffixes.
+pub fn number_suffix<I>((radix, float): (u32, bool), input: I) -> ParseResult<&'static str, I>
+where
+    I: Stream<Item = char>
+{
+    let floats = choice!(try(string("f32")), try(string("f64")));
+    let integers = choice!(
+        try(string("i8")),
+        try(string("i16")),
+        try(string("i32")),
+        try(string("i64")),
+        try(string("i128")),
+        try(string("isize")),
+        try(string("u8")),
+        try(string("u16")),
+        try(string("u32")),
+        try(string("u64")),
+        try(string("u128")),
+        try(string("usize"))
+    );
+    if float {
+        return floats.parse_stream(input);
+    }
+    if radix != 10 {
+        return integers.parse_stream(input);
+    }
+    choice!(floats, integers).parse_stream(input)
+}
+
 pub fn identifier_non_alpha_numeric<I>(input: I) -> ParseResult<char, I>
 where
     I: Stream<Item = char>
@@ -103,25 +186,123 @@ where
     choice!(token('_'), token('$')).parse_stream(input)
 }
 
+// Covers the escape sequences of both JS and Rust strings. Once the backslash
+// is consumed, anything but a valid escape sequence is an error, instead of
+// being taken as literal characters.
 pub fn escaped_character<I>(input: I) -> ParseResult<char, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
+    let start = input.source_position();
+    let invalid = RSXParseError::new(RSXParseErrorKind::InvalidEscape, start);
     token('\\')
-        .with(one_of(r#"'"\nrtbfv0"#.chars()))
-        .map(|c| match c {
-            '\'' => '\'',
-            '"' => '"',
-            '\\' => '\\',
-            'n' => '\n',
-            'r' => '\r',
-            't' => '\t',
-            'b' => '\u{0008}',
-            'f' => '\u{000c}',
-            'v' => '\u{2b7f}',
-            '0' => '\u{0000}',
-            _ => panic!("Unhandled escape character")
+        .with(choice!(
+            one_of(r#"'"\nrtbfv0"#.chars()).map(|c| match c {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'b' => '\u{0008}',
+                'f' => '\u{000c}',
+                'v' => '\u{000b}',
+                '0' => '\u{0000}',
+                c => c
+            }).map(u32::from),
+            token('x').with(parser(hex_escape)),
+            token('u').with(env_parser(start, unicode_escape))
+        ))
+        .and_then(move |code| char::from_u32(code).ok_or_else(|| invalid.clone()))
+        .parse_stream(input)
+        .map_err(|error| match error {
+            Consumed::Consumed(_) => {
+                let hint = RSXParseError::new(RSXParseErrorKind::InvalidEscape, start);
+                rsx_error_hint(error, hint)
+            }
+            Consumed::Empty(_) => error
         })
+}
+
+// Returns the code point of a `\xHH` escape, without the `\x`.
+pub fn hex_escape<I>(input: I) -> ParseResult<u32, I>
+where
+    I: Stream<Item = char>
+{
+    (hex_digit(), hex_digit())
+        .map(|(a, b)| hex_value(&[a, b]))
+        .parse_stream(input)
+}
+
+// Returns the code point of a `\u{H...}` or `\uHHHH` escape, without the `\u`.
+// Lone UTF-16 surrogates aren't code points, so are rejected later, but
+// surrogate pairs written as two consecutive escapes are combined.
+pub fn unicode_escape<I>(start: RSXPosition, input: I) -> ParseResult<u32, I>
+where
+    I: Stream<Item = char>
+{
+    let invalid = RSXParseError::new(RSXParseErrorKind::InvalidEscape, start);
+    let invalid_unit = invalid.clone();
+    choice!(
+        between(token('{'), token('}'), many1(hex_digit())).and_then(move |digits: Vec<char>| {
+            if digits.len() <= 6 {
+                Ok(hex_value(&digits))
+            } else {
+                Err(invalid.clone())
+            }
+        }),
+        (
+            parser(utf16_code_unit),
+            optional(try(string("\\u").with(parser(utf16_code_unit)).and_then(move |unit| {
+                if is_low_surrogate(unit) {
+                    Ok(unit)
+                } else {
+                    Err(invalid_unit.clone())
+                }
+            })))
+        ).map(|(unit, low)| match (unit, low) {
+            (high, Some(low)) if is_high_surrogate(high) => {
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+            }
+            (_, Some(low)) => low,
+            (unit, None) => unit
+        })
+    ).parse_stream(input)
+}
+
+fn utf16_code_unit<I>(input: I) -> ParseResult<u32, I>
+where
+    I: Stream<Item = char>
+{
+    (hex_digit(), hex_digit(), hex_digit(), hex_digit())
+        .map(|(a, b, c, d)| hex_value(&[a, b, c, d]))
+        .parse_stream(input)
+}
+
+pub fn hex_value(digits: &[char]) -> u32 {
+    digits.iter().fold(0, |value, digit| value * 16 + digit.to_digit(16).unwrap_or(0))
+}
+
+fn is_high_surrogate(unit: u32) -> bool {
+    unit >= 0xd800 && unit <= 0xdbff
+}
+
+fn is_low_surrogate(unit: u32) -> bool {
+    unit >= 0xdc00 && unit <= 0xdfff
+}
+
+// A backslash followed by a line break, which doesn't add anything to the
+// string it's in.
+pub fn line_continuation<I>(input: I) -> ParseResult<(), I>
+where
+    I: Stream<Item = char>
+{
+    token('\\')
+        .with(choice!(
+            try(string("\r\n")),
+            string("\n"),
+            string("\r"),
+            string("\u{2028}"),
+            string("\u{2029}")
+        ))
+        .with(value(()))
         .parse_stream(input)
 }
 
@@ -129,6 +310,9 @@ where
 mod tests {
     use super::*;
 
+    use parse_error::rsx_parse_error;
+    use parse_span_types::RSXSourceStream;
+
     #[test]
     pub fn test_open_tag() {
         assert_eq!(parser(open_tag).parse("").is_err(), true);
@@ -170,12 +354,24 @@ mod tests {
         assert_eq!(parser(sign).parse("a").is_err(), true);
     }
 
+    #[test]
+    pub fn test_digits() {
+        assert_eq!(env_parser(10, digits).parse("").is_err(), true);
+        assert_eq!(env_parser(10, digits).parse("_1").is_err(), true);
+        assert_eq!(env_parser(10, digits).parse("1_000").unwrap(), ("1_000".to_string(), ""));
+        assert_eq!(env_parser(10, digits).parse("0123a").unwrap(), ("0123".to_string(), "a"));
+        assert_eq!(env_parser(16, digits).parse("1fA_b").unwrap(), ("1fA_b".to_string(), ""));
+        assert_eq!(env_parser(8, digits).parse("178").unwrap(), ("17".to_string(), "8"));
+        assert_eq!(env_parser(2, digits).parse("1012").unwrap(), ("101".to_string(), "2"));
+    }
+
     #[test]
     pub fn test_frac() {
         assert_eq!(parser(frac).parse("").is_err(), true);
         assert_eq!(parser(frac).parse(" ").is_err(), true);
         assert_eq!(parser(frac).parse("1234").is_err(), true);
-        assert_eq!(parser(frac).parse(".1234").unwrap(), (1234, ""));
+        assert_eq!(parser(frac).parse(".1234").unwrap(), ("1234".to_string(), ""));
+        assert_eq!(parser(frac).parse(".05").unwrap(), ("05".to_string(), ""));
         assert_eq!(parser(frac).parse("a").is_err(), true);
     }
 
@@ -184,9 +380,9 @@ mod tests {
         assert_eq!(parser(exp).parse("").is_err(), true);
         assert_eq!(parser(exp).parse(" ").is_err(), true);
         assert_eq!(parser(exp).parse("1234").is_err(), true);
-        assert_eq!(parser(exp).parse("e1234").unwrap(), (1234, ""));
-        assert_eq!(parser(exp).parse("e+1234").unwrap(), (1234, ""));
-        assert_eq!(parser(exp).parse("e-1234").unwrap(), (-1234, ""));
+        assert_eq!(parser(exp).parse("e1234").unwrap(), ("e1234".to_string(), ""));
+        assert_eq!(parser(exp).parse("E+1234").unwrap(), ("E+1234".to_string(), ""));
+        assert_eq!(parser(exp).parse("e-1234").unwrap(), ("e-1234".to_string(), ""));
         assert_eq!(parser(exp).parse("a").is_err(), true);
     }
 
@@ -194,36 +390,53 @@ mod tests {
     pub fn test_integer() {
         assert_eq!(parser(integer).parse("").is_err(), true);
         assert_eq!(parser(integer).parse(" ").is_err(), true);
-        assert_eq!(parser(integer).parse("1").unwrap(), (1i64, ""));
-        assert_eq!(parser(integer).parse("1.2").unwrap(), (1i64, ".2"));
-        assert_eq!(parser(integer).parse("1e3").unwrap(), (1i64, "e3"));
-        assert_eq!(parser(integer).parse("1.2e3").unwrap(), (1i64, ".2e3"));
-        assert_eq!(parser(integer).parse("+1").unwrap(), (1i64, ""));
-        assert_eq!(parser(integer).parse("-1").unwrap(), (-1i64, ""));
+        assert_eq!(parser(integer).parse("1").unwrap(), ("1".to_string(), ""));
+        assert_eq!(parser(integer).parse("1.2").unwrap(), ("1".to_string(), ".2"));
+        assert_eq!(parser(integer).parse("1e3").unwrap(), ("1".to_string(), "e3"));
+        assert_eq!(parser(integer).parse("1.2e3").unwrap(), ("1".to_string(), ".2e3"));
+        assert_eq!(parser(integer).parse("+1").unwrap(), ("+1".to_string(), ""));
+        assert_eq!(parser(integer).parse("-1").unwrap(), ("-1".to_string(), ""));
+    }
+
+    #[test]
+    pub fn test_decimal() {
+        assert_eq!(parser(decimal).parse("").is_err(), true);
+        assert_eq!(parser(decimal).parse(" ").is_err(), true);
+        assert_eq!(parser(decimal).parse("-1").is_err(), true);
+        assert_eq!(parser(decimal).parse("1").unwrap(), ("1".to_string(), ""));
+        assert_eq!(parser(decimal).parse("1.2").unwrap(), ("1.2".to_string(), ""));
+        assert_eq!(parser(decimal).parse("0.05").unwrap(), ("0.05".to_string(), ""));
+        assert_eq!(parser(decimal).parse("1e3").unwrap(), ("1e3".to_string(), ""));
+        assert_eq!(parser(decimal).parse("1.2e-3").unwrap(), ("1.2e-3".to_string(), ""));
+        assert_eq!(parser(decimal).parse("1_000.5").unwrap(), ("1_000.5".to_string(), ""));
+        assert_eq!(parser(decimal).parse("1.").unwrap(), ("1".to_string(), "."));
+        assert_eq!(parser(decimal).parse("1em").unwrap(), ("1".to_string(), "em"));
     }
 
     #[test]
-    pub fn test_float_simple() {
-        assert_eq!(parser(float_simple).parse("").is_err(), true);
-        assert_eq!(parser(float_simple).parse(" ").is_err(), true);
-        assert_eq!(parser(float_simple).parse("1").is_err(), true);
-        assert_eq!(parser(float_simple).parse("1.2").unwrap(), (1.2f64, ""));
-        assert_eq!(parser(float_simple).parse("1e3").is_err(), true);
-        assert_eq!(parser(float_simple).parse("1.2e3").unwrap(), (1.2f64, "e3"));
-        assert_eq!(parser(float_simple).parse("+1.2").unwrap(), (1.2f64, ""));
-        assert_eq!(parser(float_simple).parse("-1.2").unwrap(), (-1.2f64, ""));
+    pub fn test_prefixed_integer() {
+        assert_eq!(parser(prefixed_integer).parse("").is_err(), true);
+        assert_eq!(parser(prefixed_integer).parse("0").is_err(), true);
+        assert_eq!(parser(prefixed_integer).parse("0x").is_err(), true);
+        assert_eq!(parser(prefixed_integer).parse("0x1F").unwrap(), ("0x1F".to_string(), ""));
+        assert_eq!(parser(prefixed_integer).parse("0O17").unwrap(), ("0O17".to_string(), ""));
+        assert_eq!(parser(prefixed_integer).parse("0b10_10").unwrap(), ("0b10_10".to_string(), ""));
+        assert_eq!(parser(prefixed_integer).parse("0b102").unwrap(), ("0b10".to_string(), "2"));
     }
 
     #[test]
-    pub fn test_float_exp() {
-        assert_eq!(parser(float_exp).parse("").is_err(), true);
-        assert_eq!(parser(float_exp).parse(" ").is_err(), true);
-        assert_eq!(parser(float_exp).parse("1").is_err(), true);
-        assert_eq!(parser(float_exp).parse("1.2").is_err(), true);
-        assert_eq!(parser(float_exp).parse("1e3").unwrap(), (1e3f64, ""));
-        assert_eq!(parser(float_exp).parse("1.2e3").unwrap(), (1.2e3f64, ""));
-        assert_eq!(parser(float_exp).parse("+1.2e3").unwrap(), (1.2e3f64, ""));
-        assert_eq!(parser(float_exp).parse("-1.2e3").unwrap(), (-1.2e3f64, ""));
+    pub fn test_number_suffix() {
+        assert_eq!(env_parser((10, false), number_suffix).parse("").is_err(), true);
+        assert_eq!(env_parser((10, false), number_suffix).parse("px").is_err(), true);
+        assert_eq!(env_parser((10, false), number_suffix).parse("i8").unwrap(), ("i8", ""));
+        assert_eq!(env_parser((10, false), number_suffix).parse("i128").unwrap(), ("i128", ""));
+        assert_eq!(env_parser((10, false), number_suffix).parse("u32").unwrap(), ("u32", ""));
+        assert_eq!(env_parser((10, false), number_suffix).parse("usize").unwrap(), ("usize", ""));
+        assert_eq!(env_parser((10, false), number_suffix).parse("f32").unwrap(), ("f32", ""));
+        assert_eq!(env_parser((10, true), number_suffix).parse("f64").unwrap(), ("f64", ""));
+        assert_eq!(env_parser((10, true), number_suffix).parse("u32").is_err(), true);
+        assert_eq!(env_parser((2, false), number_suffix).parse("f32").is_err(), true);
+        assert_eq!(env_parser((8, false), number_suffix).parse("u8").unwrap(), ("u8", ""));
     }
 
     #[test]
@@ -244,5 +457,43 @@ mod tests {
         assert_eq!(parser(escaped_character).parse(r#"\'"#).unwrap(), ('\'', ""));
         assert_eq!(parser(escaped_character).parse(r#"""#).is_err(), true);
         assert_eq!(parser(escaped_character).parse(r#"'"#).is_err(), true);
+        assert_eq!(parser(escaped_character).parse(r#"\\"#).unwrap(), ('\\', ""));
+        assert_eq!(parser(escaped_character).parse(r#"\v"#).unwrap(), ('\u{b}', ""));
+        assert_eq!(parser(escaped_character).parse(r#"\0"#).unwrap(), ('\u{0}', ""));
+        assert_eq!(parser(escaped_character).parse(r#"\x41"#).unwrap(), ('A', ""));
+        assert_eq!(parser(escaped_character).parse(r#"\xe9"#).unwrap(), ('\u{e9}', ""));
+        assert_eq!(parser(escaped_character).parse(r#"\u00e9"#).unwrap(), ('\u{e9}', ""));
+        assert_eq!(parser(escaped_character).parse(r#"\u{1F600}"#).unwrap(), ('\u{1f600}', ""));
+        assert_eq!(parser(escaped_character).parse(r#"\uD83D\uDE00"#).unwrap(), ('\u{1f600}', ""));
+        assert_eq!(parser(escaped_character).parse(r#"\u0041\u0042"#).unwrap(), ('A', r#"\u0042"#));
+    }
+
+    #[test]
+    pub fn test_escaped_character_invalid() {
+        let sources = [
+            r#"\q"#,
+            r#"\x4"#,
+            r#"\u12"#,
+            r#"\u{}"#,
+            r#"\u{1234567}"#,
+            r#"\u{110000}"#,
+            r#"\uD83D"#,
+            r#"\uDE00"#,
+            r#"\uD83D\u0041"#
+        ];
+        for source in sources.iter() {
+            let error = parser(escaped_character).parse(RSXSourceStream::new(source)).unwrap_err();
+            let error = rsx_parse_error(source, error);
+            assert_eq!(error.kind, RSXParseErrorKind::InvalidEscape);
+            assert_eq!(error.position.offset, 0);
+        }
+    }
+
+    #[test]
+    pub fn test_line_continuation() {
+        assert_eq!(parser(line_continuation).parse("\\\n").unwrap(), ((), ""));
+        assert_eq!(parser(line_continuation).parse("\\\r\nfoo").unwrap(), ((), "foo"));
+        assert_eq!(parser(line_continuation).parse("\\\u{2028}").unwrap(), ((), ""));
+        assert_eq!(parser(line_continuation).parse("\\n").is_err(), true);
     }
 }
@@ -0,0 +1,48 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RSXWhitespaceMode {
+    // Trim whitespace around line breaks, drop lines that are empty, and join
+    // the remaining ones with single spaces, like JSX does.
+    Jsx,
+    // Keep text exactly as written. Always used inside `<pre>` elements.
+    Preserve,
+    // Turn every run of whitespace into a single space, like HTML does. Text
+    // that's only whitespace spanning multiple lines is dropped.
+    Collapse
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RSXParseOptions {
+    // Decode HTML character references (e.g. `&amp;` or `&#169;`) in text and
+    // attribute strings, like JSX does.
+    pub decode_entities: bool,
+    pub whitespace: RSXWhitespaceMode,
+    // Check that every code block and spread block is a valid Rust expression
+    // after parsing, instead of leaving syntax errors to the Rust compiler.
+    pub validate_expressions: bool,
+    // Lex code blocks into Rust token trees while parsing, failing on code that
+    // isn't made of Rust tokens. Only turned off to parse code in another
+    // language, e.g. when converting JSX, which leaves the token trees empty.
+    pub lex_code_blocks: bool
+}
+
+impl Default for RSXParseOptions {
+    fn default() -> Self {
+        RSXParseOptions {
+            decode_entities: true,
+            whitespace: RSXWhitespaceMode::Jsx,
+            validate_expressions: false,
+            lex_code_blocks: true
+        }
+    }
+}
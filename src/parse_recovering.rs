@@ -0,0 +1,361 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::any::TypeId;
+
+use combine::{ParseResult, Parser};
+use combine::combinator::{parser, try};
+use combine::primitives::StreamOnce;
+
+use parse_attributes::rsx_attribute;
+use parse_attributes_types::RSXAttribute;
+use parse_children::{rsx_child_comments, rsx_text};
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_elements::{
+    rsx_closing_element_name,
+    rsx_element,
+    rsx_element_open,
+    rsx_fragment_closing_element,
+    rsx_fragment_opening_element,
+    rsx_named_fragment_opening_element
+};
+use parse_elements_types::{RSXElement, RSXNormalElement, RSXSelfClosingElement};
+use parse_error::{rsx_parse_error, rsx_trailing_input_error};
+use parse_error_types::{RSXDiagnostic, RSXErrorNode, RSXParseError, RSXParseErrorKind};
+use parse_external::rsx_code_block;
+use parse_js::js_whitespace;
+use parse_misc::{close_tag, closing_element_open_tag, self_closing_element_close_tag};
+use parse_span_types::{RSXPosition, RSXSourceStream, RSXSpan, RSXSpanTable, RSXStream};
+use parse_whitespace::rsx_children_options;
+
+// Closing tags are identified by the name of the element they're closing,
+// with `None` standing for fragments.
+pub type RSXClosingTagName = Option<String>;
+
+pub fn rsx_any_closing_element<I>(input: I) -> ParseResult<RSXClosingTagName, I>
+where
+    I: RSXStream
+{
+    choice!(
+        try(parser(rsx_fragment_closing_element)).map(|_| None),
+        parser(rsx_closing_element_name).map(|n| Some(n.to_string()))
+    ).parse_stream(input)
+}
+
+pub fn rsx_recovering_root(source: &str) -> (RSXElement, RSXSpanTable, Vec<RSXDiagnostic>) {
+    let mut recovery = RSXRecovery {
+        source,
+        diagnostics: vec![]
+    };
+
+    let input = rsx_skip_whitespace(RSXSourceStream::new(source));
+    let (element, input) = recovery.element(input, &[]);
+    let mut input = rsx_skip_whitespace(input);
+
+    if !input.as_str().is_empty() {
+        let error = rsx_trailing_input_error(&input);
+        let start = input.source_position();
+        while input.uncons().is_ok() {}
+        recovery.report(error, RSXSpan::new(start, input.source_position()));
+    }
+
+    (element, input.span_table(), recovery.diagnostics)
+}
+
+// Well formed subtrees are parsed by the regular parsers. Whenever those fail,
+// the subtree is walked by hand instead, reporting each error and replacing
+// the offending source with an `Error` placeholder node. Nodes built by hand
+// record their spans like the regular parsers would, so that the span table
+// still matches the tree.
+struct RSXRecovery<'a> {
+    source: &'a str,
+    diagnostics: Vec<RSXDiagnostic>
+}
+
+impl<'a> RSXRecovery<'a> {
+    fn report(&mut self, error: RSXParseError, span: RSXSpan) {
+        self.diagnostics.push(RSXDiagnostic { error, span });
+    }
+
+    fn element(
+        &mut self,
+        input: RSXSourceStream<'a>,
+        ancestors: &[RSXClosingTagName]
+    ) -> (RSXElement, RSXSourceStream<'a>) {
+        let error = match parser(rsx_element).parse(input.clone()) {
+            Ok(result) => return result,
+            Err(error) => error
+        };
+
+        let start = input.source_position();
+
+        // Fragments are parsed separately, since `<Fragment>` is only a
+        // fragment without attributes and its name isn't a node.
+        let fragment = parser(rsx_fragment_opening_element)
+            .parse(input.clone())
+            .map(|(_, rest)| (None, rest))
+            .or_else(|_| {
+                parser(rsx_named_fragment_opening_element)
+                    .parse(input.clone())
+                    .map(|(_, rest)| (Some("Fragment".to_string()), rest))
+            });
+        if let Ok((expected, input)) = fragment {
+            let opening = RSXSpan::new(start, input.source_position());
+            let (children, _, mut input) = self.children(input, &expected, opening, ancestors);
+            let span = RSXSpan::new(start, input.source_position());
+            input.record_span(TypeId::of::<RSXChildren>(), span);
+            return (RSXElement::Fragment(RSXChildren::from(children)), input);
+        }
+
+        let (name, input) = match parser(rsx_element_open).parse(input.clone()) {
+            Ok(opening) => opening,
+            Err(_) => {
+                let rest = rsx_resync(input);
+                let span = RSXSpan::new(start, rest.source_position());
+                self.report(rsx_parse_error(self.source, error), span);
+                return (RSXElement::Error(RSXErrorNode(span)), rest);
+            }
+        };
+
+        let (attributes, self_closing, mut input) = self.attributes(input);
+        if self_closing {
+            let span = RSXSpan::new(start, input.source_position());
+            input.record_span(TypeId::of::<RSXSelfClosingElement>(), span);
+            let element = RSXSelfClosingElement(name, attributes.into());
+            return (RSXElement::SelfClosing(element), input);
+        }
+
+        let opening = RSXSpan::new(start, input.source_position());
+        let expected = Some(name.to_string());
+        let options = input.options();
+        input.set_options(rsx_children_options(&name, options));
+        let (children, children_end, mut input) =
+            self.children(input, &expected, opening, ancestors);
+        input.set_options(options);
+
+        let span = RSXSpan::new(opening.end, children_end);
+        input.record_span(TypeId::of::<RSXChildren>(), span);
+        let span = RSXSpan::new(start, input.source_position());
+        input.record_span(TypeId::of::<RSXNormalElement>(), span);
+
+        let children = RSXChildren::from(children);
+        let element = RSXNormalElement(name, attributes.into(), children);
+        (RSXElement::Normal(element), input)
+    }
+
+    fn attributes(
+        &mut self,
+        input: RSXSourceStream<'a>
+    ) -> (Vec<RSXAttribute>, bool, RSXSourceStream<'a>) {
+        let mut attributes = vec![];
+        let mut input = rsx_skip_whitespace(input);
+
+        loop {
+            if let Ok((_, rest)) = parser(self_closing_element_close_tag).parse(input.clone()) {
+                return (attributes, true, rest);
+            }
+            if let Ok((_, rest)) = parser(close_tag).parse(input.clone()) {
+                return (attributes, false, rest);
+            }
+            if input.as_str().is_empty() || input.as_str().starts_with('<') {
+                // There's no telling where the children of an unclosed opening
+                // tag would end, so treat it as self-closing instead.
+                let position = input.source_position();
+                self.report(rsx_expected_error(&input, ">"), RSXSpan::new(position, position));
+                return (attributes, true, input);
+            }
+
+            match parser(rsx_attribute).parse(input.clone()) {
+                Ok((attribute, rest)) => {
+                    attributes.push(attribute);
+                    input = rsx_skip_whitespace(rest);
+                }
+                Err(error) => {
+                    let start = input.source_position();
+                    let rest = rsx_resync(input);
+                    let span = RSXSpan::new(start, rest.source_position());
+                    self.report(rsx_parse_error(self.source, error), span);
+                    attributes.push(RSXAttribute::Error(RSXErrorNode(span)));
+                    input = rsx_skip_whitespace(rest);
+                }
+            }
+        }
+    }
+
+    fn children(
+        &mut self,
+        input: RSXSourceStream<'a>,
+        expected: &RSXClosingTagName,
+        opening: RSXSpan,
+        ancestors: &[RSXClosingTagName]
+    ) -> (Vec<RSXChild>, RSXPosition, RSXSourceStream<'a>) {
+        let mut children = vec![];
+        let mut nested = ancestors.to_vec();
+        nested.push(expected.clone());
+        let mut input = input;
+
+        loop {
+            let position = input.source_position();
+
+            if input.as_str().is_empty() {
+                let span = RSXSpan::new(position, position);
+                self.report(rsx_expected_error(&input, &rsx_closing_tag(expected)), span);
+                children.push(RSXChild::Error(RSXErrorNode(span)));
+                return (children, position, input);
+            }
+
+            if parser(closing_element_open_tag).parse(input.clone()).is_ok() {
+                // Closing tag names aren't nodes, so only skip past them
+                // instead of keeping the spans parsing them recorded.
+                match parser(rsx_any_closing_element).parse(input.clone()) {
+                    Ok((ref found, ref rest)) if found == expected => {
+                        return (children, position, rsx_skip_to(input, rest));
+                    }
+                    Ok((found, rest)) => {
+                        let kind = RSXParseErrorKind::MismatchedClosingTag(opening);
+                        let error = RSXParseError::new(kind, position)
+                            .with_expected(rsx_closing_tag(expected))
+                            .with_found(rsx_closing_tag(&found));
+
+                        // Closing tags belonging to ancestors implicitly close
+                        // this element, any other closing tags are skipped.
+                        if ancestors.contains(&found) {
+                            let span = RSXSpan::new(position, position);
+                            self.report(error, span);
+                            children.push(RSXChild::Error(RSXErrorNode(span)));
+                            return (children, position, input);
+                        }
+
+                        let span = RSXSpan::new(position, rest.source_position());
+                        self.report(error, span);
+                        children.push(RSXChild::Error(RSXErrorNode(span)));
+                        input = rsx_skip_to(input, &rest);
+                    }
+                    Err(error) => {
+                        let mut rest = rsx_resync(input);
+                        if rest.as_str().starts_with('>') {
+                            let _ = rest.uncons();
+                        }
+                        let span = RSXSpan::new(position, rest.source_position());
+                        self.report(rsx_parse_error(self.source, error), span);
+                        children.push(RSXChild::Error(RSXErrorNode(span)));
+                        input = rest;
+                    }
+                }
+                continue;
+            }
+
+            if input.as_str().starts_with('{') {
+                match parser(rsx_code_block).parse(input.clone()) {
+                    Ok((expression, rest)) => {
+                        children.push(RSXChild::CodeBlock(expression));
+                        input = rsx_skip_child_comments(rest);
+                    }
+                    Err(error) => {
+                        let rest = rsx_resync(input);
+                        let span = RSXSpan::new(position, rest.source_position());
+                        self.report(rsx_parse_error(self.source, error), span);
+                        children.push(RSXChild::Error(RSXErrorNode(span)));
+                        input = rest;
+                    }
+                }
+                continue;
+            }
+
+            if input.as_str().starts_with('<') {
+                let (element, rest) = self.element(input, &nested);
+                children.push(match element {
+                    RSXElement::Error(error) => RSXChild::Error(error),
+                    element => RSXChild::Element(element)
+                });
+                input = rsx_skip_child_comments(rest);
+                continue;
+            }
+
+            match parser(rsx_text).parse(input.clone()) {
+                Ok((text, rest)) => {
+                    if !text.0.is_empty() {
+                        children.push(RSXChild::Text(text));
+                    }
+                    input = rest;
+                }
+                Err(error) => {
+                    // Only stray `>` and `}` characters aren't valid text.
+                    let mut rest = input.clone();
+                    let _ = rest.uncons();
+                    let span = RSXSpan::new(position, rest.source_position());
+                    self.report(rsx_parse_error(self.source, error), span);
+                    children.push(RSXChild::Error(RSXErrorNode(span)));
+                    input = rest;
+                }
+            }
+        }
+    }
+}
+
+fn rsx_closing_tag(name: &RSXClosingTagName) -> String {
+    format!("</{}>", name.as_ref().map(|n| n.as_str()).unwrap_or(""))
+}
+
+fn rsx_expected_error(input: &RSXSourceStream, expected: &str) -> RSXParseError {
+    let (kind, found) = match input.as_str().chars().next() {
+        Some(c) => (RSXParseErrorKind::UnexpectedToken, c.to_string()),
+        None => (RSXParseErrorKind::UnexpectedEof, "end of input".to_string())
+    };
+    RSXParseError::new(kind, input.source_position())
+        .with_expected(expected)
+        .with_found(found)
+}
+
+fn rsx_skip_whitespace(input: RSXSourceStream) -> RSXSourceStream {
+    match parser(js_whitespace).parse(input.clone()) {
+        Ok((_, rest)) => rest,
+        Err(_) => input
+    }
+}
+
+fn rsx_skip_child_comments(input: RSXSourceStream) -> RSXSourceStream {
+    match parser(rsx_child_comments).parse(input.clone()) {
+        Ok((_, rest)) => rest,
+        Err(_) => input
+    }
+}
+
+// Advances `input` up to where `rest` is, without the spans recorded on the
+// way there.
+fn rsx_skip_to<'a>(
+    mut input: RSXSourceStream<'a>,
+    rest: &RSXSourceStream<'a>
+) -> RSXSourceStream<'a> {
+    while input.source_position() < rest.source_position() && input.uncons().is_ok() {}
+    input
+}
+
+// Skips over the offending character and everything up to the next `<` or
+// `>`, or up to and including the next `}`.
+fn rsx_resync(mut input: RSXSourceStream) -> RSXSourceStream {
+    match input.uncons() {
+        Ok('}') | Err(_) => return input,
+        Ok(_) => {}
+    }
+    loop {
+        match input.as_str().chars().next() {
+            Some('}') => {
+                let _ = input.uncons();
+                return input;
+            }
+            Some('<') | Some('>') | None => return input,
+            Some(_) => {
+                let _ = input.uncons();
+            }
+        }
+    }
+}
@@ -0,0 +1,456 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use syn::{self, Block, Expr, ExprKind, FnArg, Mac, Pat, Path, Stmt};
+
+use parse_attributes_types::{RSXAttribute, RSXAttributeValue};
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_elements_types::RSXElement;
+use parse_external::rsx_code_with_holes;
+use parse_external_types::RSXParsedExpression;
+use parse_references_types::{RSXExpressionKind, RSXExpressionReferences};
+use parse_span_types::RSXSpans;
+
+// Finds the free variables and field paths read by every code block. Code is
+// only analyzed syntactically, so a few assumptions are made: paths with more
+// than one segment, names starting with an uppercase letter and paths being
+// called are items (e.g. constants, enum variants or functions), not variables.
+// Code blocks which aren't valid Rust expressions have no references, though
+// the elements inside them are still analyzed. Code blocks are returned in
+// source order, each with its span, so that blocks without one don't collide.
+pub fn rsx_element_references(
+    element: &RSXElement,
+    spans: &RSXSpans
+) -> Vec<RSXExpressionReferences> {
+    let mut analyzer = RSXReferenceAnalyzer {
+        spans,
+        references: vec![],
+        blocks: vec![],
+        holes: vec![],
+        scope: vec![]
+    };
+    analyzer.element(element);
+    analyzer.references
+}
+
+struct RSXReferenceAnalyzer<'a> {
+    spans: &'a RSXSpans<'a>,
+    references: Vec<RSXExpressionReferences>,
+    // Indices of the code blocks being analyzed, innermost last. Every
+    // reference is added to all of them.
+    blocks: Vec<usize>,
+    // The hole identifier prefix and the elements of every code block being
+    // analyzed, innermost last.
+    holes: Vec<(String, Vec<&'a RSXElement>)>,
+    // Names bound at the current point, by any of the code blocks.
+    scope: Vec<String>
+}
+
+impl<'a> RSXReferenceAnalyzer<'a> {
+    fn element(&mut self, element: &'a RSXElement) {
+        match element {
+            &RSXElement::SelfClosing(ref element) => {
+                for attribute in (element.1).0.iter() {
+                    self.attribute(attribute);
+                }
+            }
+            &RSXElement::Normal(ref element) => {
+                for attribute in (element.1).0.iter() {
+                    self.attribute(attribute);
+                }
+                self.children(&element.2);
+            }
+            &RSXElement::Fragment(ref children) => self.children(children),
+            &RSXElement::Error(_) => {}
+        }
+    }
+
+    fn attribute(&mut self, attribute: &'a RSXAttribute) {
+        match attribute {
+            &RSXAttribute::Named(_, RSXAttributeValue::Element(ref element)) => {
+                self.element(element)
+            }
+            &RSXAttribute::Named(ref name, RSXAttributeValue::CodeBlock(ref expression)) => {
+                self.expression(expression, RSXExpressionKind::Attribute(name.to_string()))
+            }
+            &RSXAttribute::Spread(ref expression) => {
+                self.expression(expression, RSXExpressionKind::Spread)
+            }
+            _ => {}
+        }
+    }
+
+    fn children(&mut self, children: &'a RSXChildren) {
+        for child in children.0.iter() {
+            match child {
+                &RSXChild::Element(ref element) => self.element(element),
+                &RSXChild::CodeBlock(ref expression) => {
+                    self.expression(expression, RSXExpressionKind::Child)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &'a RSXParsedExpression, kind: RSXExpressionKind) {
+        let (code, prefix) = rsx_code_with_holes(expression);
+        // The block's entry is added before analyzing it, which keeps blocks
+        // nested in its elements after it.
+        self.blocks.push(self.references.len());
+        let span = expression.span(self.spans);
+        self.references.push(RSXExpressionReferences::new(kind, span));
+        self.holes.push((prefix, expression.holes().collect()));
+
+        match syn::parse_expr(&format!("{{{}}}", code)) {
+            Ok(expr) => self.expr(&expr),
+            Err(_) => {
+                for element in expression.holes() {
+                    self.element(element);
+                }
+            }
+        }
+
+        self.holes.pop();
+        self.blocks.pop();
+    }
+
+    fn scoped<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Self)
+    {
+        let len = self.scope.len();
+        f(self);
+        self.scope.truncate(len);
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.scoped(|this| {
+            for stmt in &block.stmts {
+                match *stmt {
+                    Stmt::Local(ref local) => {
+                        if let Some(ref init) = local.init {
+                            this.expr(init);
+                        }
+                        this.pat(&local.pat);
+                    }
+                    Stmt::Expr(ref expr) | Stmt::Semi(ref expr) => this.expr(expr),
+                    Stmt::Mac(ref mac) => this.mac(&mac.0),
+                    Stmt::Item(_) => {}
+                }
+            }
+        })
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    fn optional_expr(&mut self, expr: &Option<Box<Expr>>) {
+        if let Some(ref expr) = *expr {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr.node {
+            ExprKind::Path(None, ref path) => self.path(path),
+            ExprKind::Field(..) | ExprKind::TupField(..) => self.field(expr),
+            ExprKind::Call(ref callee, ref args) => {
+                match callee.node {
+                    ExprKind::Path(..) => {}
+                    _ => self.expr(callee)
+                }
+                self.exprs(args);
+            }
+            ExprKind::MethodCall(_, _, ref args) |
+            ExprKind::Array(ref args) |
+            ExprKind::Tup(ref args) => self.exprs(args),
+            ExprKind::Box(ref expr) |
+            ExprKind::Unary(_, ref expr) |
+            ExprKind::Cast(ref expr, _) |
+            ExprKind::Type(ref expr, _) |
+            ExprKind::AddrOf(_, ref expr) |
+            ExprKind::Paren(ref expr) |
+            ExprKind::Try(ref expr) => self.expr(expr),
+            ExprKind::InPlace(ref left, ref right) |
+            ExprKind::Binary(_, ref left, ref right) |
+            ExprKind::Assign(ref left, ref right) |
+            ExprKind::AssignOp(_, ref left, ref right) |
+            ExprKind::Index(ref left, ref right) |
+            ExprKind::Repeat(ref left, ref right) => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Range(ref start, ref end, _) => {
+                self.optional_expr(start);
+                self.optional_expr(end);
+            }
+            ExprKind::Break(_, ref expr) | ExprKind::Ret(ref expr) => self.optional_expr(expr),
+            ExprKind::If(ref cond, ref then, ref otherwise) => {
+                self.expr(cond);
+                self.block(then);
+                self.optional_expr(otherwise);
+            }
+            ExprKind::IfLet(ref pat, ref expr, ref then, ref otherwise) => {
+                self.expr(expr);
+                self.scoped(|this| {
+                    this.pat(pat);
+                    this.block(then);
+                });
+                self.optional_expr(otherwise);
+            }
+            ExprKind::While(ref cond, ref body, _) => {
+                self.expr(cond);
+                self.block(body);
+            }
+            ExprKind::WhileLet(ref pat, ref expr, ref body, _) |
+            ExprKind::ForLoop(ref pat, ref expr, ref body, _) => {
+                self.expr(expr);
+                self.scoped(|this| {
+                    this.pat(pat);
+                    this.block(body);
+                });
+            }
+            ExprKind::Loop(ref body, _) | ExprKind::Block(_, ref body) => self.block(body),
+            ExprKind::Match(ref expr, ref arms) => {
+                self.expr(expr);
+                for arm in arms {
+                    self.scoped(|this| {
+                        for pat in &arm.pats {
+                            this.pat(pat);
+                        }
+                        this.optional_expr(&arm.guard);
+                        this.expr(&arm.body);
+                    });
+                }
+            }
+            ExprKind::Closure(_, ref decl, ref body) => {
+                self.scoped(|this| {
+                    for input in &decl.inputs {
+                        if let FnArg::Captured(ref pat, _) = *input {
+                            this.pat(pat);
+                        }
+                    }
+                    this.expr(body);
+                });
+            }
+            ExprKind::Struct(_, ref fields, ref base) => {
+                for field in fields {
+                    self.expr(&field.expr);
+                }
+                self.optional_expr(base);
+            }
+            ExprKind::Mac(ref mac) => self.mac(mac),
+            ExprKind::Path(Some(_), _) | ExprKind::Lit(_) | ExprKind::Continue(_) => {}
+        }
+    }
+
+    // Macro arguments are arbitrary tokens, but most macros used in expressions
+    // (e.g. `format!` or `vec!`) take something that also parses as an array.
+    fn mac(&mut self, mac: &Mac) {
+        let tts = mac.tts.iter();
+        if let Ok(expr) = syn::parse_expr(&format!("[{}]", quote! { #(#tts)* })) {
+            self.expr(&expr);
+        }
+    }
+
+    fn pat(&mut self, pat: &Pat) {
+        match *pat {
+            Pat::Ident(_, ref ident, ref subpattern) => {
+                self.scope.push(ident.as_ref().to_string());
+                if let Some(ref pat) = *subpattern {
+                    self.pat(pat);
+                }
+            }
+            Pat::Struct(_, ref fields, _) => {
+                for field in fields {
+                    self.pat(&field.pat);
+                }
+            }
+            Pat::TupleStruct(_, ref pats, _) | Pat::Tuple(ref pats, _) => {
+                for pat in pats {
+                    self.pat(pat);
+                }
+            }
+            Pat::Slice(ref before, ref middle, ref after) => {
+                for pat in before.iter().chain(middle.iter().map(|pat| &**pat)).chain(after) {
+                    self.pat(pat);
+                }
+            }
+            Pat::Box(ref pat) | Pat::Ref(ref pat, _) => self.pat(pat),
+            Pat::Wild | Pat::Path(..) | Pat::Lit(_) | Pat::Range(..) | Pat::Mac(_) => {}
+        }
+    }
+
+    fn path(&mut self, path: &Path) {
+        if let Some(element) = self.hole(path) {
+            self.element(element);
+        } else if let Some(name) = self.free_variable(path) {
+            for &block in &self.blocks {
+                self.references[block].variables.insert(name.clone());
+            }
+        }
+    }
+
+    fn field(&mut self, expr: &Expr) {
+        let mut fields = vec![];
+        let mut base = expr;
+        loop {
+            match base.node {
+                ExprKind::Field(ref expr, ref ident) => {
+                    fields.push(ident.as_ref().to_string());
+                    base = &**expr;
+                }
+                ExprKind::TupField(ref expr, index) => {
+                    fields.push(index.to_string());
+                    base = &**expr;
+                }
+                ExprKind::Paren(ref expr) => base = &**expr,
+                _ => break
+            }
+        }
+
+        if let ExprKind::Path(None, ref path) = base.node {
+            if let Some(name) = self.free_variable(path) {
+                fields.push(name);
+                fields.reverse();
+                let field_path = fields.join(".");
+                for &block in &self.blocks {
+                    self.references[block].field_paths.insert(field_path.clone());
+                }
+            }
+        }
+        self.expr(base);
+    }
+
+    fn hole(&self, path: &Path) -> Option<&'a RSXElement> {
+        match (rsx_variable_name(path), self.holes.last()) {
+            (Some(name), Some(&(ref prefix, ref elements))) if name.starts_with(&**prefix) => {
+                let index = name[prefix.len()..].parse::<usize>().ok();
+                index.and_then(|index| elements.get(index).cloned())
+            }
+            _ => None
+        }
+    }
+
+    fn free_variable(&self, path: &Path) -> Option<String> {
+        match rsx_variable_name(path) {
+            Some(name) if self.hole(path).is_none() && !self.scope.iter().any(|b| b == name) => {
+                Some(name.to_string())
+            }
+            _ => None
+        }
+    }
+}
+
+fn rsx_variable_name(path: &Path) -> Option<&str> {
+    if path.global || path.segments.len() != 1 || !path.segments[0].parameters.is_empty() {
+        return None;
+    }
+    let name = path.segments[0].ident.as_ref();
+    if name.starts_with(|c: char| c.is_uppercase()) {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use combine::Parser;
+    use combine::combinator::parser;
+
+    use parse_rsx::rsx_element_ignoring_ws;
+    use parse_span_types::RSXSpan;
+    use test_helpers::with_spans;
+
+    fn references(source: &str) -> Vec<(RSXExpressionKind, Vec<String>, Vec<String>)> {
+        with_spans(source, rsx_element_references)
+            .into_iter()
+            .map(|r| {
+                let variables = r.variables.into_iter().collect();
+                (r.kind, variables, r.field_paths.into_iter().collect())
+            })
+            .collect()
+    }
+
+    fn strings(strings: &[&str]) -> Vec<String> {
+        strings.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    pub fn test_rsx_references() {
+        let source = "<div class={props.class} {...rest}>\
+                      { props.user.name.len() }{ count + 1 }{ (a.0).b }\
+                      </div>";
+        assert_eq!(
+            references(source),
+            vec![
+                (
+                    RSXExpressionKind::Attribute("class".into()),
+                    strings(&["props"]),
+                    strings(&["props.class"])
+                ),
+                (RSXExpressionKind::Spread, strings(&["rest"]), strings(&[])),
+                (RSXExpressionKind::Child, strings(&["props"]), strings(&["props.user.name"])),
+                (RSXExpressionKind::Child, strings(&["count"]), strings(&[])),
+                (RSXExpressionKind::Child, strings(&["a"]), strings(&["a.0.b"])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_references_scopes() {
+        let source = "<ul>{ items.iter().map(|(i, item)| <li key={i}>{ item.label }</li>) }\
+                      { let x = y; match x { Some(z) => z + w, None => MAX } }\
+                      { foo::bar(baz) }</ul>";
+        assert_eq!(
+            references(source),
+            vec![
+                (RSXExpressionKind::Child, strings(&["items"]), strings(&[])),
+                (RSXExpressionKind::Attribute("key".into()), strings(&[]), strings(&[])),
+                (RSXExpressionKind::Child, strings(&[]), strings(&[])),
+                (RSXExpressionKind::Child, strings(&["w", "y"]), strings(&[])),
+                (RSXExpressionKind::Child, strings(&["baz"]), strings(&[])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_references_nested() {
+        let source = "<div>{ if show { <b>{ format!(\"{}\", user.name) }</b> } else { <i/> } }\
+                      </div>";
+        assert_eq!(
+            references(source),
+            vec![
+                (RSXExpressionKind::Child, strings(&["show", "user"]), strings(&["user.name"])),
+                (RSXExpressionKind::Child, strings(&["user"]), strings(&["user.name"])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_references_without_spans() {
+        let (element, _) = parser(rsx_element_ignoring_ws)
+            .parse("<div>{ a }{ b }<i>{ c }</i></div>")
+            .unwrap();
+        let references = rsx_element_references(&element, &RSXSpans::default());
+        assert_eq!(
+            references.iter().map(|r| r.variables.iter().collect()).collect::<Vec<Vec<_>>>(),
+            vec![vec!["a"], vec!["b"], vec!["c"]]
+        );
+        assert_eq!(references.iter().all(|r| r.span == RSXSpan::default()), true);
+    }
+}
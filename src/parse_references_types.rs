@@ -0,0 +1,48 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::collections::BTreeSet;
+
+use parse_span_types::RSXSpan;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RSXExpressionKind {
+    Child,
+    // Carries the name of the attribute whose value is the code block.
+    Attribute(String),
+    Spread
+}
+
+// What a code block reads from its surroundings. Names bound inside the block,
+// or by an enclosing code block (e.g. closure arguments around an element), are
+// not included. References made by code blocks in elements nested inside this
+// one count as this block's references too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSXExpressionReferences {
+    pub kind: RSXExpressionKind,
+    // The code block's span, or a default span when the tree has none.
+    pub span: RSXSpan,
+    pub variables: BTreeSet<String>,
+    // Chains of field accesses on variables, like `props.user.name`. Only the
+    // longest chain is kept, not every prefix of it.
+    pub field_paths: BTreeSet<String>
+}
+
+impl RSXExpressionReferences {
+    pub fn new(kind: RSXExpressionKind, span: RSXSpan) -> Self {
+        RSXExpressionReferences {
+            kind,
+            span,
+            variables: BTreeSet::new(),
+            field_paths: BTreeSet::new()
+        }
+    }
+}
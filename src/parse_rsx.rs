@@ -9,19 +9,20 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
-use combine::{ParseResult, Parser, Stream};
+use combine::{ParseResult, Parser};
 use combine::combinator::{optional, parser};
 
+use parse_cst::rsx_whitespace;
 use parse_elements::rsx_element;
 use parse_elements_types::RSXElement;
-use parse_js::js_whitespace;
+use parse_span_types::RSXStream;
 
 pub fn rsx_element_ignoring_ws<I>(input: I) -> ParseResult<RSXElement, I>
 where
-    I: Stream<Item = char>
+    I: RSXStream
 {
-    optional(parser(js_whitespace))
-        .with(parser(rsx_element).skip(parser(js_whitespace)))
+    optional(parser(rsx_whitespace))
+        .with(parser(rsx_element).skip(parser(rsx_whitespace)))
         .parse_stream(input)
 }
 
@@ -32,8 +33,8 @@ mod tests {
     use parse_attributes_types::{RSXAttribute, RSXAttributeName, RSXAttributeValue, RSXAttributes};
     use parse_children_types::{RSXChild, RSXChildren, RSXText};
     use parse_elements_types::{RSXElement, RSXElementName, RSXNormalElement, RSXSelfClosingElement};
-    use parse_external_placeholders::RSXElementPlaceholder;
     use parse_external_types::RSXParsedExpression;
+    use parse_js_types::JSNumberKind;
 
     #[test]
     pub fn test_rsx_simple_expression() {
@@ -105,7 +106,7 @@ mod tests {
                         ),
                         RSXAttribute::Named(
                             RSXAttributeName::Name("fourth".into()),
-                            RSXAttributeValue::Number(1f64.into())
+                            RSXAttributeValue::Number(("1", JSNumberKind::Integer).into())
                         ),
                         RSXAttribute::Named(
                             RSXAttributeName::Name("fifth".into()),
@@ -125,17 +126,15 @@ mod tests {
                         ),
                         RSXAttribute::Named(
                             RSXAttributeName::Name("ninth".into()),
-                            RSXAttributeValue::CodeBlock(RSXParsedExpression {
-                                tokens: "6 + 7 - 8 * 9 / 10".into(),
-                                elements: vec![]
-                            })
+                            RSXAttributeValue::CodeBlock(RSXParsedExpression::from(
+                                "6 + 7 - 8 * 9 / 10"
+                            ))
                         ),
                         RSXAttribute::Named(
                             RSXAttributeName::Name("tenth".into()),
-                            RSXAttributeValue::CodeBlock(RSXParsedExpression {
-                                tokens: "|e: Event| { println!(\"{:?}\", e); }".into(),
-                                elements: vec![]
-                            })
+                            RSXAttributeValue::CodeBlock(RSXParsedExpression::from(
+                                "|e: Event| { println!(\"{:?}\", e); }"
+                            ))
                         ),
                     ]),
                     RSXChildren::from(vec![
@@ -149,47 +148,39 @@ mod tests {
                             RSXAttributes::from(vec![]),
                             RSXChildren::from(vec![RSXChild::Text(RSXText("world".into()))])
                         ))),
-                        RSXChild::CodeBlock(RSXParsedExpression {
-                            tokens: r#"
+                        RSXChild::CodeBlock(RSXParsedExpression::from((
+                            vec![
+                                r#"
                                 if foo {
-                                    /* rsx:15848556381555908996 */
+                                    "#,
+                                r#"
                                 } else {
-                                    /* rsx:13987966085338848396 */
+                                    "#,
+                                r#"
                                 }
-                            "#.into(),
-                            elements: vec![
-                                (
-                                    RSXElementPlaceholder::dummy(),
-                                    RSXElement::Normal(RSXNormalElement(
-                                        RSXElementName::Name("first".into()),
-                                        RSXAttributes::from(vec![]),
-                                        RSXChildren::from(vec![
-                                            RSXChild::Text(RSXText("lorem".into())),
-                                            RSXChild::CodeBlock(RSXParsedExpression {
-                                                tokens: " 1 + 2 ".into(),
-                                                elements: vec![]
-                                            }),
-                                            RSXChild::Text(RSXText("ipsum".into())),
-                                        ])
-                                    ))
-                                ),
-                                (
-                                    RSXElementPlaceholder::dummy(),
-                                    RSXElement::Normal(RSXNormalElement(
-                                        RSXElementName::Name("second".into()),
-                                        RSXAttributes::from(vec![]),
-                                        RSXChildren::from(vec![
-                                            RSXChild::Text(RSXText("dolor".into())),
-                                            RSXChild::CodeBlock(RSXParsedExpression {
-                                                tokens: " 3 + 4 ".into(),
-                                                elements: vec![]
-                                            }),
-                                            RSXChild::Text(RSXText("sit".into())),
-                                        ])
-                                    ))
-                                ),
+                            "#,
+                            ],
+                            vec![
+                                RSXElement::Normal(RSXNormalElement(
+                                    RSXElementName::Name("first".into()),
+                                    RSXAttributes::from(vec![]),
+                                    RSXChildren::from(vec![
+                                        RSXChild::Text(RSXText("lorem ".into())),
+                                        RSXChild::CodeBlock(RSXParsedExpression::from(" 1 + 2 ")),
+                                        RSXChild::Text(RSXText(" ipsum".into())),
+                                    ])
+                                )),
+                                RSXElement::Normal(RSXNormalElement(
+                                    RSXElementName::Name("second".into()),
+                                    RSXAttributes::from(vec![]),
+                                    RSXChildren::from(vec![
+                                        RSXChild::Text(RSXText("dolor ".into())),
+                                        RSXChild::CodeBlock(RSXParsedExpression::from(" 3 + 4 ")),
+                                        RSXChild::Text(RSXText(" sit".into())),
+                                    ])
+                                )),
                             ]
-                        }),
+                        ))),
                         RSXChild::Element(RSXElement::Normal(RSXNormalElement(
                             RSXElementName::Name("ul".into()),
                             RSXAttributes::from(vec![]),
@@ -260,10 +251,7 @@ mod tests {
                     RSXAttributes::from(vec![
                         RSXAttribute::Named(
                             RSXAttributeName::Name("show".into()),
-                            RSXAttributeValue::CodeBlock(RSXParsedExpression {
-                                tokens: "props.visible".into(),
-                                elements: vec![]
-                            })
+                            RSXAttributeValue::CodeBlock(RSXParsedExpression::from("props.visible"))
                         ),
                     ]),
                     RSXChildren::from(vec![
@@ -273,24 +261,21 @@ mod tests {
                             RSXAttributes::from(vec![
                                 RSXAttribute::Named(
                                     RSXAttributeName::Name("icon".into()),
-                                    RSXAttributeValue::CodeBlock(RSXParsedExpression {
-                                        tokens: "props.menu.icon".into(),
-                                        elements: vec![]
-                                    })
+                                    RSXAttributeValue::CodeBlock(RSXParsedExpression::from(
+                                        "props.menu.icon"
+                                    ))
                                 ),
                                 RSXAttribute::Named(
                                     RSXAttributeName::Name("onHide".into()),
-                                    RSXAttributeValue::CodeBlock(RSXParsedExpression {
-                                        tokens: "|e| println!(\"{:?}\", e)".into(),
-                                        elements: vec![]
-                                    })
+                                    RSXAttributeValue::CodeBlock(RSXParsedExpression::from(
+                                        "|e| println!(\"{:?}\", e)"
+                                    ))
                                 ),
                                 RSXAttribute::Named(
                                     RSXAttributeName::Name("onShow".into()),
-                                    RSXAttributeValue::CodeBlock(RSXParsedExpression {
-                                        tokens: "|e| println!(\"{:?}\", e)".into(),
-                                        elements: vec![]
-                                    })
+                                    RSXAttributeValue::CodeBlock(RSXParsedExpression::from(
+                                        "|e| println!(\"{:?}\", e)"
+                                    ))
                                 ),
                             ]),
                             RSXChildren::from(vec![
@@ -301,41 +286,39 @@ mod tests {
                                         RSXChild::Text(RSXText("Do Something".into())),
                                     ])
                                 ))),
-                                RSXChild::CodeBlock(RSXParsedExpression {
-                                    tokens: r#"
+                                RSXChild::CodeBlock(RSXParsedExpression::from((
+                                    vec![
+                                        r#"
                                     if should_do_something_fun() {
-                                        /* rsx:15848556381555908996 */
+                                        "#,
+                                        r#"
                                     } else {
-                                        /* rsx:13987966085338848396 */
+                                        "#,
+                                        r#"
                                     }
-                                "#.into(),
-                                    elements: vec![
-                                        (
-                                            RSXElementPlaceholder::dummy(),
-                                            RSXElement::Normal(RSXNormalElement(
-                                                RSXElementName::Name("MenuItem".into()),
-                                                RSXAttributes::from(vec![]),
-                                                RSXChildren::from(vec![
-                                                    RSXChild::Text(RSXText(
-                                                        "Do Something Fun!".into()
-                                                    )),
-                                                ])
-                                            ))
-                                        ),
-                                        (
-                                            RSXElementPlaceholder::dummy(),
-                                            RSXElement::Normal(RSXNormalElement(
-                                                RSXElementName::Name("MenuItem".into()),
-                                                RSXAttributes::from(vec![]),
-                                                RSXChildren::from(vec![
-                                                    RSXChild::Text(RSXText(
-                                                        "Do Something Else".into()
-                                                    )),
-                                                ])
-                                            ))
-                                        ),
+                                "#,
+                                    ],
+                                    vec![
+                                        RSXElement::Normal(RSXNormalElement(
+                                            RSXElementName::Name("MenuItem".into()),
+                                            RSXAttributes::from(vec![]),
+                                            RSXChildren::from(vec![
+                                                RSXChild::Text(RSXText(
+                                                    "Do Something Fun!".into()
+                                                )),
+                                            ])
+                                        )),
+                                        RSXElement::Normal(RSXNormalElement(
+                                            RSXElementName::Name("MenuItem".into()),
+                                            RSXAttributes::from(vec![]),
+                                            RSXChildren::from(vec![
+                                                RSXChild::Text(RSXText(
+                                                    "Do Something Else".into()
+                                                )),
+                                            ])
+                                        )),
                                     ]
-                                }),
+                                ))),
                             ])
                         ))),
                     ])
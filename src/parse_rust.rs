@@ -9,44 +9,351 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
+use std::char;
+use std::iter;
+
 use combine::{ParseResult, Parser, Stream};
-use combine::combinator::{between, many, none_of, parser, token, try};
+use combine::char::{alpha_num, hex_digit, letter, space, string};
+use combine::combinator::{
+    any,
+    between,
+    env_parser,
+    many,
+    none_of,
+    not_followed_by,
+    one_of,
+    parser,
+    satisfy,
+    skip_many,
+    token,
+    tokens,
+    try,
+    value
+};
+use combine::primitives::Consumed;
 
-use parse_js::{js_comment, js_whitespace};
-use parse_misc::escaped_character;
-use parse_rust_types::{RSChar, RSString};
+use parse_error::rsx_error_hint;
+use parse_error_types::{RSXParseError, RSXParseErrorKind};
+use parse_misc::{hex_escape, hex_value};
+use parse_rust_types::{
+    RSByteChar,
+    RSByteString,
+    RSCString,
+    RSChar,
+    RSComment,
+    RSLifetime,
+    RSRawString,
+    RSString
+};
+use parse_span_types::RSXStream;
 
 pub fn rs_char<I>(input: I) -> ParseResult<RSChar, I>
+where
+    I: RSXStream
+{
+    between(
+        token('\''),
+        token('\''),
+        choice!(parser(rs_escape), none_of("\\'\n\r\t".chars()).map(|c| (c, c.to_string())))
+    ).map(|(value, lexeme)| {
+        let lexeme = format!("'{}'", lexeme);
+        RSChar { value, lexeme }
+    })
+        .parse_stream(input)
+}
+
+// Like in rustc, a quote followed by an identifier starts a lifetime or a loop
+// label, unless the identifier is a single character followed by a quote.
+pub fn rs_lifetime<I>(input: I) -> ParseResult<RSLifetime, I>
 where
     I: Stream<Item = char>
 {
-    between(token('\''), token('\''), choice!(try(parser(escaped_character)), none_of("'".chars())))
-        .map(RSChar)
+    token('\'')
+        .with((choice!(letter(), token('_')), many(choice!(alpha_num(), token('_')))))
+        .skip(not_followed_by(token('\'')))
+        .map(|(first, rest): (char, String)| RSLifetime(format!("{}{}", first, rest)))
         .parse_stream(input)
 }
 
 pub fn rs_string<I>(input: I) -> ParseResult<RSString, I>
+where
+    I: RSXStream
+{
+    between(token('"'), token('"'), parser(rs_string_characters))
+        .map(|(value, lexeme)| {
+            let lexeme = format!("\"{}\"", lexeme);
+            RSString { value, lexeme }
+        })
+        .parse_stream(input)
+}
+
+// Returns the decoded contents of a string along with the contents as written.
+pub fn rs_string_characters<I>(input: I) -> ParseResult<(String, String), I>
+where
+    I: RSXStream
+{
+    (
+        parser(rs_line_continuations),
+        many(
+            (
+                choice!(parser(rs_escape), none_of("\\\"".chars()).map(|c| (c, c.to_string()))),
+                parser(rs_line_continuations)
+            ).map(|((value, escape), continuations)| (value, escape + &continuations))
+        )
+    ).map(|(continuations, characters): (String, Vec<(char, String)>)| {
+        let value: String = characters.iter().map(|&(value, _)| value).collect();
+        let lexeme: String = iter::once(continuations)
+            .chain(characters.into_iter().map(|(_, lexeme)| lexeme))
+            .collect();
+        (value, lexeme)
+    })
+        .parse_stream(input)
+}
+
+pub fn rs_c_string<I>(input: I) -> ParseResult<RSCString, I>
+where
+    I: RSXStream
+{
+    token('c')
+        .with(parser(rs_string))
+        .map(|string| {
+            let lexeme = format!("c{}", string.lexeme);
+            RSCString { value: string.value, lexeme }
+        })
+        .parse_stream(input)
+}
+
+// Rust escapes are narrower than JS ones: there's no `\b`, `\f`, `\v` or
+// `\uXXXX`, `\x` only goes up to `\x7F`, and `\u{...}` can have underscores.
+// Returns the character along with the escape as written.
+pub fn rs_escape<I>(input: I) -> ParseResult<(char, String), I>
+where
+    I: RSXStream
+{
+    let start = input.source_position();
+    let invalid = RSXParseError::new(RSXParseErrorKind::InvalidEscape, start);
+    let invalid_digits = invalid.clone();
+    token('\\')
+        .with(choice!(
+            one_of("nrt0\\'\"".chars()).map(|c| {
+                let value = match c {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    c => c
+                };
+                (u32::from(value), c.to_string())
+            }),
+            (token('x'), one_of("01234567".chars()), hex_digit())
+                .map(|(_, high, low)| (hex_value(&[high, low]), format!("x{}{}", high, low))),
+            (
+                string("u{"),
+                hex_digit(),
+                many(choice!(hex_digit(), token('_'))),
+                token('}')
+            ).and_then(move |(_, first, rest, _): (_, char, String, _)| {
+                let digits = iter::once(first)
+                    .chain(rest.chars().filter(|&c| c != '_'))
+                    .collect::<Vec<_>>();
+                if digits.len() <= 6 {
+                    Ok((hex_value(&digits), format!("u{{{}{}}}", first, rest)))
+                } else {
+                    Err(invalid_digits.clone())
+                }
+            })
+        ))
+        .and_then(move |(code, escape)| match char::from_u32(code) {
+            Some(value) => Ok((value, format!("\\{}", escape))),
+            None => Err(invalid.clone())
+        })
+        .parse_stream(input)
+        .map_err(|error| match error {
+            Consumed::Consumed(_) => {
+                let hint = RSXParseError::new(RSXParseErrorKind::InvalidEscape, start);
+                rsx_error_hint(error, hint)
+            }
+            Consumed::Empty(_) => error
+        })
+}
+
+pub fn rs_byte_char<I>(input: I) -> ParseResult<RSByteChar, I>
 where
     I: Stream<Item = char>
 {
-    between(
-        token('"'),
-        token('"'),
-        many(choice!(try(parser(escaped_character)), none_of("\"".chars())))
-    ).map(RSString)
+    token('b')
+        .with(between(
+            token('\''),
+            token('\''),
+            choice!(parser(rs_byte_escape), env_parser('\'', rs_byte_character))
+        ))
+        .map(RSByteChar)
+        .parse_stream(input)
+}
+
+pub fn rs_byte_string<I>(input: I) -> ParseResult<RSByteString, I>
+where
+    I: Stream<Item = char>
+{
+    token('b')
+        .with(between(
+            token('"').skip(parser(rs_line_continuations)),
+            token('"'),
+            many(
+                choice!(parser(rs_byte_escape), env_parser('"', rs_byte_character))
+                    .skip(parser(rs_line_continuations))
+            )
+        ))
+        .map(RSByteString)
+        .parse_stream(input)
+}
+
+// Any ASCII character other than a backslash or the closing quote.
+pub fn rs_byte_character<I>(quote: char, input: I) -> ParseResult<u8, I>
+where
+    I: Stream<Item = char>
+{
+    satisfy(move |c: char| c < '\u{80}' && c != '\\' && c != quote)
+        .map(|c| c as u8)
+        .parse_stream(input)
+}
+
+// Bytes can't be unicode escapes, but hex escapes go all the way to `\xFF`.
+pub fn rs_byte_escape<I>(input: I) -> ParseResult<u8, I>
+where
+    I: Stream<Item = char>
+{
+    token('\\')
+        .with(choice!(
+            one_of("nrt0\\'\"".chars()).map(|c| match c {
+                'n' => b'\n',
+                'r' => b'\r',
+                't' => b'\t',
+                '0' => b'\0',
+                c => c as u8
+            }),
+            token('x').with(parser(hex_escape)).map(|v| v as u8)
+        ))
         .parse_stream(input)
 }
 
-pub fn rs_comment<I>(input: I) -> ParseResult<(), I>
+pub fn rs_raw_string<I>(input: I) -> ParseResult<RSRawString, I>
 where
     I: Stream<Item = char>
 {
-    parser(js_comment).parse_stream(input)
+    (
+        choice!(try(string("br")), try(string("cr")), string("r")),
+        many(token('#')).map(|hashes: String| hashes.len()),
+        token('"')
+    ).parse_stream(input)
+        .and_then(|((prefix, hashes, _), consumed)| {
+            consumed.combine(|input| {
+                env_parser(hashes, rs_raw_string_characters)
+                    .parse_stream(input)
+                    .map(|(value, consumed)| {
+                        let prefix = prefix.to_string();
+                        (RSRawString { prefix, hashes, value }, consumed)
+                    })
+            })
+        })
+}
+
+// Raw strings end at the first quote followed by as many hashes as there were
+// before the opening quote.
+pub fn rs_raw_string_characters<I>(hashes: usize, input: I) -> ParseResult<String, I>
+where
+    I: Stream<Item = char>
+{
+    let end = || {
+        let end = iter::once('"').chain(iter::repeat('#').take(hashes)).collect::<Vec<_>>();
+        tokens(|l: char, r: char| l == r, "end of raw string".into(), end)
+    };
+    many(not_followed_by(try(end().map(|_| "end of raw string"))).with(any()))
+        .skip(end())
+        .parse_stream(input)
+}
+
+// Unlike in JS, line continuations in Rust strings also skip the whitespace at
+// the start of the next line. Returns the skipped source.
+pub fn rs_line_continuations<I>(input: I) -> ParseResult<String, I>
+where
+    I: Stream<Item = char>
+{
+    many(
+        try((token('\\'), choice!(try(string("\r\n")), string("\n")), many(space())))
+            .map(|(_, newline, spaces): (_, _, String)| format!("\\{}{}", newline, spaces))
+    ).map(|continuations: Vec<String>| continuations.concat())
+        .parse_stream(input)
+}
+
+pub fn rs_comment<I>(input: I) -> ParseResult<RSComment, I>
+where
+    I: Stream<Item = char>
+{
+    choice!(try(parser(rs_line_comment)), parser(rs_block_comment)).parse_stream(input)
+}
+
+pub fn rs_line_comment<I>(input: I) -> ParseResult<RSComment, I>
+where
+    I: Stream<Item = char>
+{
+    between(string("//"), token('\n'), many(none_of("\n".chars())))
+        .map(RSComment::Line)
+        .parse_stream(input)
+}
+
+// Unlike in JS, block comments in Rust can be nested.
+pub fn rs_block_comment<I>(input: I) -> ParseResult<RSComment, I>
+where
+    I: Stream<Item = char>
+{
+    between(
+        string("/*"),
+        string("*/"),
+        many(choice!(
+            try(parser(rs_block_comment)).map(|comment| comment.to_string()),
+            try(token('*').skip(not_followed_by(token('/')))).map(|c: char| c.to_string()),
+            try(token('/').skip(not_followed_by(token('*')))).map(|c: char| c.to_string()),
+            none_of("*/".chars()).map(|c: char| c.to_string())
+        ))
+    ).map(RSComment::Block)
+        .parse_stream(input)
 }
 
 pub fn rs_whitespace<I>(input: I) -> ParseResult<(), I>
 where
     I: Stream<Item = char>
 {
-    parser(js_whitespace).parse_stream(input)
+    skip_many(choice!(try(parser(rs_comment).with(value(()))), space().with(value(()))))
+        .parse_stream(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_rs_char() {
+        let (value, rest) = parser(rs_char).parse(r"'\u{1F_600}'").unwrap();
+        assert_eq!((value.value, rest), ('\u{1F600}', ""));
+        assert_eq!(value.to_string(), r"'\u{1F_600}'");
+        assert_eq!(parser(rs_char).parse(r"'\x41'").unwrap().0.value, 'A');
+        assert_eq!(parser(rs_char).parse(r"'\v'").is_err(), true);
+        assert_eq!(parser(rs_char).parse(r"'\x80'").is_err(), true);
+        assert_eq!(parser(rs_char).parse(r"'\u{D800}'").is_err(), true);
+        assert_eq!(parser(rs_char).parse(r"'\u{1000000}'").is_err(), true);
+    }
+
+    #[test]
+    pub fn test_rs_string() {
+        let source = "\"a\\t\\\"b\\\"\\\n    c\"";
+        let (value, rest) = parser(rs_string).parse(source).unwrap();
+        assert_eq!((value.value.as_str(), rest), ("a\t\"b\"c", ""));
+        assert_eq!(value.to_string(), source);
+        assert_eq!(parser(rs_string).parse(r#""\uABCD""#).is_err(), true);
+        assert_eq!(parser(rs_string).parse(r#""\b""#).is_err(), true);
+
+        let (value, _) = parser(rs_c_string).parse(r#"c"\x7F""#).unwrap();
+        assert_eq!((value.value.as_str(), value.to_string()), ("\x7F", r#"c"\x7F""#.into()));
+    }
 }
@@ -9,16 +9,135 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
+use std::ascii;
+use std::fmt;
 use std::iter::FromIterator;
 
+// Char and string literals are decoded when parsing, but keep their lexeme as
+// written, quotes and prefix included, which is what displaying them yields.
+
 #[derive(Debug, PartialEq)]
-pub struct RSChar(pub char);
+pub struct RSChar {
+    pub value: char,
+    pub lexeme: String
+}
+
+impl fmt::Display for RSChar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.lexeme)
+    }
+}
 
 #[derive(Debug, PartialEq)]
-pub struct RSString(pub String);
+pub struct RSString {
+    pub value: String,
+    pub lexeme: String
+}
 
+// Strings built from their value are given the lexeme Rust would print.
 impl FromIterator<char> for RSString {
     fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
-        RSString(iter.into_iter().collect())
+        let value: String = iter.into_iter().collect();
+        let lexeme = format!("{:?}", value);
+        RSString { value, lexeme }
+    }
+}
+
+impl fmt::Display for RSString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.lexeme)
+    }
+}
+
+// Byte literals are decoded when parsing, so displaying them escapes them
+// again, yielding valid Rust source.
+#[derive(Debug, PartialEq)]
+pub struct RSByteChar(pub u8);
+
+impl fmt::Display for RSByteChar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "b'{}'", escape_bytes(&[self.0]))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RSByteString(pub Vec<u8>);
+
+impl fmt::Display for RSByteString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "b\"{}\"", escape_bytes(&self.0))
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct RSCString {
+    pub value: String,
+    pub lexeme: String
+}
+
+impl fmt::Display for RSCString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.lexeme)
+    }
+}
+
+// Lifetimes and loop labels, without the leading quote.
+#[derive(Debug, PartialEq)]
+pub struct RSLifetime(pub String);
+
+impl fmt::Display for RSLifetime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}", self.0)
+    }
+}
+
+// The text of a comment, without its delimiters.
+#[derive(Debug, PartialEq)]
+pub enum RSComment {
+    Line(String),
+    Block(String)
+}
+
+impl RSComment {
+    // Like in rustc, `////` and `/***` start regular comments instead.
+    pub fn is_doc(&self) -> bool {
+        match self {
+            &RSComment::Line(ref text) => {
+                text.starts_with('!') || (text.starts_with('/') && !text.starts_with("//"))
+            }
+            &RSComment::Block(ref text) => {
+                text.starts_with('!') ||
+                    (text.starts_with('*') && !text.starts_with("**") && text.len() > 1)
+            }
+        }
+    }
+}
+
+impl fmt::Display for RSComment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &RSComment::Line(ref text) => write!(f, "//{}\n", text),
+            &RSComment::Block(ref text) => write!(f, "/*{}*/", text)
+        }
+    }
+}
+
+// Raw strings, raw byte strings and raw C strings, which are told apart by
+// their prefix (`r`, `br` or `cr`). Their contents are never escaped.
+#[derive(Debug, PartialEq)]
+pub struct RSRawString {
+    pub prefix: String,
+    pub hashes: usize,
+    pub value: String
+}
+
+impl fmt::Display for RSRawString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hashes = "#".repeat(self.hashes);
+        write!(f, "{}{}\"{}\"{}", self.prefix, hashes, self.value, hashes)
+    }
+}
+
+fn escape_bytes(bytes: &[u8]) -> String {
+    bytes.iter().flat_map(|b| ascii::escape_default(*b)).map(char::from).collect()
+}
@@ -0,0 +1,249 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::any::{Any, TypeId};
+use std::slice;
+
+use combine::ParseResult;
+use combine::primitives::Consumed;
+
+use parse_attributes_types::{RSXAttribute, RSXAttributeName, RSXAttributeValue, RSXAttributes};
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_elements_types::{RSXElement, RSXElementName};
+use parse_external_types::RSXParsedExpression;
+use parse_span_types::{RSXPosition, RSXSpan, RSXSpanMismatch, RSXSpanTable, RSXSpans, RSXStream};
+
+pub fn rsx_position<I>(input: I) -> ParseResult<RSXPosition, I>
+where
+    I: RSXStream
+{
+    let position = input.source_position();
+    Ok((position, Consumed::Empty(input)))
+}
+
+// Records the span of a node parsed from `start` up to wherever parsing it
+// left the stream.
+pub fn rsx_record_span<N, I>(start: RSXPosition, result: ParseResult<N, I>) -> ParseResult<N, I>
+where
+    N: Any,
+    I: RSXStream
+{
+    result.map(|(node, consumed)| {
+        let consumed = consumed.map(|mut input: I| {
+            let span = RSXSpan::new(start, input.source_position());
+            input.record_span(TypeId::of::<N>(), span);
+            input
+        });
+        (node, consumed)
+    })
+}
+
+// Parsers record spans as they finish nodes, so walking the tree depth first,
+// visiting every node after its contents, finds the nodes in table order.
+pub fn rsx_element_spans<'a>(
+    element: &'a RSXElement,
+    table: &RSXSpanTable
+) -> Result<RSXSpans<'a>, RSXSpanMismatch> {
+    let mut resolver = RSXSpanResolver {
+        table: table.0.iter(),
+        spans: RSXSpans::default(),
+        matches: true
+    };
+    resolver.element(element);
+    if resolver.matches && resolver.table.next().is_none() {
+        Ok(resolver.spans)
+    } else {
+        Err(RSXSpanMismatch)
+    }
+}
+
+struct RSXSpanResolver<'a, 't> {
+    table: slice::Iter<'t, (TypeId, RSXSpan)>,
+    spans: RSXSpans<'a>,
+    matches: bool
+}
+
+impl<'a, 't> RSXSpanResolver<'a, 't> {
+    fn node<T: Any>(&mut self, node: &'a T) {
+        match self.table.next() {
+            Some(&(kind, span)) if kind == TypeId::of::<T>() => self.spans.insert(node, span),
+            _ => self.matches = false
+        }
+    }
+
+    fn element(&mut self, element: &'a RSXElement) {
+        match element {
+            &RSXElement::SelfClosing(ref element) => {
+                self.element_name(&element.0);
+                self.attributes(&element.1);
+                self.node(element);
+            }
+            &RSXElement::Normal(ref element) => {
+                self.element_name(&element.0);
+                self.attributes(&element.1);
+                self.children(&element.2);
+                self.node(element);
+            }
+            &RSXElement::Fragment(ref children) => self.children(children),
+            &RSXElement::Error(_) => {}
+        }
+    }
+
+    fn element_name(&mut self, name: &'a RSXElementName) {
+        match name {
+            &RSXElementName::Name(ref n) => self.node(n),
+            &RSXElementName::NamedspacedName(ref ns, ref n) => {
+                self.node(ns);
+                self.node(n);
+            }
+            &RSXElementName::MemberExpression(ref e) => {
+                for n in e.iter() {
+                    self.node(n);
+                }
+            }
+            &RSXElementName::KnownName(_) => {}
+        }
+    }
+
+    fn attributes(&mut self, attributes: &'a RSXAttributes) {
+        for attribute in attributes.0.iter() {
+            match attribute {
+                &RSXAttribute::Named(ref name, ref value) => {
+                    self.attribute_name(name);
+                    self.attribute_value(value);
+                }
+                &RSXAttribute::Spread(ref expression) => self.expression(expression),
+                &RSXAttribute::Error(_) => {}
+            }
+        }
+    }
+
+    fn attribute_name(&mut self, name: &'a RSXAttributeName) {
+        match name {
+            &RSXAttributeName::Name(ref n) => self.node(n),
+            &RSXAttributeName::NamedspacedName(ref ns, ref n) => {
+                self.node(ns);
+                self.node(n);
+            }
+            &RSXAttributeName::KnownName(_) => {}
+        }
+    }
+
+    fn attribute_value(&mut self, value: &'a RSXAttributeValue) {
+        match value {
+            &RSXAttributeValue::Default => {}
+            &RSXAttributeValue::Boolean(ref boolean) => self.node(boolean),
+            &RSXAttributeValue::Number(ref number) => self.node(number),
+            &RSXAttributeValue::Str(ref string) => self.node(string),
+            &RSXAttributeValue::Element(ref element) => self.element(element),
+            &RSXAttributeValue::CodeBlock(ref expression) => self.expression(expression)
+        }
+    }
+
+    fn children(&mut self, children: &'a RSXChildren) {
+        for child in children.0.iter() {
+            match child {
+                &RSXChild::Element(ref element) => self.element(element),
+                &RSXChild::Text(ref text) => self.node(text),
+                &RSXChild::CodeBlock(ref expression) => self.expression(expression),
+                &RSXChild::Error(_) => {}
+            }
+        }
+        self.node(children);
+    }
+
+    fn expression(&mut self, expression: &'a RSXParsedExpression) {
+        for element in expression.holes() {
+            self.element(element);
+        }
+        self.node(expression);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use combine::Parser;
+    use combine::char::string;
+    use combine::combinator::parser;
+
+    use parse_children_types::RSXText;
+    use parse_elements::rsx_element;
+    use parse_span_types::RSXSourceStream;
+
+    #[test]
+    pub fn test_rsx_position() {
+        assert_eq!(parser(rsx_position).parse("foo").unwrap(), (RSXPosition::default(), "foo"));
+
+        let (position, remaining) = string("foo\nb")
+            .with(parser(rsx_position))
+            .parse(RSXSourceStream::new("foo\nbar"))
+            .unwrap();
+
+        assert_eq!(
+            position,
+            RSXPosition {
+                offset: 5,
+                line: 2,
+                column: 2
+            }
+        );
+        assert_eq!(remaining.as_str(), "ar");
+    }
+
+    #[test]
+    pub fn test_rsx_element_spans() {
+        let source = "<div> <a-b/> text {<i/>} </div>";
+        let (element, rest) = parser(rsx_element).parse(RSXSourceStream::new(source)).unwrap();
+        let table = rest.span_table();
+        let spans = RSXSpans::new(&element, &table).unwrap();
+
+        let children = match element {
+            RSXElement::Normal(ref element) => &element.2,
+            _ => panic!("Expected a normal element")
+        };
+        let offsets = children
+            .0
+            .iter()
+            .map(|child| {
+                let span = child.span(&spans);
+                (span.start.offset, span.end.offset)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![(5, 6), (6, 12), (12, 18), (18, 24), (24, 25)]);
+
+        let span = element.span(&spans);
+        assert_eq!((span.start.offset, span.end.offset), (0, source.len()));
+
+        // Nodes which aren't part of the tree aren't found.
+        assert_eq!(spans.get(&RSXText("text".into())), RSXSpan::default());
+    }
+
+    #[test]
+    pub fn test_rsx_element_spans_mismatch() {
+        let (element, rest) = parser(rsx_element).parse(RSXSourceStream::new("<a>b</a>")).unwrap();
+        let table = rest.span_table();
+
+        let (other, _) = parser(rsx_element).parse(RSXSourceStream::new("<div/>")).unwrap();
+        assert_eq!(RSXSpans::new(&other, &table).err(), Some(RSXSpanMismatch));
+
+        let mut edited = element;
+        if let RSXElement::Normal(ref mut element) = edited {
+            element.2 = RSXChildren::from(vec![]);
+        }
+        assert_eq!(RSXSpans::new(&edited, &table).err(), Some(RSXSpanMismatch));
+
+        // Streams which don't know their position don't record any spans.
+        let (element, _) = parser(rsx_element).parse("<a>b</a>").unwrap();
+        assert_eq!(RSXSpans::new(&element, &RSXSpanTable::default()).err(), Some(RSXSpanMismatch));
+    }
+}
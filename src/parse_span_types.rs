@@ -0,0 +1,301 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use combine::Stream;
+use combine::primitives::{Error, StreamOnce};
+
+use parse_cst_types::RSXCstKind;
+use parse_elements_types::RSXElement;
+use parse_options_types::RSXParseOptions;
+use parse_span::rsx_element_spans;
+
+// A default position (line and column 0) means the node wasn't created from
+// a positioned stream, e.g. when parsing a plain `&str`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RSXPosition {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize
+}
+
+impl RSXPosition {
+    pub fn start() -> Self {
+        RSXPosition {
+            offset: 0,
+            line: 1,
+            column: 1
+        }
+    }
+
+    pub fn advance(&mut self, c: char) {
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+impl fmt::Display for RSXPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RSXSpan {
+    pub start: RSXPosition,
+    pub end: RSXPosition
+}
+
+impl RSXSpan {
+    pub fn new(start: RSXPosition, end: RSXPosition) -> Self {
+        RSXSpan { start, end }
+    }
+
+    pub fn join(&self, other: &RSXSpan) -> Self {
+        RSXSpan::new(self.start, other.end)
+    }
+}
+
+impl fmt::Display for RSXSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+// The spans of the nodes of a parsed tree, in the order the parser finished
+// them. Nodes don't store their spans themselves, so that they can still be
+// built and matched on without one. See `RSXSpans` for looking them up.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RSXSpanTable(pub Vec<(TypeId, RSXSpan)>);
+
+// A span table which doesn't belong to the tree it was looked up for, e.g.
+// because the tree was edited or built by hand, or wasn't parsed from a
+// positioned stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RSXSpanMismatch;
+
+impl fmt::Display for RSXSpanMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "span table doesn't match the tree")
+    }
+}
+
+impl StdError for RSXSpanMismatch {
+    fn description(&self) -> &str {
+        "span table doesn't match the tree"
+    }
+}
+
+// Spans keyed by node. Nodes are told apart by their address, which is why
+// the tree stays borrowed for as long as its spans are looked up. Nodes that
+// aren't part of the tree have the default span.
+#[derive(Debug, Default)]
+pub struct RSXSpans<'a> {
+    spans: HashMap<(TypeId, usize), RSXSpan>,
+    tree: PhantomData<&'a RSXElement>
+}
+
+impl<'a> RSXSpans<'a> {
+    pub fn new(element: &'a RSXElement, table: &RSXSpanTable) -> Result<Self, RSXSpanMismatch> {
+        rsx_element_spans(element, table)
+    }
+
+    pub fn get<T: Any>(&self, node: &T) -> RSXSpan {
+        self.spans.get(&rsx_span_key(node)).cloned().unwrap_or_default()
+    }
+
+    pub fn insert<T: Any>(&mut self, node: &'a T, span: RSXSpan) {
+        self.spans.insert(rsx_span_key(node), span);
+    }
+}
+
+fn rsx_span_key<T: Any>(node: &T) -> (TypeId, usize) {
+    (TypeId::of::<T>(), node as *const T as usize)
+}
+
+// Spans are recorded into a persistent list shared between copies of the
+// stream, so that backtracking to an earlier copy also forgets the spans of
+// nodes parsed since.
+#[derive(Debug, PartialEq)]
+struct RSXSpanLog<T> {
+    entry: (T, RSXSpan),
+    previous: Option<Rc<RSXSpanLog<T>>>
+}
+
+impl<T: Copy> RSXSpanLog<T> {
+    fn record(log: &mut Option<Rc<RSXSpanLog<T>>>, entry: (T, RSXSpan)) {
+        let previous = log.take();
+        *log = Some(Rc::new(RSXSpanLog { entry, previous }));
+    }
+
+    fn entries(log: &Option<Rc<RSXSpanLog<T>>>) -> Vec<(T, RSXSpan)> {
+        let mut entries = vec![];
+        let mut log = log.as_ref();
+        while let Some(item) = log {
+            entries.push(item.entry);
+            log = item.previous.as_ref();
+        }
+        entries.reverse();
+        entries
+    }
+}
+
+// Long lists would otherwise be dropped recursively.
+impl<T> Drop for RSXSpanLog<T> {
+    fn drop(&mut self) {
+        let mut previous = self.previous.take();
+        while let Some(log) = previous {
+            previous = match Rc::try_unwrap(log) {
+                Ok(mut log) => log.previous.take(),
+                Err(_) => None
+            };
+        }
+    }
+}
+
+pub trait RSXStream: Stream<Item = char> {
+    fn source_position(&self) -> RSXPosition;
+
+    fn options(&self) -> RSXParseOptions;
+
+    fn set_options(&mut self, options: RSXParseOptions);
+
+    fn record_span(&mut self, kind: TypeId, span: RSXSpan);
+
+    // Records a node or token of the concrete syntax tree, see `rsx_record_cst`.
+    fn record_cst(&mut self, kind: RSXCstKind, span: RSXSpan);
+}
+
+impl<'a> RSXStream for &'a str {
+    fn source_position(&self) -> RSXPosition {
+        RSXPosition::default()
+    }
+
+    fn options(&self) -> RSXParseOptions {
+        RSXParseOptions::default()
+    }
+
+    fn set_options(&mut self, _: RSXParseOptions) {}
+
+    fn record_span(&mut self, _: TypeId, _: RSXSpan) {}
+
+    fn record_cst(&mut self, _: RSXCstKind, _: RSXSpan) {}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RSXSourceStream<'a> {
+    input: &'a str,
+    position: RSXPosition,
+    options: RSXParseOptions,
+    spans: Option<Rc<RSXSpanLog<TypeId>>>,
+    cst: Option<Rc<RSXSpanLog<RSXCstKind>>>,
+    recording_cst: bool
+}
+
+impl<'a> RSXSourceStream<'a> {
+    pub fn new(input: &'a str) -> Self {
+        RSXSourceStream::with_options(input, RSXParseOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: RSXParseOptions) -> Self {
+        RSXSourceStream::at(input, RSXPosition::start(), options)
+    }
+
+    // For parsing part of a larger source, which starts at `position` in it.
+    pub fn at(input: &'a str, position: RSXPosition, options: RSXParseOptions) -> Self {
+        RSXSourceStream {
+            input,
+            position,
+            options,
+            spans: None,
+            cst: None,
+            recording_cst: false
+        }
+    }
+
+    // Concrete syntax trees are only needed by some callers, so recording
+    // them has to be asked for.
+    pub fn recording_cst(mut self) -> Self {
+        self.recording_cst = true;
+        self
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.input
+    }
+
+    pub fn span_table(&self) -> RSXSpanTable {
+        RSXSpanTable(RSXSpanLog::entries(&self.spans))
+    }
+
+    // The nodes and tokens of the concrete syntax tree, in the order the
+    // parser finished them. See `rsx_cst_root` for building the tree.
+    pub fn cst_entries(&self) -> Vec<(RSXCstKind, RSXSpan)> {
+        RSXSpanLog::entries(&self.cst)
+    }
+}
+
+impl<'a> StreamOnce for RSXSourceStream<'a> {
+    type Item = char;
+    type Range = &'a str;
+    type Position = RSXPosition;
+
+    fn uncons(&mut self) -> Result<char, Error<char, &'a str>> {
+        let mut chars = self.input.chars();
+        match chars.next() {
+            Some(c) => {
+                self.input = chars.as_str();
+                self.position.advance(c);
+                Ok(c)
+            }
+            None => Err(Error::end_of_input())
+        }
+    }
+
+    fn position(&self) -> Self::Position {
+        self.position
+    }
+}
+
+impl<'a> RSXStream for RSXSourceStream<'a> {
+    fn source_position(&self) -> RSXPosition {
+        self.position
+    }
+
+    fn options(&self) -> RSXParseOptions {
+        self.options
+    }
+
+    fn set_options(&mut self, options: RSXParseOptions) {
+        self.options = options;
+    }
+
+    fn record_span(&mut self, kind: TypeId, span: RSXSpan) {
+        RSXSpanLog::record(&mut self.spans, (kind, span));
+    }
+
+    fn record_cst(&mut self, kind: RSXCstKind, span: RSXSpan) {
+        if self.recording_cst {
+            RSXSpanLog::record(&mut self.cst, (kind, span));
+        }
+    }
+}
@@ -0,0 +1,185 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use syn;
+
+use parse_attributes_types::{RSXAttribute, RSXAttributeValue};
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_elements_types::RSXElement;
+use parse_error_types::{RSXDiagnostic, RSXParseError, RSXParseErrorKind};
+use parse_external::rsx_code_with_holes;
+use parse_external_types::RSXParsedExpression;
+use parse_span_types::RSXSpans;
+
+// Code blocks are only lexed while parsing, so this separate pass checks that
+// every one of them is also a valid Rust expression. Diagnostics are returned
+// in source order, one for each invalid code block.
+pub fn rsx_validate_element(element: &RSXElement, spans: &RSXSpans) -> Vec<RSXDiagnostic> {
+    let mut diagnostics = vec![];
+    rsx_validate_element_into(element, spans, &mut diagnostics);
+    diagnostics
+}
+
+fn rsx_validate_element_into(
+    element: &RSXElement,
+    spans: &RSXSpans,
+    diagnostics: &mut Vec<RSXDiagnostic>
+) {
+    match element {
+        &RSXElement::SelfClosing(ref element) => {
+            for attribute in (element.1).0.iter() {
+                rsx_validate_attribute(attribute, spans, diagnostics);
+            }
+        }
+        &RSXElement::Normal(ref element) => {
+            for attribute in (element.1).0.iter() {
+                rsx_validate_attribute(attribute, spans, diagnostics);
+            }
+            rsx_validate_children(&element.2, spans, diagnostics);
+        }
+        &RSXElement::Fragment(ref children) => {
+            rsx_validate_children(children, spans, diagnostics)
+        }
+        &RSXElement::Error(_) => {}
+    }
+}
+
+fn rsx_validate_attribute(
+    attribute: &RSXAttribute,
+    spans: &RSXSpans,
+    diagnostics: &mut Vec<RSXDiagnostic>
+) {
+    match attribute {
+        &RSXAttribute::Named(_, RSXAttributeValue::Element(ref element)) => {
+            rsx_validate_element_into(element, spans, diagnostics)
+        }
+        &RSXAttribute::Named(_, RSXAttributeValue::CodeBlock(ref expression)) => {
+            rsx_validate_expression(expression, spans, diagnostics)
+        }
+        &RSXAttribute::Spread(ref expression) => {
+            rsx_validate_expression(expression, spans, diagnostics)
+        }
+        _ => {}
+    }
+}
+
+fn rsx_validate_children(
+    children: &RSXChildren,
+    spans: &RSXSpans,
+    diagnostics: &mut Vec<RSXDiagnostic>
+) {
+    for child in children.0.iter() {
+        match child {
+            &RSXChild::Element(ref element) => {
+                rsx_validate_element_into(element, spans, diagnostics)
+            }
+            &RSXChild::CodeBlock(ref expression) => {
+                rsx_validate_expression(expression, spans, diagnostics)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn rsx_validate_expression(
+    expression: &RSXParsedExpression,
+    spans: &RSXSpans,
+    diagnostics: &mut Vec<RSXDiagnostic>
+) {
+    // Elements always generate an expression, so an identifier is good enough
+    // as a stub. The code is wrapped in braces, just like in generated code.
+    let (code, _) = rsx_code_with_holes(expression);
+    if let Err(message) = syn::parse_expr(&format!("{{{}}}", code)) {
+        let span = expression.span(spans);
+        let kind = RSXParseErrorKind::InvalidExpression;
+        diagnostics.push(RSXDiagnostic {
+            error: RSXParseError::new(kind, span.start).with_found(message),
+            span
+        });
+    }
+
+    for element in expression.holes() {
+        rsx_validate_element_into(element, spans, diagnostics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_helpers::with_spans;
+
+    fn validate(source: &str) -> Vec<RSXDiagnostic> {
+        with_spans(source, rsx_validate_element)
+    }
+
+    #[test]
+    pub fn test_rsx_validate_valid() {
+        let sources = [
+            "<div/>",
+            "<div a={1 + 2} {...props} b={<span/>}>{}{/* comment */}</div>",
+            "<div>{ let x = 1; x }{ foo.iter().map(|x| <b>{x}</b>) }</div>",
+            "<div>{ if a { <b/> } else { <i/> } }</div>",
+            r##"<div a={c"x"}>{ f(cr#"y"#, <b/>) }</div>"##
+        ];
+        for source in sources.iter() {
+            assert_eq!(validate(source), vec![]);
+        }
+    }
+
+    #[test]
+    pub fn test_rsx_validate_invalid() {
+        let diagnostics = validate("<div a={1 +}>\n  {foo bar}\n</div>");
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| {
+                    let (start, end) = (d.span.start, d.span.end);
+                    (d.error.kind, (start.line, start.column), (end.line, end.column))
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                (RSXParseErrorKind::InvalidExpression, (1, 8), (1, 13)),
+                (RSXParseErrorKind::InvalidExpression, (2, 3), (2, 12)),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "1:8: invalid Rust expression, found `failed to parse expression: \"{1 +}\"`"
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_validate_holes() {
+        assert_eq!(validate("<div>{ f(<a/>, <b/>) }{<a/>}</div>"), vec![]);
+
+        let diagnostics = validate("<div>{x <a/>}</div>");
+        assert_eq!(
+            diagnostics.iter().map(|d| d.span.start.offset).collect::<Vec<_>>(),
+            vec![5]
+        );
+
+        let diagnostics = validate("<div>{<a/> <b/>}</div>");
+        assert_eq!(
+            diagnostics.iter().map(|d| d.span.start.offset).collect::<Vec<_>>(),
+            vec![5]
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_validate_nested() {
+        let diagnostics = validate("<div {...a b}>{ <b c={,}/> }</div>");
+        assert_eq!(
+            diagnostics.iter().map(|d| d.span.start.offset).collect::<Vec<_>>(),
+            vec![5, 21]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use parse_elements_types::RSXElementName;
+use parse_options_types::{RSXParseOptions, RSXWhitespaceMode};
+
+// Text normalized to an empty string isn't kept as a child at all.
+pub fn rsx_normalize_whitespace(text: &str, mode: RSXWhitespaceMode) -> String {
+    match mode {
+        RSXWhitespaceMode::Jsx => rsx_jsx_whitespace(text),
+        RSXWhitespaceMode::Preserve => text.to_string(),
+        RSXWhitespaceMode::Collapse => rsx_collapse_whitespace(text)
+    }
+}
+
+// Options for parsing the children of the given element.
+pub fn rsx_children_options(name: &RSXElementName, options: RSXParseOptions) -> RSXParseOptions {
+    if rsx_preserves_whitespace(name) {
+        RSXParseOptions {
+            whitespace: RSXWhitespaceMode::Preserve,
+            ..options
+        }
+    } else {
+        options
+    }
+}
+
+pub fn rsx_preserves_whitespace(name: &RSXElementName) -> bool {
+    match name {
+        &RSXElementName::Name(ref n) => n.0.eq_ignore_ascii_case("pre"),
+        _ => false
+    }
+}
+
+pub fn rsx_is_line_break(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+pub fn rsx_is_space(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn rsx_jsx_whitespace(text: &str) -> String {
+    let lines = text.lines().collect::<Vec<_>>();
+    let last = lines.len().saturating_sub(1);
+    let mut lines = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = if i == 0 { *line } else { line.trim_left_matches(rsx_is_space) };
+            let line = if i == last { line } else { line.trim_right_matches(rsx_is_space) };
+            line.replace('\t', " ")
+        })
+        .filter(|line| !line.is_empty());
+
+    // Trailing line breaks don't show up in `lines`, but still mean that the
+    // trailing whitespace of the last line isn't significant.
+    let mut normalized = lines.next().unwrap_or_default();
+    for line in lines {
+        normalized.push(' ');
+        normalized.push_str(&line);
+    }
+    if text.ends_with(rsx_is_line_break) {
+        let len = normalized.trim_right_matches(rsx_is_space).len();
+        normalized.truncate(len);
+    }
+    normalized
+}
+
+fn rsx_collapse_whitespace(text: &str) -> String {
+    if text.chars().all(|c| rsx_is_space(c) || rsx_is_line_break(c)) && text.contains('\n') {
+        return String::new();
+    }
+
+    let mut collapsed = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if rsx_is_space(c) || rsx_is_line_break(c) {
+            space = true;
+        } else {
+            if space {
+                collapsed.push(' ');
+                space = false;
+            }
+            collapsed.push(c);
+        }
+    }
+    if space {
+        collapsed.push(' ');
+    }
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_rsx_jsx_whitespace() {
+        let jsx = |text| rsx_normalize_whitespace(text, RSXWhitespaceMode::Jsx);
+        assert_eq!(jsx(""), "");
+        assert_eq!(jsx(" "), " ");
+        assert_eq!(jsx("\n"), "");
+        assert_eq!(jsx("\n    "), "");
+        assert_eq!(jsx("  foo  "), "  foo  ");
+        assert_eq!(jsx("foo\n   foo bar baz\n   123"), "foo foo bar baz 123");
+        assert_eq!(jsx("\n    foo  \n\n    bar\n"), "foo bar");
+        assert_eq!(jsx("foo \n  "), "foo");
+        assert_eq!(jsx("  \n  foo"), "foo");
+        assert_eq!(jsx("\tfoo\tbar\t"), " foo bar ");
+        assert_eq!(jsx("foo\r\nbar"), "foo bar");
+    }
+
+    #[test]
+    pub fn test_rsx_collapse_whitespace() {
+        let collapse = |text| rsx_normalize_whitespace(text, RSXWhitespaceMode::Collapse);
+        assert_eq!(collapse(""), "");
+        assert_eq!(collapse(" \t "), " ");
+        assert_eq!(collapse("\n    "), "");
+        assert_eq!(collapse("  foo  "), " foo ");
+        assert_eq!(collapse("foo\n   foo bar   baz\n"), "foo foo bar baz ");
+    }
+
+    #[test]
+    pub fn test_rsx_preserve_whitespace() {
+        let preserve = |text| rsx_normalize_whitespace(text, RSXWhitespaceMode::Preserve);
+        assert_eq!(preserve("\n  foo \n"), "\n  foo \n");
+    }
+}
@@ -0,0 +1,399 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use itertools::Itertools;
+
+use parse_attributes_types::{RSXAttribute, RSXAttributeString, RSXAttributeValue, RSXAttributes};
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_elements_types::{RSXElement, RSXElementName};
+use parse_external_types::RSXParsedExpression;
+use parse_options_types::RSXWhitespaceMode;
+use parse_whitespace::{rsx_is_space, rsx_preserves_whitespace};
+use print_types::{RSXAttributeWrap, RSXPrintOptions, RSXQuoteStyle};
+
+pub fn rsx_print(element: &RSXElement, options: &RSXPrintOptions) -> String {
+    RSXPrinter { options }.element(element, 0)
+}
+
+// Whitespace between attributes is never significant, so it can be freely
+// inserted when breaking lines. Between children, it's only inserted where
+// parsing would normalize it away again. Text and code blocks are always
+// printed verbatim, since their contents are significant.
+struct RSXPrinter<'a> {
+    options: &'a RSXPrintOptions
+}
+
+impl<'a> RSXPrinter<'a> {
+    fn indent(&self, depth: usize) -> String {
+        " ".repeat(depth * self.options.indent_width)
+    }
+
+    fn fits(&self, s: &str, depth: usize) -> bool {
+        !s.contains('\n')
+            && depth * self.options.indent_width + s.chars().count() <= self.options.max_width
+    }
+
+    fn element(&self, element: &RSXElement, depth: usize) -> String {
+        let flat = self.element_layout(element, depth, true);
+        if self.fits(&flat, depth) {
+            flat
+        } else {
+            self.element_layout(element, depth, false)
+        }
+    }
+
+    fn element_layout(&self, element: &RSXElement, depth: usize, flat: bool) -> String {
+        match element {
+            &RSXElement::SelfClosing(ref element) => {
+                self.tag(&element.0, &element.1, None, depth, flat)
+            }
+            &RSXElement::Normal(ref element) => {
+                self.tag(&element.0, &element.1, Some(&element.2), depth, flat)
+            }
+            &RSXElement::Fragment(ref children) => {
+                format!("<>{}</>", self.children(children, false, depth, flat))
+            }
+            &RSXElement::Error(_) => String::new()
+        }
+    }
+
+    fn nested_element(&self, element: &RSXElement, depth: usize, flat: bool) -> String {
+        if flat {
+            self.element_layout(element, depth, true)
+        } else {
+            self.element(element, depth)
+        }
+    }
+
+    fn tag(
+        &self,
+        name: &RSXElementName,
+        attributes: &RSXAttributes,
+        children: Option<&RSXChildren>,
+        depth: usize,
+        flat: bool
+    ) -> String {
+        let attributes = attributes
+            .0
+            .iter()
+            .filter_map(|attribute| self.attribute(attribute, depth + 1, flat))
+            .collect::<Vec<_>>();
+
+        let self_closing = match children {
+            Some(children) => children.0.is_empty() && self.options.self_close_empty,
+            None => true
+        };
+        let close = if self_closing { "/>" } else { ">" };
+
+        let inline = format!(
+            "<{}{}{}",
+            name,
+            attributes.iter().map(|a| format!(" {}", a)).join(""),
+            close
+        );
+        let wrap = match self.options.attribute_wrap {
+            RSXAttributeWrap::Auto => !flat && !self.fits(&inline, depth),
+            RSXAttributeWrap::Always => attributes.len() > 1,
+            RSXAttributeWrap::Never => false
+        };
+
+        let mut output = if wrap {
+            let indent = self.indent(depth + 1);
+            format!(
+                "<{}{}\n{}{}",
+                name,
+                attributes.iter().map(|a| format!("\n{}{}", indent, a)).join(""),
+                self.indent(depth),
+                close
+            )
+        } else {
+            inline
+        };
+
+        if let (Some(children), false) = (children, self_closing) {
+            let preserve = rsx_preserves_whitespace(name);
+            output.push_str(&self.children(children, preserve, depth, flat));
+            output.push_str(&format!("</{}>", name));
+        }
+        output
+    }
+
+    fn children(
+        &self,
+        children: &RSXChildren,
+        preserve: bool,
+        depth: usize,
+        flat: bool
+    ) -> String {
+        let preserve = preserve || self.options.whitespace == RSXWhitespaceMode::Preserve;
+        let flat = flat || preserve;
+        let children = children
+            .0
+            .iter()
+            .filter_map(|child| self.child(child, depth + 1, flat, preserve).map(|c| (child, c)))
+            .collect::<Vec<_>>();
+
+        if flat {
+            return children.iter().map(|&(_, ref c)| c).join("");
+        }
+
+        let indent = self.indent(depth + 1);
+        let mut output = String::new();
+        let mut previous = None;
+        for &(child, ref c) in &children {
+            if self.breakable(previous, Some(child)) {
+                output.push_str(&format!("\n{}", indent));
+            }
+            output.push_str(c);
+            previous = Some(child);
+        }
+        if previous.is_some() && self.breakable(previous, None) {
+            output.push_str(&format!("\n{}", self.indent(depth)));
+        }
+        output
+    }
+
+    // Whether a line break and indentation can be inserted between two
+    // children, or before the first and after the last one.
+    fn breakable(&self, before: Option<&RSXChild>, after: Option<&RSXChild>) -> bool {
+        let before = before.and_then(rsx_child_text);
+        let after = after.and_then(rsx_child_text);
+        match self.options.whitespace {
+            RSXWhitespaceMode::Jsx => {
+                !before.map_or(false, |t| t.ends_with(rsx_is_space))
+                    && !after.map_or(false, |t| t.starts_with(rsx_is_space))
+            }
+            RSXWhitespaceMode::Preserve => false,
+            RSXWhitespaceMode::Collapse => before.is_none() && after.is_none()
+        }
+    }
+
+    fn child(&self, child: &RSXChild, depth: usize, flat: bool, preserve: bool) -> Option<String> {
+        match child {
+            &RSXChild::Element(ref element) => Some(self.nested_element(element, depth, flat)),
+            &RSXChild::Text(ref text) => Some(self.escape_text(&text.0, preserve)),
+            &RSXChild::CodeBlock(ref expression) => {
+                Some(format!("{{{}}}", self.code(expression, depth, flat)))
+            }
+            &RSXChild::Error(_) => None
+        }
+    }
+
+    fn attribute(&self, attribute: &RSXAttribute, depth: usize, flat: bool) -> Option<String> {
+        match attribute {
+            &RSXAttribute::Named(ref name, ref value) => Some(match value {
+                &RSXAttributeValue::Default => name.to_string(),
+                &RSXAttributeValue::Boolean(ref b) => format!("{}={{{}}}", name, b.0),
+                &RSXAttributeValue::Number(ref n) => format!("{}={{{}}}", name, n.0.lexeme()),
+                &RSXAttributeValue::Str(ref s) => format!("{}={}", name, self.string(s)),
+                &RSXAttributeValue::Element(ref element) => {
+                    format!("{}={}", name, self.nested_element(element, depth, flat))
+                }
+                &RSXAttributeValue::CodeBlock(ref expression) => {
+                    format!("{}={{{}}}", name, self.code(expression, depth, flat))
+                }
+            }),
+            &RSXAttribute::Spread(ref expression) => {
+                Some(format!("{{...{}}}", self.code(expression, depth, flat)))
+            }
+            &RSXAttribute::Error(_) => None
+        }
+    }
+
+    fn string(&self, string: &RSXAttributeString) -> String {
+        let quote = match (self.options.quote_style, string) {
+            (RSXQuoteStyle::Double, _) => '"',
+            (RSXQuoteStyle::Single, _) => '\'',
+            (RSXQuoteStyle::Preserve, &RSXAttributeString::DoubleQuoted(_)) => '"',
+            (RSXQuoteStyle::Preserve, &RSXAttributeString::SingleQuoted(_)) => '\''
+        };
+
+        let mut output = quote.to_string();
+        for c in self.escape(string.value()).chars() {
+            if c == quote || c == '\\' {
+                output.push('\\');
+            }
+            output.push(c);
+        }
+        output.push(quote);
+        output
+    }
+
+    fn escape(&self, s: &str) -> String {
+        if self.options.escape_entities {
+            s.replace('&', "&amp;")
+        } else {
+            s.to_string()
+        }
+    }
+
+    // Text can't contain tags or code blocks, and outside of preserved
+    // whitespace its line breaks would be normalized away when parsing, so
+    // those characters are written as character references too.
+    fn escape_text(&self, s: &str, preserve: bool) -> String {
+        if !self.options.escape_entities {
+            return s.to_string();
+        }
+        let mut output = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '&' => output.push_str("&amp;"),
+                '<' => output.push_str("&lt;"),
+                '>' => output.push_str("&gt;"),
+                '{' => output.push_str("&#123;"),
+                '}' => output.push_str("&#125;"),
+                '\n' if !preserve => output.push_str("&#10;"),
+                '\r' if !preserve => output.push_str("&#13;"),
+                c => output.push(c)
+            }
+        }
+        output
+    }
+
+    // Code blocks keep their code split around the elements inside them, so
+    // the elements are printed back in between.
+    fn code(&self, expression: &RSXParsedExpression, depth: usize, flat: bool) -> String {
+        expression.replace_elements(|element| self.nested_element(element, depth, flat))
+    }
+}
+
+fn rsx_child_text(child: &RSXChild) -> Option<&str> {
+    match child {
+        &RSXChild::Text(ref text) => Some(&text.0),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use combine::Parser;
+    use combine::combinator::parser;
+
+    use parse_rsx::rsx_element_ignoring_ws;
+    use parse_span_types::RSXSourceStream;
+
+    fn parse(source: &str) -> RSXElement {
+        parser(rsx_element_ignoring_ws).parse(RSXSourceStream::new(source)).unwrap().0
+    }
+
+    fn print(source: &str, options: &RSXPrintOptions) -> String {
+        rsx_print(&parse(source), options)
+    }
+
+    #[test]
+    pub fn test_rsx_print_flat() {
+        let options = RSXPrintOptions::default();
+        assert_eq!(print("<div/>", &options), "<div/>");
+        assert_eq!(print("<div></div>", &options), "<div></div>");
+        assert_eq!(print("<div  />", &options), "<div/>");
+        assert_eq!(print("<foo.bar-baz/>", &options), "<foo.bar-baz/>");
+        assert_eq!(print("<  >  foo  </ >", &options), "<>  foo  </>");
+        assert_eq!(
+            print("<div a b='c' d={true} e={1} {...f}>g { h } <i/></div>", &options),
+            "<div a b='c' d={true} e={1} {...f}>g { h } <i/></div>"
+        );
+        assert_eq!(
+            print("<div a=0.05 b={0x1F} c={1_000u32} d=-1.5e-3f32/>", &options),
+            "<div a={0.05} b={0x1F} c={1_000u32} d={-1.5e-3f32}/>"
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_print_broken() {
+        let options = RSXPrintOptions {
+            indent_width: 2,
+            max_width: 20,
+            ..RSXPrintOptions::default()
+        };
+        assert_eq!(
+            print("<div><span>Hello</span><span>world</span></div>", &options),
+            "<div>\n  <span>Hello</span>\n  <span>world</span>\n</div>"
+        );
+        assert_eq!(
+            print("<div first='foo' second='bar'/>", &options),
+            "<div\n  first='foo'\n  second='bar'\n/>"
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_print_whitespace() {
+        let options = RSXPrintOptions {
+            max_width: 20,
+            ..RSXPrintOptions::default()
+        };
+        assert_eq!(
+            print("<div>Hello, <b>world</b>!<br/></div>", &options),
+            "<div>\n    Hello, <b>world</b>\n    !\n    <br/>\n</div>"
+        );
+        assert_eq!(
+            print("<pre>  Hello,\n    <b>world</b>!</pre>", &options),
+            "<pre>  Hello,\n    <b>world</b>!</pre>"
+        );
+
+        let options = RSXPrintOptions {
+            max_width: 20,
+            whitespace: RSXWhitespaceMode::Collapse,
+            ..RSXPrintOptions::default()
+        };
+        assert_eq!(
+            print("<div>Hello <b>world</b><br/></div>", &options),
+            "<div>Hello <b>world</b>\n    <br/>\n</div>"
+        );
+    }
+
+    #[test]
+    pub fn test_rsx_print_options() {
+        let options = RSXPrintOptions {
+            attribute_wrap: RSXAttributeWrap::Always,
+            quote_style: RSXQuoteStyle::Double,
+            self_close_empty: true,
+            ..RSXPrintOptions::default()
+        };
+        assert_eq!(
+            print("<div a='b\"c' d='e\\\\f'></div>", &options),
+            "<div\n    a=\"b\\\"c\"\n    d=\"e\\\\f\"\n/>"
+        );
+
+        let options = RSXPrintOptions {
+            quote_style: RSXQuoteStyle::Single,
+            ..RSXPrintOptions::default()
+        };
+        assert_eq!(print("<div a=\"b'c\"/>", &options), "<div a='b\\'c'/>");
+    }
+
+    #[test]
+    pub fn test_rsx_print_escaped_text() {
+        let options = RSXPrintOptions::default();
+        assert_eq!(
+            print("<p>1 &lt; 2 &amp;&amp; {x} &#123;y&#125; &gt; 0</p>", &options),
+            "<p>1 &lt; 2 &amp;&amp; {x} &#123;y&#125; &gt; 0</p>"
+        );
+        assert_eq!(print("<p>a&#10;b&#13;</p>", &options), "<p>a&#10;b&#13;</p>");
+        assert_eq!(print("<pre>a&#10;b</pre>", &options), "<pre>a\nb</pre>");
+
+        let options = RSXPrintOptions {
+            escape_entities: false,
+            ..RSXPrintOptions::default()
+        };
+        assert_eq!(print("<p>a &lt; b&#10;</p>", &options), "<p>a < b\n</p>");
+    }
+
+    #[test]
+    pub fn test_rsx_print_code_block_elements() {
+        let options = RSXPrintOptions::default();
+        assert_eq!(
+            print("<div>{ if foo { <bar/> } else { <baz>{ <qux/> }</baz> } }</div>", &options),
+            "<div>{ if foo { <bar/> } else { <baz>{ <qux/> }</baz> } }</div>"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use parse_options_types::RSXWhitespaceMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RSXAttributeWrap {
+    // Put every attribute on its own line, but only if the opening tag
+    // doesn't fit on a single line.
+    Auto,
+    // Put every attribute on its own line whenever there's more than one.
+    Always,
+    Never
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RSXQuoteStyle {
+    Preserve,
+    Double,
+    Single
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RSXPrintOptions {
+    pub indent_width: usize,
+    pub max_width: usize,
+    pub attribute_wrap: RSXAttributeWrap,
+    pub quote_style: RSXQuoteStyle,
+    // Print normal elements without children as self-closing elements. They
+    // generate the same code, but no longer parse into an equal AST.
+    pub self_close_empty: bool,
+    // Escape `&` in text and attribute strings, so that they don't turn into
+    // character references when parsing with entity decoding enabled, along
+    // with `<`, `>`, `{`, `}` and line breaks in text. Text is printed as is
+    // otherwise, so it only parses back the same if it has none of those.
+    pub escape_entities: bool,
+    // The whitespace mode the output is going to be parsed with, which decides
+    // where line breaks can be inserted between children.
+    pub whitespace: RSXWhitespaceMode
+}
+
+impl Default for RSXPrintOptions {
+    fn default() -> Self {
+        RSXPrintOptions {
+            indent_width: 4,
+            max_width: 100,
+            attribute_wrap: RSXAttributeWrap::Auto,
+            quote_style: RSXQuoteStyle::Preserve,
+            self_close_empty: false,
+            escape_entities: true,
+            whitespace: RSXWhitespaceMode::Jsx
+        }
+    }
+}
@@ -9,6 +9,12 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
+use combine::Parser;
+use combine::combinator::parser;
+
+use parse_external::rsx_lex_code;
+use parse_external_types::RSXCodeGap;
+use parse_rsx::rsx_element_ignoring_ws;
 use types::{
     JSBool,
     JSDoubleStringCharacter,
@@ -16,6 +22,7 @@ use types::{
     JSIdentifierPart,
     JSIdentifierStart,
     JSNumber,
+    JSNumberKind,
     JSSingleStringCharacter,
     JSSingleStringCharacters,
     RSString,
@@ -28,15 +35,32 @@ use types::{
     RSXAttributes,
     RSXChild,
     RSXChildren,
+    RSXElement,
     RSXElementName,
     RSXIdentifier,
     RSXNormalElement,
     RSXOpeningElement,
+    RSXParsedExpression,
     RSXSelfClosingElement,
+    RSXSourceStream,
+    RSXSpans,
     RSXText,
     RSXTextCharacter
 };
 
+// Parses the source and calls `f` with the tree and the spans of its nodes,
+// which borrow it.
+pub fn with_spans<F, T>(source: &str, f: F) -> T
+where
+    F: FnOnce(&RSXElement, &RSXSpans) -> T
+{
+    let (element, rest) = parser(rsx_element_ignoring_ws)
+        .parse(RSXSourceStream::new(source))
+        .unwrap();
+    let spans = RSXSpans::new(&element, &rest.span_table()).unwrap();
+    f(&element, &spans)
+}
+
 // JS Types
 
 impl From<bool> for JSBool {
@@ -45,9 +69,23 @@ impl From<bool> for JSBool {
     }
 }
 
-impl From<f64> for JSNumber {
-    fn from(v: f64) -> Self {
-        JSNumber(v)
+impl From<(&'static str, JSNumberKind)> for JSNumber {
+    fn from((literal, kind): (&'static str, JSNumberKind)) -> Self {
+        JSNumber {
+            literal: literal.to_string(),
+            kind,
+            suffix: None
+        }
+    }
+}
+
+impl From<(&'static str, JSNumberKind, &'static str)> for JSNumber {
+    fn from((literal, kind, suffix): (&'static str, JSNumberKind, &'static str)) -> Self {
+        JSNumber {
+            literal: literal.to_string(),
+            kind,
+            suffix: Some(suffix.to_string())
+        }
     }
 }
 
@@ -91,7 +129,7 @@ impl From<&'static str> for JSIdentifierPart {
 
 impl From<&'static str> for RSString {
     fn from(v: &'static str) -> Self {
-        RSString(v.into())
+        v.chars().collect()
     }
 }
 
@@ -208,8 +246,8 @@ impl From<bool> for RSXAttributeValue {
     }
 }
 
-impl From<f64> for RSXAttributeValue {
-    fn from(v: f64) -> Self {
+impl From<JSNumber> for RSXAttributeValue {
+    fn from(v: JSNumber) -> Self {
         RSXAttributeValue::Number(v.into())
     }
 }
@@ -240,9 +278,9 @@ impl From<bool> for RSXAttributeBoolean {
     }
 }
 
-impl From<f64> for RSXAttributeNumber {
-    fn from(v: f64) -> Self {
-        RSXAttributeNumber(v)
+impl From<(&'static str, JSNumberKind)> for RSXAttributeNumber {
+    fn from(v: (&'static str, JSNumberKind)) -> Self {
+        RSXAttributeNumber(v.into())
     }
 }
 
@@ -281,3 +319,23 @@ impl From<char> for RSXTextCharacter {
         RSXTextCharacter(v)
     }
 }
+
+// RSX Code Blocks
+
+impl From<&'static str> for RSXParsedExpression {
+    fn from(code: &'static str) -> Self {
+        (vec![code], vec![]).into()
+    }
+}
+
+impl From<(Vec<&'static str>, Vec<RSXElement>)> for RSXParsedExpression {
+    fn from((code, elements): (Vec<&'static str>, Vec<RSXElement>)) -> Self {
+        let code = code.into_iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let gaps = vec![RSXCodeGap::Element; elements.len()];
+        RSXParsedExpression {
+            token_trees: rsx_lex_code(&code, &gaps).unwrap(),
+            elements,
+            code
+        }
+    }
+}
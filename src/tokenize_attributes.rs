@@ -9,7 +9,6 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the
 specific language governing permissions and limitations under the License.
 */
 
-use rsx_shared::types::KnownAttributeName;
 use self_tokenize_trait::{ToCustomTokens, Tokens};
 
 use parse_attributes_types::{
@@ -23,120 +22,87 @@ use parse_attributes_types::{
 };
 
 use parse_js_types::{JSDoubleStringCharacters, JSSingleStringCharacters};
+use tokenize_elements::rsx_element_tokens;
+use tokenize_external::rsx_expression_tokens;
+use tokenize_names::rsx_attribute_name_path;
+use tokenize_types::{CodegenBackend, DefaultCodegenBackend};
 
 impl ToCustomTokens for RSXAttributes {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        let attributes = &self.0;
-        tokens.append(quote! { vec!#attributes });
+        tokens.append(rsx_attributes_tokens(self, &DefaultCodegenBackend::default()));
     }
 }
 
 impl ToCustomTokens for RSXAttribute {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        match self {
-            &RSXAttribute::Named(ref n, ref v) => {
-                tokens.append(quote! { DOMAttribute::from((#n, #v)) });
-            }
-            &RSXAttribute::Spread(ref spread) => {
-                tokens.append(quote! { DOMAttribute::from(#spread) });
-            }
-        }
+        tokens.append(rsx_attribute_tokens(self, &DefaultCodegenBackend::default()));
     }
 }
 
 impl ToCustomTokens for RSXAttributeName {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        use self::KnownAttributeName::*;
-        match self {
-            // HTML global attributes
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("accesskey") => {
-                RSXAttributeName::KnownName(Accesskey).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("class") => {
-                RSXAttributeName::KnownName(Class).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("contenteditable") => {
-                RSXAttributeName::KnownName(CntEditable).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("contextmenu") => {
-                RSXAttributeName::KnownName(Contextmenu).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("dir") => {
-                RSXAttributeName::KnownName(Dir).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("draggable") => {
-                RSXAttributeName::KnownName(Draggable).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("dropzone") => {
-                RSXAttributeName::KnownName(Dropzone).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("hidden") => {
-                RSXAttributeName::KnownName(Hidden).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("id") => {
-                RSXAttributeName::KnownName(Id).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("lang") => {
-                RSXAttributeName::KnownName(Lang).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("spellcheck") => {
-                RSXAttributeName::KnownName(Spellcheck).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("src") => {
-                RSXAttributeName::KnownName(Src).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("style") => {
-                RSXAttributeName::KnownName(Style).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("tabindex") => {
-                RSXAttributeName::KnownName(Tabindex).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("title") => {
-                RSXAttributeName::KnownName(Title).to_custom_tokens(tokens);
-            }
-            &RSXAttributeName::Name(ref n) if n.0.eq_ignore_ascii_case("translate") => {
-                RSXAttributeName::KnownName(Translate).to_custom_tokens(tokens);
-            }
-
-            // Parsed
-            &RSXAttributeName::KnownName(ref n) => {
-                tokens.append(quote! { DOMAttributeName::from(#n) });
-            }
-            &RSXAttributeName::Name(ref n) => {
-                tokens.append(quote! { DOMAttributeName::from(#n) });
-            }
-            &RSXAttributeName::NamedspacedName(ref ns, ref n) => {
-                tokens.append(quote! { DOMAttributeName::from((#ns, #n)) });
-            }
-        }
+        tokens.append(rsx_attribute_name_tokens(self, &DefaultCodegenBackend::default()));
     }
 }
 
 impl ToCustomTokens for RSXAttributeValue {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        match self {
-            &RSXAttributeValue::Default => {
-                tokens.append(quote! { DOMAttributeValue::from(true) });
-            }
-            &RSXAttributeValue::Boolean(ref boolean) => {
-                tokens.append(quote! { DOMAttributeValue::from(#boolean) });
-            }
-            &RSXAttributeValue::Number(ref number) => {
-                tokens.append(quote! { DOMAttributeValue::from(#number) });
-            }
-            &RSXAttributeValue::Str(ref string) => {
-                tokens.append(quote! { DOMAttributeValue::from(#string) });
-            }
-            &RSXAttributeValue::Element(ref element) => {
-                tokens.append(quote! { DOMAttributeValue::from(#element) });
-            }
-            &RSXAttributeValue::CodeBlock(ref expression) => {
-                tokens.append(quote! { DOMAttributeValue::from(#expression) });
-            }
+        tokens.append(rsx_attribute_value_tokens(self, &DefaultCodegenBackend::default()));
+    }
+}
+
+pub fn rsx_attributes_tokens(attributes: &RSXAttributes, backend: &CodegenBackend) -> Tokens {
+    backend.attributes(
+        attributes
+            .0
+            .iter()
+            .map(|attribute| rsx_attribute_tokens(attribute, backend))
+            .collect()
+    )
+}
+
+pub fn rsx_attribute_tokens(attribute: &RSXAttribute, backend: &CodegenBackend) -> Tokens {
+    match attribute {
+        &RSXAttribute::Named(ref n, ref v) => backend.attribute(
+            rsx_attribute_name_tokens(n, backend),
+            rsx_attribute_value_tokens(v, backend)
+        ),
+        &RSXAttribute::Spread(ref spread) => {
+            backend.spread_attribute(rsx_expression_tokens(spread, backend))
+        }
+        &RSXAttribute::Error(ref error) => {
+            let mut tokens = Tokens::new();
+            error.to_custom_tokens(&mut tokens);
+            tokens
         }
     }
 }
 
+pub fn rsx_attribute_name_tokens(name: &RSXAttributeName, backend: &CodegenBackend) -> Tokens {
+    let name = match name {
+        &RSXAttributeName::Name(ref n) => {
+            rsx_attribute_name_path(&n.0, backend).unwrap_or_else(|| quote! { #n })
+        }
+        &RSXAttributeName::KnownName(ref n) => {
+            backend.path(&format!("KnownAttributeName::{:?}", n))
+        }
+        &RSXAttributeName::NamedspacedName(ref ns, ref n) => quote! { (#ns, #n) }
+    };
+    backend.attribute_name(name)
+}
+
+pub fn rsx_attribute_value_tokens(value: &RSXAttributeValue, backend: &CodegenBackend) -> Tokens {
+    let value = match value {
+        &RSXAttributeValue::Default => quote! { true },
+        &RSXAttributeValue::Boolean(ref boolean) => quote! { #boolean },
+        &RSXAttributeValue::Number(ref number) => quote! { #number },
+        &RSXAttributeValue::Str(ref string) => quote! { #string },
+        &RSXAttributeValue::Element(ref element) => rsx_element_tokens(element, backend),
+        &RSXAttributeValue::CodeBlock(ref expression) => rsx_expression_tokens(expression, backend)
+    };
+    backend.attribute_value(value)
+}
+
 impl ToCustomTokens for RSXAttributeBoolean {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
         self.0.to_custom_tokens(tokens);
@@ -144,7 +110,7 @@ impl ToCustomTokens for RSXAttributeBoolean {
 }
 impl ToCustomTokens for RSXAttributeNumber {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        self.0.to_custom_tokens(tokens);
+        tokens.append(self.0.rust_literal());
     }
 }
 
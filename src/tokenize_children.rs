@@ -12,21 +12,19 @@ specific language governing permissions and limitations under the License.
 use self_tokenize_trait::{ToCustomTokens, Tokens};
 
 use parse_children_types::{RSXChild, RSXChildren, RSXText};
+use tokenize_elements::rsx_element_tokens;
+use tokenize_external::rsx_expression_tokens;
+use tokenize_types::{CodegenBackend, DefaultCodegenBackend};
 
 impl ToCustomTokens for RSXChildren {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        let children = &self.0;
-        tokens.append(quote! { vec!#children });
+        tokens.append(rsx_children_tokens(self, &DefaultCodegenBackend::default()));
     }
 }
 
 impl ToCustomTokens for RSXChild {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        match self {
-            &RSXChild::Element(ref element) => element.to_custom_tokens(tokens),
-            &RSXChild::Text(ref text) => tokens.append(quote! { DOMNode::from(#text) }),
-            &RSXChild::CodeBlock(ref code) => tokens.append(quote! { DOMNode::from(#code) })
-        }
+        tokens.append(rsx_child_tokens(self, &DefaultCodegenBackend::default()));
     }
 }
 
@@ -36,3 +34,20 @@ impl ToCustomTokens for RSXText {
         tokens.append(quote! { #string_ref });
     }
 }
+
+pub fn rsx_children_tokens(children: &RSXChildren, backend: &CodegenBackend) -> Tokens {
+    backend.children(children.0.iter().map(|child| rsx_child_tokens(child, backend)).collect())
+}
+
+pub fn rsx_child_tokens(child: &RSXChild, backend: &CodegenBackend) -> Tokens {
+    match child {
+        &RSXChild::Element(ref element) => rsx_element_tokens(element, backend),
+        &RSXChild::Text(ref text) => backend.text(&text.0),
+        &RSXChild::CodeBlock(ref code) => backend.code_block(rsx_expression_tokens(code, backend)),
+        &RSXChild::Error(ref error) => {
+            let mut tokens = Tokens::new();
+            error.to_custom_tokens(&mut tokens);
+            tokens
+        }
+    }
+}
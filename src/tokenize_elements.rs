@@ -12,6 +12,7 @@ specific language governing permissions and limitations under the License.
 use rsx_shared::types::KnownElementName;
 use self_tokenize_trait::{ToCustomTokens, Tokens};
 
+use parse_children_types::{RSXChild, RSXChildren};
 use parse_elements_types::{
     RSXElement,
     RSXElementName,
@@ -19,336 +20,149 @@ use parse_elements_types::{
     RSXNormalElement,
     RSXSelfClosingElement
 };
+use tokenize_attributes::rsx_attributes_tokens;
+use tokenize_children::{rsx_child_tokens, rsx_children_tokens};
+use tokenize_hoisting::rsx_is_hoistable_element;
+use tokenize_names::rsx_element_name_path;
+use tokenize_types::{CodegenBackend, DefaultCodegenBackend};
 
 impl ToCustomTokens for RSXElement {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        match self {
-            &RSXElement::SelfClosing(ref element) => {
-                element.to_custom_tokens(tokens);
-            }
-            &RSXElement::Normal(ref element) => {
-                element.to_custom_tokens(tokens);
-            }
-        }
+        tokens.append(rsx_element_tokens(self, &DefaultCodegenBackend::default()));
     }
 }
 
 impl ToCustomTokens for RSXSelfClosingElement {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        let name = &self.0;
-        let attributes = &self.1;
-        let has_attributes = attributes.0.len() != 0;
-
-        if has_attributes {
-            tokens.append(quote! {
-                DOMNode::from((#name, DOMAttributes::from(#attributes)))
-            })
-        } else {
-            tokens.append(quote! {
-                DOMNode::from(#name)
-            })
-        }
+        tokens.append(rsx_self_closing_element_tokens(self, &DefaultCodegenBackend::default()));
     }
 }
 
 impl ToCustomTokens for RSXNormalElement {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        let name = &self.0;
-        let attributes = &self.1;
-        let children = &self.2;
-        let has_attributes = attributes.0.len() != 0;
-        let has_children = children.0.len() != 0;
-
-        if has_attributes && has_children {
-            tokens.append(quote! {
-                DOMNode::from((#name, #attributes, #children))
-            })
-        } else if has_attributes {
-            tokens.append(quote! {
-                DOMNode::from((#name, DOMAttributes::from(#attributes)))
-            })
-        } else if has_children {
-            tokens.append(quote! {
-                DOMNode::from((#name, DOMChildren::from(#children)))
-            })
-        } else {
-            tokens.append(quote! {
-                DOMNode::from(#name)
-            })
-        }
+        let backend = DefaultCodegenBackend::default();
+        let children = |children: &RSXChildren| rsx_children_tokens(children, &backend);
+        tokens.append(rsx_normal_element_tokens(self, &backend, &children));
     }
 }
 
-#[allow(unknown_lints, cyclomatic_complexity)]
 impl ToCustomTokens for RSXElementName {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        use self::KnownElementName::*;
-        match self {
-            // HTML content sectioning
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("address") => {
-                RSXElementName::KnownName(Address).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("article") => {
-                RSXElementName::KnownName(Article).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("aside") => {
-                RSXElementName::KnownName(Aside).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("footer") => {
-                RSXElementName::KnownName(Footer).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("header") => {
-                RSXElementName::KnownName(Header).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("nav") => {
-                RSXElementName::KnownName(Nav).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("section") => {
-                RSXElementName::KnownName(Section).to_custom_tokens(tokens);
-            }
-
-            // HTML text sectioning
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("hgroup") => {
-                RSXElementName::KnownName(Hgroup).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("h1") => {
-                RSXElementName::KnownName(H1).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("h2") => {
-                RSXElementName::KnownName(H2).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("h3") => {
-                RSXElementName::KnownName(H3).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("h4") => {
-                RSXElementName::KnownName(H4).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("h5") => {
-                RSXElementName::KnownName(H5).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("h6") => {
-                RSXElementName::KnownName(H6).to_custom_tokens(tokens);
-            }
-
-            // HTML text content
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("main") => {
-                RSXElementName::KnownName(Main).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("div") => {
-                RSXElementName::KnownName(Div).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("span") => {
-                RSXElementName::KnownName(Span).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("p") => {
-                RSXElementName::KnownName(P).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("ol") => {
-                RSXElementName::KnownName(Ol).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("ul") => {
-                RSXElementName::KnownName(Ul).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("li") => {
-                RSXElementName::KnownName(Li).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("dl") => {
-                RSXElementName::KnownName(Dl).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("dt") => {
-                RSXElementName::KnownName(Dt).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("dd") => {
-                RSXElementName::KnownName(Dd).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("figure") => {
-                RSXElementName::KnownName(Figure).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("figcaption") => {
-                RSXElementName::KnownName(Figcaption).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("hr") => {
-                RSXElementName::KnownName(Hr).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("pre") => {
-                RSXElementName::KnownName(Pre).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("blockquote") => {
-                RSXElementName::KnownName(Blockquote).to_custom_tokens(tokens);
-            }
-
-            // HTML inline text semantics
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("a") => {
-                RSXElementName::KnownName(A).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("b") => {
-                RSXElementName::KnownName(Bold).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("i") => {
-                RSXElementName::KnownName(Italic).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("u") => {
-                RSXElementName::KnownName(Underline).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("s") => {
-                RSXElementName::KnownName(Strikethrough).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("em") => {
-                RSXElementName::KnownName(Emphasis).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("mark") => {
-                RSXElementName::KnownName(Mark).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("q") => {
-                RSXElementName::KnownName(Quotation).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("cite") => {
-                RSXElementName::KnownName(Citation).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("code") => {
-                RSXElementName::KnownName(Code).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("data") => {
-                RSXElementName::KnownName(Data).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("time") => {
-                RSXElementName::KnownName(Time).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("sub") => {
-                RSXElementName::KnownName(Sub).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("sup") => {
-                RSXElementName::KnownName(Sup).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("br") => {
-                RSXElementName::KnownName(Br).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("wbr") => {
-                RSXElementName::KnownName(Wbr).to_custom_tokens(tokens);
-            }
+        tokens.append(rsx_element_name_tokens(self, &DefaultCodegenBackend::default()));
+    }
+}
 
-            // HTML media and links
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("img") => {
-                RSXElementName::KnownName(Image).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("area") => {
-                RSXElementName::KnownName(Area).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("map") => {
-                RSXElementName::KnownName(Map).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("audio") => {
-                RSXElementName::KnownName(Audio).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("video") => {
-                RSXElementName::KnownName(Video).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("track") => {
-                RSXElementName::KnownName(Track).to_custom_tokens(tokens);
-            }
+pub fn rsx_element_tokens(element: &RSXElement, backend: &CodegenBackend) -> Tokens {
+    if backend.hoist_static_subtrees() && rsx_is_hoistable_element(element) {
+        return backend.hoisted(rsx_static_element_tokens(element, backend));
+    }
+    let children = |children: &RSXChildren| rsx_children_tokens(children, backend);
+    rsx_element_tokens_with(element, backend, &children)
+}
 
-            // HTML forms
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("button") => {
-                RSXElementName::KnownName(Button).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("datalist") => {
-                RSXElementName::KnownName(Datalist).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("fieldset") => {
-                RSXElementName::KnownName(Fieldset).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("form") => {
-                RSXElementName::KnownName(Form).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("input") => {
-                RSXElementName::KnownName(Input).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("label") => {
-                RSXElementName::KnownName(Label).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("legend") => {
-                RSXElementName::KnownName(Legend).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("meter") => {
-                RSXElementName::KnownName(Meter).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("optgroup") => {
-                RSXElementName::KnownName(Optgroup).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("option") => {
-                RSXElementName::KnownName(Option).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("output") => {
-                RSXElementName::KnownName(Output).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("progress") => {
-                RSXElementName::KnownName(Progress).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("select") => {
-                RSXElementName::KnownName(Select).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("textarea") => {
-                RSXElementName::KnownName(Textarea).to_custom_tokens(tokens);
-            }
+// Generates the code of a hoisted subtree. Nothing nested inside it is hoisted
+// again, since the whole subtree is only built once anyway. Static subtrees
+// only contain elements in their children, never in attributes.
+fn rsx_static_element_tokens(element: &RSXElement, backend: &CodegenBackend) -> Tokens {
+    let children = |children: &RSXChildren| {
+        let children = children.0.iter().map(|child| match child {
+            &RSXChild::Element(ref element) => rsx_static_element_tokens(element, backend),
+            child => rsx_child_tokens(child, backend)
+        });
+        backend.children(children.collect())
+    };
+    rsx_element_tokens_with(element, backend, &children)
+}
 
-            // React Fiber components
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("fragment") => {
-                RSXElementName::KnownName(Fragment).to_custom_tokens(tokens);
-            }
+fn rsx_element_tokens_with(
+    element: &RSXElement,
+    backend: &CodegenBackend,
+    children_tokens: &Fn(&RSXChildren) -> Tokens
+) -> Tokens {
+    match element {
+        &RSXElement::SelfClosing(ref element) => rsx_self_closing_element_tokens(element, backend),
+        &RSXElement::Normal(ref element) => {
+            rsx_normal_element_tokens(element, backend, children_tokens)
+        }
+        &RSXElement::Fragment(ref children) => {
+            let name = RSXElementName::KnownName(KnownElementName::Fragment);
+            let has_children = children.0.len() != 0;
+
+            backend.element(
+                rsx_element_name_tokens(&name, backend),
+                None,
+                if has_children {
+                    Some(children_tokens(children))
+                } else {
+                    None
+                }
+            )
+        }
+        &RSXElement::Error(ref error) => {
+            let mut tokens = Tokens::new();
+            error.to_custom_tokens(&mut tokens);
+            tokens
+        }
+    }
+}
 
-            // React Native basic components
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("view") => {
-                RSXElementName::KnownName(View).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("text") => {
-                RSXElementName::KnownName(Text).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("image") => {
-                RSXElementName::KnownName(Image).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("textinput") => {
-                RSXElementName::KnownName(TextInput).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("scrollview") => {
-                RSXElementName::KnownName(ScrollView).to_custom_tokens(tokens);
-            }
+fn rsx_self_closing_element_tokens(
+    element: &RSXSelfClosingElement,
+    backend: &CodegenBackend
+) -> Tokens {
+    let attributes = &element.1;
+    let has_attributes = attributes.0.len() != 0;
 
-            // React Native user interface
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("picker") => {
-                RSXElementName::KnownName(Picker).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("slider") => {
-                RSXElementName::KnownName(Slider).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("switch") => {
-                RSXElementName::KnownName(Switch).to_custom_tokens(tokens);
-            }
+    backend.element(
+        rsx_element_name_tokens(&element.0, backend),
+        if has_attributes {
+            Some(rsx_attributes_tokens(attributes, backend))
+        } else {
+            None
+        },
+        None
+    )
+}
 
-            // React Native list views
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("flatlist") => {
-                RSXElementName::KnownName(FlatList).to_custom_tokens(tokens);
-            }
-            &RSXElementName::Name(ref n) if n.0.eq_ignore_ascii_case("sectionlist") => {
-                RSXElementName::KnownName(SectionList).to_custom_tokens(tokens);
-            }
+fn rsx_normal_element_tokens(
+    element: &RSXNormalElement,
+    backend: &CodegenBackend,
+    children_tokens: &Fn(&RSXChildren) -> Tokens
+) -> Tokens {
+    let attributes = &element.1;
+    let children = &element.2;
+    let has_attributes = attributes.0.len() != 0;
+    let has_children = children.0.len() != 0;
+
+    backend.element(
+        rsx_element_name_tokens(&element.0, backend),
+        if has_attributes {
+            Some(rsx_attributes_tokens(attributes, backend))
+        } else {
+            None
+        },
+        if has_children {
+            Some(children_tokens(children))
+        } else {
+            None
+        }
+    )
+}
 
-            // Parsed
-            &RSXElementName::KnownName(ref n) => {
-                tokens.append(quote! { DOMTagName::from(#n) });
-            }
-            &RSXElementName::Name(ref n) => {
-                tokens.append(quote! { DOMTagName::from(#n) });
-            }
-            &RSXElementName::NamedspacedName(ref ns, ref n) => {
-                tokens.append(quote! { DOMTagName::from((#ns, #n)) });
-            }
-            &RSXElementName::MemberExpression(ref member_expression) => {
-                let mut inner_tokens = Tokens::new();
-                member_expression.to_custom_tokens(&mut inner_tokens);
-                tokens.append(quote! { DOMTagName::from(#inner_tokens) });
-            }
+pub fn rsx_element_name_tokens(name: &RSXElementName, backend: &CodegenBackend) -> Tokens {
+    let name = match name {
+        &RSXElementName::Name(ref n) => {
+            rsx_element_name_path(&n.0, backend).unwrap_or_else(|| quote! { #n })
         }
-    }
+        &RSXElementName::KnownName(ref n) => backend.path(&format!("KnownElementName::{:?}", n)),
+        &RSXElementName::NamedspacedName(ref ns, ref n) => quote! { (#ns, #n) },
+        &RSXElementName::MemberExpression(ref member_expression) => {
+            let mut tokens = Tokens::new();
+            member_expression.to_custom_tokens(&mut tokens);
+            tokens
+        }
+    };
+    backend.element_name(name)
 }
 
 impl ToCustomTokens for RSXIdentifier {
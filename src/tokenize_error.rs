@@ -0,0 +1,22 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use self_tokenize_trait::{ToCustomTokens, Tokens};
+
+use parse_error_types::RSXErrorNode;
+
+impl ToCustomTokens for RSXErrorNode {
+    fn to_custom_tokens(&self, tokens: &mut Tokens) {
+        let message = format!("Invalid RSX at {}", self.0);
+        let message_ref: &str = message.as_ref();
+        tokens.append(quote! { compile_error!(#message_ref) });
+    }
+}
@@ -10,19 +10,58 @@ specific language governing permissions and limitations under the License.
 */
 
 use self_tokenize_trait::{ToCustomTokens, Tokens};
+use syn::{DelimToken, Token, TokenTree};
 
-use parse_external_types::RSXParsedExpression;
+use parse_elements_types::RSXElement;
+use parse_external_types::{RSXCodeTokenTree, RSXParsedExpression};
+use tokenize_elements::rsx_element_tokens;
+use tokenize_types::{CodegenBackend, DefaultCodegenBackend};
 
 impl ToCustomTokens for RSXParsedExpression {
     fn to_custom_tokens(&self, tokens: &mut Tokens) {
-        let mut code = format!("{}{}{}", "{", self.tokens, "}");
+        tokens.append(rsx_expression_tokens(self, &DefaultCodegenBackend::default()));
+    }
+}
 
-        self.elements.iter().for_each(|&(ref placeholder, ref element)| {
-            let mut inner_tokens = Tokens::new();
-            element.to_custom_tokens(&mut inner_tokens);
-            code = code.replace(placeholder.as_ref(), &inner_tokens.to_string());
-        });
+pub fn rsx_expression_tokens(expression: &RSXParsedExpression, backend: &CodegenBackend) -> Tokens {
+    let mut tokens = Tokens::new();
+    tokens.append("{");
+    rsx_code_tokens(&expression.token_trees, &expression.elements, backend, &mut tokens);
+    tokens.append("}");
+    tokens
+}
 
-        tokens.append(code);
+fn rsx_code_tokens(
+    trees: &[RSXCodeTokenTree],
+    elements: &[RSXElement],
+    backend: &CodegenBackend,
+    tokens: &mut Tokens
+) {
+    for tree in trees {
+        match tree {
+            // Line doc comments need to end with a line break, otherwise they
+            // would swallow the tokens after them.
+            &RSXCodeTokenTree::Token(Token::DocComment(ref doc)) => {
+                tokens.append(format!("{}\n", doc));
+            }
+            &RSXCodeTokenTree::Token(ref token) => {
+                let tree = TokenTree::Token(token.clone());
+                tokens.append(quote! { #tree });
+            }
+            &RSXCodeTokenTree::Delimited(ref delim, ref trees) => {
+                let (open, close) = match delim {
+                    &DelimToken::Paren => ("(", ")"),
+                    &DelimToken::Bracket => ("[", "]"),
+                    &DelimToken::Brace => ("{", "}")
+                };
+                tokens.append(open);
+                rsx_code_tokens(trees, elements, backend, tokens);
+                tokens.append(close);
+            }
+            &RSXCodeTokenTree::Literal(ref literal) => tokens.append(literal),
+            &RSXCodeTokenTree::Hole(index) => {
+                tokens.append(rsx_element_tokens(&elements[index], backend))
+            }
+        }
     }
 }
@@ -0,0 +1,96 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use parse_attributes_types::{RSXAttribute, RSXAttributeValue, RSXAttributes};
+use parse_children_types::{RSXChild, RSXChildren};
+use parse_elements_types::RSXElement;
+
+// Returns whether an element builds the same node on every render, which is
+// when neither it nor any of its descendants contain code blocks, spread
+// attributes or attributes whose values are elements.
+pub fn rsx_is_static_element(element: &RSXElement) -> bool {
+    match element {
+        &RSXElement::SelfClosing(ref element) => rsx_are_static_attributes(&element.1),
+        &RSXElement::Normal(ref element) => {
+            rsx_are_static_attributes(&element.1) && rsx_are_static_children(&element.2)
+        }
+        &RSXElement::Fragment(ref children) => rsx_are_static_children(children),
+        &RSXElement::Error(_) => false
+    }
+}
+
+// Only static elements with attributes or children are worth hoisting, since
+// building a bare element costs about as much as looking up a hoisted one.
+pub fn rsx_is_hoistable_element(element: &RSXElement) -> bool {
+    let has_content = match element {
+        &RSXElement::SelfClosing(ref element) => (element.1).0.len() != 0,
+        &RSXElement::Normal(ref element) => (element.1).0.len() != 0 || (element.2).0.len() != 0,
+        &RSXElement::Fragment(ref children) => children.0.len() != 0,
+        &RSXElement::Error(_) => false
+    };
+    has_content && rsx_is_static_element(element)
+}
+
+fn rsx_are_static_attributes(attributes: &RSXAttributes) -> bool {
+    attributes.0.iter().all(|attribute| match attribute {
+        &RSXAttribute::Named(_, ref value) => match value {
+            &RSXAttributeValue::Element(_) | &RSXAttributeValue::CodeBlock(_) => false,
+            _ => true
+        },
+        &RSXAttribute::Spread(_) | &RSXAttribute::Error(_) => false
+    })
+}
+
+fn rsx_are_static_children(children: &RSXChildren) -> bool {
+    children.0.iter().all(|child| match child {
+        &RSXChild::Element(ref element) => rsx_is_static_element(element),
+        &RSXChild::Text(_) => true,
+        &RSXChild::CodeBlock(_) | &RSXChild::Error(_) => false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use combine::Parser;
+    use combine::combinator::parser;
+
+    use parse_rsx::rsx_element_ignoring_ws;
+    use parse_span_types::RSXSourceStream;
+
+    fn element(source: &str) -> RSXElement {
+        let (element, _) = parser(rsx_element_ignoring_ws)
+            .parse(RSXSourceStream::new(source))
+            .unwrap();
+        element
+    }
+
+    #[test]
+    pub fn test_rsx_is_static_element() {
+        assert!(rsx_is_static_element(&element("<div class='a'>Hello <b>world</b></div>")));
+        assert!(rsx_is_static_element(&element("<input checked tabindex=1/>")));
+        assert!(rsx_is_static_element(&element("<></>")));
+        assert!(!rsx_is_static_element(&element("<div>Hello <b>{name}</b></div>")));
+        assert!(!rsx_is_static_element(&element("<div class={class}/>")));
+        assert!(!rsx_is_static_element(&element("<div {...props}/>")));
+        assert!(!rsx_is_static_element(&element("<div icon=<img/>/>")));
+    }
+
+    #[test]
+    pub fn test_rsx_is_hoistable_element() {
+        assert!(rsx_is_hoistable_element(&element("<div>Hello</div>")));
+        assert!(rsx_is_hoistable_element(&element("<br class='a'/>")));
+        assert!(!rsx_is_hoistable_element(&element("<br/>")));
+        assert!(!rsx_is_hoistable_element(&element("<div></div>")));
+        assert!(!rsx_is_hoistable_element(&element("<div>{x}</div>")));
+    }
+}
@@ -0,0 +1,123 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use self_tokenize_trait::Tokens;
+
+use tokenize_names_table::{KNOWN_ATTRIBUTE_NAMES, KNOWN_ELEMENT_NAMES};
+use tokenize_types::CodegenBackend;
+
+pub struct RSXNameTable<T: 'static> {
+    pub seed: u64,
+    pub displacements: &'static [(u32, u32)],
+    pub entries: &'static [(&'static str, T)]
+}
+
+impl<T> RSXNameTable<T> {
+    // Names are matched ignoring ASCII case, so looking one up takes a single
+    // hash and comparison, instead of comparing against every known name.
+    pub fn get(&self, name: &str) -> Option<&'static T> {
+        let (bucket, f1, f2) = rsx_name_hash(name, self.seed);
+        let (d1, d2) = self.displacements[bucket as usize % self.displacements.len()];
+        let index = d2.wrapping_add(f1.wrapping_mul(d1)).wrapping_add(f2) as usize;
+
+        let entry = &self.entries[index % self.entries.len()];
+        if entry.0.eq_ignore_ascii_case(name) {
+            Some(&entry.1)
+        } else {
+            None
+        }
+    }
+}
+
+// FNV-1a over the lowercase name, split into the bucket hash and the two hashes
+// the displacements are applied to.
+pub fn rsx_name_hash(name: &str, seed: u64) -> (u32, u32, u32) {
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for byte in name.bytes() {
+        hash ^= byte.to_ascii_lowercase() as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (
+        (hash >> 42) as u32 & 0x1f_ffff,
+        (hash >> 21) as u32 & 0x1f_ffff,
+        hash as u32 & 0x1f_ffff
+    )
+}
+
+// Returns the path to the given element name, when it's either registered with
+// the backend or known. Registered names take precedence.
+pub fn rsx_element_name_path(name: &str, backend: &CodegenBackend) -> Option<Tokens> {
+    match backend.names().and_then(|names| names.element(name)) {
+        Some(path) => Some(rsx_path_tokens(path)),
+        None => KNOWN_ELEMENT_NAMES
+            .get(name)
+            .map(|known| backend.path(&format!("KnownElementName::{:?}", known)))
+    }
+}
+
+// Returns the path to the given attribute name, when it's either registered
+// with the backend or known. Registered names take precedence.
+pub fn rsx_attribute_name_path(name: &str, backend: &CodegenBackend) -> Option<Tokens> {
+    match backend.names().and_then(|names| names.attribute(name)) {
+        Some(path) => Some(rsx_path_tokens(path)),
+        None => KNOWN_ATTRIBUTE_NAMES
+            .get(name)
+            .map(|known| backend.path(&format!("KnownAttributeName::{:?}", known)))
+    }
+}
+
+// Splits a path like `::foo::Bar` into tokens.
+pub fn rsx_path_tokens(path: &str) -> Tokens {
+    let mut tokens = Tokens::new();
+    if path.trim_left().starts_with("::") {
+        tokens.append("::");
+    }
+    let segments = path.split("::").map(str::trim).filter(|segment| !segment.is_empty());
+    for (index, segment) in segments.enumerate() {
+        if index != 0 {
+            tokens.append("::");
+        }
+        tokens.append(segment);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rsx_shared::types::{KnownAttributeName, KnownElementName};
+
+    #[test]
+    pub fn test_rsx_name_tables() {
+        for &(name, ref known) in KNOWN_ELEMENT_NAMES.entries {
+            assert_eq!(KNOWN_ELEMENT_NAMES.get(name), Some(known));
+            assert_eq!(KNOWN_ELEMENT_NAMES.get(&name.to_uppercase()), Some(known));
+        }
+        for &(name, ref known) in KNOWN_ATTRIBUTE_NAMES.entries {
+            assert_eq!(KNOWN_ATTRIBUTE_NAMES.get(name), Some(known));
+        }
+
+        assert_eq!(KNOWN_ELEMENT_NAMES.get("DiV"), Some(&KnownElementName::Div));
+        assert_eq!(KNOWN_ELEMENT_NAMES.get("TextInput"), Some(&KnownElementName::TextInput));
+        assert_eq!(KNOWN_ATTRIBUTE_NAMES.get("class"), Some(&KnownAttributeName::Class));
+        assert_eq!(KNOWN_ELEMENT_NAMES.get("foo"), None);
+        assert_eq!(KNOWN_ELEMENT_NAMES.get(""), None);
+        assert_eq!(KNOWN_ATTRIBUTE_NAMES.get("div"), None);
+    }
+
+    #[test]
+    pub fn test_rsx_path_tokens() {
+        assert_eq!(rsx_path_tokens("::foo::Bar").as_str(), ":: foo :: Bar");
+        assert_eq!(rsx_path_tokens("foo :: Bar").as_str(), "foo :: Bar");
+        assert_eq!(rsx_path_tokens("Bar").as_str(), "Bar");
+    }
+}
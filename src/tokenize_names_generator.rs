@@ -0,0 +1,285 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+// Generator for `tokenize_names_table.rs`. The name lists below are the source
+// of truth: `test_rsx_name_tables_generated` rebuilds the tables from them and
+// fails when the checked-in file is out of date. After changing a list, run
+//
+//     RSX_UPDATE_NAMES_TABLE=1 cargo test test_rsx_name_tables_generated
+//
+// to rewrite the file.
+
+use std::env;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::Write;
+
+use rsx_shared::types::{KnownAttributeName, KnownElementName};
+
+use tokenize_names::rsx_name_hash;
+
+// Seeds the search for each table starts from. Keeping them fixed keeps the
+// generated tables stable; the next seed is only tried if a list can't be
+// placed with the current one.
+const KNOWN_ELEMENT_NAMES_SEED: u64 = 1;
+const KNOWN_ATTRIBUTE_NAMES_SEED: u64 = 15;
+
+// Same bucket size as the `phf` crate.
+const LAMBDA: usize = 5;
+
+const KNOWN_ELEMENT_NAME_LIST: &'static [(&'static str, KnownElementName)] = &[
+    ("a", KnownElementName::A),
+    ("address", KnownElementName::Address),
+    ("area", KnownElementName::Area),
+    ("article", KnownElementName::Article),
+    ("aside", KnownElementName::Aside),
+    ("audio", KnownElementName::Audio),
+    ("b", KnownElementName::Bold),
+    ("blockquote", KnownElementName::Blockquote),
+    ("br", KnownElementName::Br),
+    ("button", KnownElementName::Button),
+    ("cite", KnownElementName::Citation),
+    ("code", KnownElementName::Code),
+    ("data", KnownElementName::Data),
+    ("datalist", KnownElementName::Datalist),
+    ("dd", KnownElementName::Dd),
+    ("div", KnownElementName::Div),
+    ("dl", KnownElementName::Dl),
+    ("dt", KnownElementName::Dt),
+    ("em", KnownElementName::Emphasis),
+    ("fieldset", KnownElementName::Fieldset),
+    ("figcaption", KnownElementName::Figcaption),
+    ("figure", KnownElementName::Figure),
+    ("flatlist", KnownElementName::FlatList),
+    ("footer", KnownElementName::Footer),
+    ("form", KnownElementName::Form),
+    ("fragment", KnownElementName::Fragment),
+    ("h1", KnownElementName::H1),
+    ("h2", KnownElementName::H2),
+    ("h3", KnownElementName::H3),
+    ("h4", KnownElementName::H4),
+    ("h5", KnownElementName::H5),
+    ("h6", KnownElementName::H6),
+    ("header", KnownElementName::Header),
+    ("hgroup", KnownElementName::Hgroup),
+    ("hr", KnownElementName::Hr),
+    ("i", KnownElementName::Italic),
+    ("image", KnownElementName::Image),
+    ("img", KnownElementName::Image),
+    ("input", KnownElementName::Input),
+    ("label", KnownElementName::Label),
+    ("legend", KnownElementName::Legend),
+    ("li", KnownElementName::Li),
+    ("main", KnownElementName::Main),
+    ("map", KnownElementName::Map),
+    ("mark", KnownElementName::Mark),
+    ("meter", KnownElementName::Meter),
+    ("nav", KnownElementName::Nav),
+    ("ol", KnownElementName::Ol),
+    ("optgroup", KnownElementName::Optgroup),
+    ("option", KnownElementName::Option),
+    ("output", KnownElementName::Output),
+    ("p", KnownElementName::P),
+    ("picker", KnownElementName::Picker),
+    ("pre", KnownElementName::Pre),
+    ("progress", KnownElementName::Progress),
+    ("q", KnownElementName::Quotation),
+    ("s", KnownElementName::Strikethrough),
+    ("scrollview", KnownElementName::ScrollView),
+    ("section", KnownElementName::Section),
+    ("sectionlist", KnownElementName::SectionList),
+    ("select", KnownElementName::Select),
+    ("slider", KnownElementName::Slider),
+    ("span", KnownElementName::Span),
+    ("sub", KnownElementName::Sub),
+    ("sup", KnownElementName::Sup),
+    ("switch", KnownElementName::Switch),
+    ("text", KnownElementName::Text),
+    ("textarea", KnownElementName::Textarea),
+    ("textinput", KnownElementName::TextInput),
+    ("time", KnownElementName::Time),
+    ("track", KnownElementName::Track),
+    ("u", KnownElementName::Underline),
+    ("ul", KnownElementName::Ul),
+    ("video", KnownElementName::Video),
+    ("view", KnownElementName::View),
+    ("wbr", KnownElementName::Wbr),
+];
+
+const KNOWN_ATTRIBUTE_NAME_LIST: &'static [(&'static str, KnownAttributeName)] = &[
+    ("accesskey", KnownAttributeName::Accesskey),
+    ("class", KnownAttributeName::Class),
+    ("contenteditable", KnownAttributeName::CntEditable),
+    ("contextmenu", KnownAttributeName::Contextmenu),
+    ("dir", KnownAttributeName::Dir),
+    ("draggable", KnownAttributeName::Draggable),
+    ("dropzone", KnownAttributeName::Dropzone),
+    ("hidden", KnownAttributeName::Hidden),
+    ("id", KnownAttributeName::Id),
+    ("lang", KnownAttributeName::Lang),
+    ("spellcheck", KnownAttributeName::Spellcheck),
+    ("src", KnownAttributeName::Src),
+    ("style", KnownAttributeName::Style),
+    ("tabindex", KnownAttributeName::Tabindex),
+    ("title", KnownAttributeName::Title),
+    ("translate", KnownAttributeName::Translate),
+];
+
+struct RSXNameTableLayout {
+    seed: u64,
+    displacements: Vec<(u32, u32)>,
+    slots: Vec<usize>
+}
+
+fn rsx_name_slot(f1: u32, f2: u32, (d1, d2): (u32, u32), len: usize) -> usize {
+    d2.wrapping_add(f1.wrapping_mul(d1)).wrapping_add(f2) as usize % len
+}
+
+// Places every name in its own slot by searching, bucket by bucket and largest
+// bucket first, for the first pair of displacements which only hits free slots.
+fn rsx_name_table_layout_with_seed(names: &[&str], seed: u64) -> Option<RSXNameTableLayout> {
+    let len = names.len();
+    let hashes: Vec<_> = names.iter().map(|name| rsx_name_hash(name, seed)).collect();
+
+    let mut buckets: Vec<(usize, Vec<usize>)> =
+        (0..(len + LAMBDA - 1) / LAMBDA).map(|index| (index, vec![])).collect();
+    let buckets_len = buckets.len();
+    for (key, &(bucket, _, _)) in hashes.iter().enumerate() {
+        buckets[bucket as usize % buckets_len].1.push(key);
+    }
+    buckets.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
+
+    let mut displacements = vec![(0, 0); buckets_len];
+    let mut slots: Vec<Option<usize>> = vec![None; len];
+
+    'buckets: for &(index, ref keys) in &buckets {
+        for d1 in 0..len as u32 {
+            'displacements: for d2 in 0..len as u32 {
+                let mut taken = vec![];
+                for &key in keys {
+                    let (_, f1, f2) = hashes[key];
+                    let slot = rsx_name_slot(f1, f2, (d1, d2), len);
+                    if slots[slot].is_some() || taken.contains(&slot) {
+                        continue 'displacements;
+                    }
+                    taken.push(slot);
+                }
+                for (&key, slot) in keys.iter().zip(taken) {
+                    slots[slot] = Some(key);
+                }
+                displacements[index] = (d1, d2);
+                continue 'buckets;
+            }
+        }
+        return None;
+    }
+
+    Some(RSXNameTableLayout {
+        seed: seed,
+        displacements,
+        slots: slots.into_iter().map(Option::unwrap).collect()
+    })
+}
+
+fn rsx_name_table_layout(names: &[&str], seed: u64) -> RSXNameTableLayout {
+    (seed..)
+        .filter_map(|seed| rsx_name_table_layout_with_seed(names, seed))
+        .next()
+        .unwrap()
+}
+
+fn rsx_render_name_table<T: Debug>(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    list: &[(&str, T)],
+    seed: u64
+) {
+    let names: Vec<_> = list.iter().map(|&(name, _)| name.to_lowercase()).collect();
+    let names: Vec<_> = names.iter().map(String::as_str).collect();
+    let layout = rsx_name_table_layout(&names, seed);
+
+    out.push_str(&format!(
+        "pub static {}: RSXNameTable<{}> = RSXNameTable {{\n",
+        name,
+        kind
+    ));
+    out.push_str(&format!("    seed: {},\n", layout.seed));
+    out.push_str("    displacements: &[\n");
+    for &(d1, d2) in &layout.displacements {
+        out.push_str(&format!("        ({}, {}),\n", d1, d2));
+    }
+    out.push_str("    ],\n");
+    out.push_str("    entries: &[\n");
+    for &key in &layout.slots {
+        out.push_str(&format!("        ({:?}, {}::{:?}),\n", names[key], kind, list[key].1));
+    }
+    out.push_str("    ]\n");
+    out.push_str("};\n");
+}
+
+const HEADER: &'static str = r#"/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+// Perfect hash tables of the element and attribute names that generated code
+// refers to through the `KnownElementName` and `KnownAttributeName` enums.
+// Generated by `tokenize_names_generator.rs`, which hashes every lowercase name
+// with `rsx_name_hash` and searches for per bucket displacements which put each
+// name in its own slot, the same way the `phf` crate does. Don't edit by hand;
+// change the lists there and run
+//
+//     RSX_UPDATE_NAMES_TABLE=1 cargo test test_rsx_name_tables_generated
+
+
+use rsx_shared::types::{KnownAttributeName, KnownElementName};
+
+use tokenize_names::RSXNameTable;
+
+"#;
+
+pub fn rsx_render_name_tables() -> String {
+    let mut out = String::from(HEADER);
+    rsx_render_name_table(
+        &mut out,
+        "KNOWN_ELEMENT_NAMES",
+        "KnownElementName",
+        KNOWN_ELEMENT_NAME_LIST,
+        KNOWN_ELEMENT_NAMES_SEED
+    );
+    out.push_str("\n");
+    rsx_render_name_table(
+        &mut out,
+        "KNOWN_ATTRIBUTE_NAMES",
+        "KnownAttributeName",
+        KNOWN_ATTRIBUTE_NAME_LIST,
+        KNOWN_ATTRIBUTE_NAMES_SEED
+    );
+    out
+}
+
+#[test]
+pub fn test_rsx_name_tables_generated() {
+    let generated = rsx_render_name_tables();
+    if env::var_os("RSX_UPDATE_NAMES_TABLE").is_some() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tokenize_names_table.rs");
+        File::create(path).unwrap().write_all(generated.as_bytes()).unwrap();
+    } else {
+        assert_eq!(generated, include_str!("tokenize_names_table.rs"));
+    }
+}
@@ -0,0 +1,152 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+// Perfect hash tables of the element and attribute names that generated code
+// refers to through the `KnownElementName` and `KnownAttributeName` enums.
+// Generated by `tokenize_names_generator.rs`, which hashes every lowercase name
+// with `rsx_name_hash` and searches for per bucket displacements which put each
+// name in its own slot, the same way the `phf` crate does. Don't edit by hand;
+// change the lists there and run
+//
+//     RSX_UPDATE_NAMES_TABLE=1 cargo test test_rsx_name_tables_generated
+
+
+use rsx_shared::types::{KnownAttributeName, KnownElementName};
+
+use tokenize_names::RSXNameTable;
+
+pub static KNOWN_ELEMENT_NAMES: RSXNameTable<KnownElementName> = RSXNameTable {
+    seed: 1,
+    displacements: &[
+        (1, 0),
+        (0, 56),
+        (2, 65),
+        (0, 0),
+        (3, 51),
+        (2, 4),
+        (16, 7),
+        (0, 3),
+        (0, 2),
+        (0, 6),
+        (7, 67),
+        (1, 7),
+        (0, 59),
+        (1, 33),
+        (3, 26),
+        (4, 18),
+    ],
+    entries: &[
+        ("option", KnownElementName::Option),
+        ("pre", KnownElementName::Pre),
+        ("text", KnownElementName::Text),
+        ("header", KnownElementName::Header),
+        ("span", KnownElementName::Span),
+        ("sub", KnownElementName::Sub),
+        ("h2", KnownElementName::H2),
+        ("meter", KnownElementName::Meter),
+        ("code", KnownElementName::Code),
+        ("h5", KnownElementName::H5),
+        ("q", KnownElementName::Quotation),
+        ("section", KnownElementName::Section),
+        ("h6", KnownElementName::H6),
+        ("area", KnownElementName::Area),
+        ("track", KnownElementName::Track),
+        ("datalist", KnownElementName::Datalist),
+        ("div", KnownElementName::Div),
+        ("form", KnownElementName::Form),
+        ("br", KnownElementName::Br),
+        ("sectionlist", KnownElementName::SectionList),
+        ("picker", KnownElementName::Picker),
+        ("hgroup", KnownElementName::Hgroup),
+        ("h4", KnownElementName::H4),
+        ("image", KnownElementName::Image),
+        ("fragment", KnownElementName::Fragment),
+        ("output", KnownElementName::Output),
+        ("ol", KnownElementName::Ol),
+        ("ul", KnownElementName::Ul),
+        ("data", KnownElementName::Data),
+        ("footer", KnownElementName::Footer),
+        ("hr", KnownElementName::Hr),
+        ("cite", KnownElementName::Citation),
+        ("time", KnownElementName::Time),
+        ("label", KnownElementName::Label),
+        ("wbr", KnownElementName::Wbr),
+        ("flatlist", KnownElementName::FlatList),
+        ("figcaption", KnownElementName::Figcaption),
+        ("sup", KnownElementName::Sup),
+        ("dt", KnownElementName::Dt),
+        ("aside", KnownElementName::Aside),
+        ("dd", KnownElementName::Dd),
+        ("img", KnownElementName::Image),
+        ("video", KnownElementName::Video),
+        ("scrollview", KnownElementName::ScrollView),
+        ("li", KnownElementName::Li),
+        ("b", KnownElementName::Bold),
+        ("input", KnownElementName::Input),
+        ("map", KnownElementName::Map),
+        ("s", KnownElementName::Strikethrough),
+        ("textinput", KnownElementName::TextInput),
+        ("dl", KnownElementName::Dl),
+        ("nav", KnownElementName::Nav),
+        ("textarea", KnownElementName::Textarea),
+        ("i", KnownElementName::Italic),
+        ("mark", KnownElementName::Mark),
+        ("optgroup", KnownElementName::Optgroup),
+        ("progress", KnownElementName::Progress),
+        ("em", KnownElementName::Emphasis),
+        ("figure", KnownElementName::Figure),
+        ("view", KnownElementName::View),
+        ("button", KnownElementName::Button),
+        ("h3", KnownElementName::H3),
+        ("u", KnownElementName::Underline),
+        ("fieldset", KnownElementName::Fieldset),
+        ("slider", KnownElementName::Slider),
+        ("main", KnownElementName::Main),
+        ("a", KnownElementName::A),
+        ("p", KnownElementName::P),
+        ("legend", KnownElementName::Legend),
+        ("h1", KnownElementName::H1),
+        ("select", KnownElementName::Select),
+        ("address", KnownElementName::Address),
+        ("article", KnownElementName::Article),
+        ("audio", KnownElementName::Audio),
+        ("blockquote", KnownElementName::Blockquote),
+        ("switch", KnownElementName::Switch),
+    ]
+};
+
+pub static KNOWN_ATTRIBUTE_NAMES: RSXNameTable<KnownAttributeName> = RSXNameTable {
+    seed: 15,
+    displacements: &[
+        (2, 5),
+        (0, 1),
+        (9, 7),
+        (0, 0),
+    ],
+    entries: &[
+        ("id", KnownAttributeName::Id),
+        ("dir", KnownAttributeName::Dir),
+        ("tabindex", KnownAttributeName::Tabindex),
+        ("hidden", KnownAttributeName::Hidden),
+        ("title", KnownAttributeName::Title),
+        ("contextmenu", KnownAttributeName::Contextmenu),
+        ("lang", KnownAttributeName::Lang),
+        ("translate", KnownAttributeName::Translate),
+        ("src", KnownAttributeName::Src),
+        ("draggable", KnownAttributeName::Draggable),
+        ("class", KnownAttributeName::Class),
+        ("accesskey", KnownAttributeName::Accesskey),
+        ("contenteditable", KnownAttributeName::CntEditable),
+        ("spellcheck", KnownAttributeName::Spellcheck),
+        ("dropzone", KnownAttributeName::Dropzone),
+        ("style", KnownAttributeName::Style),
+    ]
+};
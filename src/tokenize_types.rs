@@ -0,0 +1,241 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use self_tokenize_trait::Tokens;
+
+use tokenize_names::rsx_path_tokens;
+
+pub const RSX_DEFAULT_PATH_PREFIX: &str = "::rsx_dom::types";
+
+// Decides which types generated code builds nodes out of. Every hook receives
+// the code already generated for its parts, and returns the code for the whole.
+// All hooks default to building `DOMNode`s, so a backend only needs to override
+// the ones its runtime does differently.
+pub trait CodegenBackend {
+    // The path every type in generated code is referred to through, including
+    // the known element and attribute name enums. Absolute by default, so that
+    // generated code doesn't depend on what's imported where it's expanded.
+    // Types are referred to by their bare names when this is empty.
+    fn path_prefix(&self) -> &str {
+        RSX_DEFAULT_PATH_PREFIX
+    }
+
+    // Returns the path to a type, or to an enum variant like
+    // `KnownElementName::Div`, through the path prefix.
+    fn path(&self, name: &str) -> Tokens {
+        let prefix = self.path_prefix();
+        if prefix.trim().is_empty() {
+            rsx_path_tokens(name)
+        } else {
+            rsx_path_tokens(&format!("{}::{}", prefix, name))
+        }
+    }
+
+    // Element and attribute names to refer to through custom paths, on top of
+    // the known names.
+    fn names(&self) -> Option<&NameRegistry> {
+        None
+    }
+
+    // The type of the nodes generated code builds, where it has to be spelled
+    // out, e.g. for storing hoisted subtrees. Backends whose node type takes
+    // parameters return it along with them.
+    fn node_type(&self) -> Tokens {
+        self.path("DOMNode")
+    }
+
+    // Whether elements which build the same node on every render are built only
+    // once, see `hoisted`.
+    fn hoist_static_subtrees(&self) -> bool {
+        false
+    }
+
+    // Called with the code for a static subtree, and returns the code which the
+    // render expression uses instead. By default, the subtree is built the first
+    // time it's rendered on each thread, and every render gets a clone of it,
+    // which requires the node type to implement `Clone`.
+    fn hoisted(&self, node: Tokens) -> Tokens {
+        let node_type = self.node_type();
+        quote! {{
+            thread_local! {
+                static __RSX_STATIC_NODE: #node_type = #node;
+            }
+            __RSX_STATIC_NODE.with(|node| ::std::clone::Clone::clone(node))
+        }}
+    }
+
+    // Called with the path to a `KnownElementName` variant, a string, a
+    // `(namespace, name)` tuple of strings, or the member expression's names.
+    fn element_name(&self, name: Tokens) -> Tokens {
+        let tag_name = self.path("DOMTagName");
+        quote! { #tag_name::from(#name) }
+    }
+
+    // Attributes and children are `None` when the element doesn't have any.
+    fn element(
+        &self,
+        name: Tokens,
+        attributes: Option<Tokens>,
+        children: Option<Tokens>
+    ) -> Tokens {
+        let node = self.path("DOMNode");
+        match (attributes, children) {
+            (Some(attributes), Some(children)) => {
+                quote! { #node::from((#name, #attributes, #children)) }
+            }
+            (Some(attributes), None) => {
+                let dom_attributes = self.path("DOMAttributes");
+                quote! { #node::from((#name, #dom_attributes::from(#attributes))) }
+            }
+            (None, Some(children)) => {
+                let dom_children = self.path("DOMChildren");
+                quote! { #node::from((#name, #dom_children::from(#children))) }
+            }
+            (None, None) => quote! { #node::from(#name) }
+        }
+    }
+
+    fn attributes(&self, attributes: Vec<Tokens>) -> Tokens {
+        quote! { vec!#attributes }
+    }
+
+    fn attribute(&self, name: Tokens, value: Tokens) -> Tokens {
+        let attribute = self.path("DOMAttribute");
+        quote! { #attribute::from((#name, #value)) }
+    }
+
+    fn spread_attribute(&self, code_block: Tokens) -> Tokens {
+        let attribute = self.path("DOMAttribute");
+        quote! { #attribute::from(#code_block) }
+    }
+
+    // Called with the path to a `KnownAttributeName` variant, a string, or a
+    // `(namespace, name)` tuple of strings.
+    fn attribute_name(&self, name: Tokens) -> Tokens {
+        let attribute_name = self.path("DOMAttributeName");
+        quote! { #attribute_name::from(#name) }
+    }
+
+    // Called with a literal, an element, or a code block. Attributes without a
+    // value are `true`.
+    fn attribute_value(&self, value: Tokens) -> Tokens {
+        let attribute_value = self.path("DOMAttributeValue");
+        quote! { #attribute_value::from(#value) }
+    }
+
+    fn children(&self, children: Vec<Tokens>) -> Tokens {
+        quote! { vec!#children }
+    }
+
+    fn text(&self, text: &str) -> Tokens {
+        let node = self.path("DOMNode");
+        quote! { #node::from(#text) }
+    }
+
+    // Only called for code blocks which are children. The code is a block
+    // expression, with the elements inside it already generated.
+    fn code_block(&self, code_block: Tokens) -> Tokens {
+        let node = self.path("DOMNode");
+        quote! { #node::from(#code_block) }
+    }
+}
+
+// Element and attribute names which generated code refers to through paths of
+// the caller's choosing, e.g. variants of a design system's own enum, instead
+// of as strings. Names are matched ignoring ASCII case, and take precedence
+// over the known names.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NameRegistry {
+    elements: HashMap<String, String>,
+    attributes: HashMap<String, String>
+}
+
+impl NameRegistry {
+    pub fn new() -> Self {
+        NameRegistry::default()
+    }
+
+    // The path is used as is, e.g. `::my_crate::Elements::Card`.
+    pub fn register_element<N, P>(&mut self, name: N, path: P) -> &mut Self
+    where
+        N: AsRef<str>,
+        P: Into<String>
+    {
+        self.elements.insert(name.as_ref().to_ascii_lowercase(), path.into());
+        self
+    }
+
+    pub fn register_attribute<N, P>(&mut self, name: N, path: P) -> &mut Self
+    where
+        N: AsRef<str>,
+        P: Into<String>
+    {
+        self.attributes.insert(name.as_ref().to_ascii_lowercase(), path.into());
+        self
+    }
+
+    pub fn element(&self, name: &str) -> Option<&str> {
+        self.elements.get(&name.to_ascii_lowercase()).map(|path| path.as_str())
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(&name.to_ascii_lowercase()).map(|path| path.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultCodegenBackend {
+    pub path_prefix: String,
+    pub names: NameRegistry,
+    pub hoist_static_subtrees: bool
+}
+
+impl DefaultCodegenBackend {
+    pub fn with_path_prefix<S>(path_prefix: S) -> Self
+    where
+        S: Into<String>
+    {
+        DefaultCodegenBackend {
+            path_prefix: path_prefix.into(),
+            names: NameRegistry::new(),
+            hoist_static_subtrees: false
+        }
+    }
+
+    pub fn with_names(names: NameRegistry) -> Self {
+        DefaultCodegenBackend {
+            names,
+            ..DefaultCodegenBackend::default()
+        }
+    }
+}
+
+impl Default for DefaultCodegenBackend {
+    fn default() -> Self {
+        DefaultCodegenBackend::with_path_prefix(RSX_DEFAULT_PATH_PREFIX)
+    }
+}
+
+impl CodegenBackend for DefaultCodegenBackend {
+    fn path_prefix(&self) -> &str {
+        &self.path_prefix
+    }
+
+    fn names(&self) -> Option<&NameRegistry> {
+        Some(&self.names)
+    }
+
+    fn hoist_static_subtrees(&self) -> bool {
+        self.hoist_static_subtrees
+    }
+}
@@ -12,7 +12,24 @@ specific language governing permissions and limitations under the License.
 extern crate rsx_parser;
 extern crate self_tokenize_trait;
 
-use rsx_parser::parse;
+use rsx_parser::{
+    convert_jsx,
+    html_to_rsx,
+    import_html,
+    parse,
+    parse_complete,
+    parse_complete_with_options,
+    parse_cst,
+    parse_cst_with_options,
+    parse_recovering,
+    parse_with_spans,
+    print,
+    references,
+    render_html,
+    render_html_with,
+    tokenize_with_backend,
+    validate
+};
 use rsx_parser::types::*;
 use self_tokenize_trait::{ToCustomTokens, Tokens};
 
@@ -34,6 +51,567 @@ pub fn test_simple() {
     );
 }
 
+#[test]
+pub fn test_spans() {
+    let source = "<div>\n  <span hidden>Hello</span>\n</div>";
+    let (ast, table) = parse_complete(source).unwrap();
+    let spans = RSXSpans::new(&ast, &table).unwrap();
+
+    let span = ast.span(&spans);
+    assert_eq!((span.start.line, span.start.column), (1, 1));
+    assert_eq!((span.end.line, span.end.column), (3, 7));
+    assert_eq!((span.start.offset, span.end.offset), (0, source.len()));
+
+    let element = match ast {
+        RSXElement::Normal(RSXNormalElement(_, _, ref children)) => {
+            let span = children.span(&spans);
+            assert_eq!((span.start.line, span.start.column), (1, 6));
+            assert_eq!((span.end.line, span.end.column), (3, 1));
+
+            match children.0[0] {
+                RSXChild::Element(ref element) => element,
+                _ => panic!("Expected an element child")
+            }
+        }
+        _ => panic!("Expected a normal element")
+    };
+
+    let span = element.span(&spans);
+    assert_eq!((span.start.line, span.start.column), (2, 3));
+    assert_eq!((span.end.line, span.end.column), (2, 28));
+
+    match element {
+        &RSXElement::Normal(RSXNormalElement(ref name, ref attributes, ref children)) => {
+            let span = name.span(&spans);
+            assert_eq!((span.start.line, span.start.column), (2, 4));
+            assert_eq!((span.end.line, span.end.column), (2, 8));
+
+            let span = attributes.0[0].span(&spans);
+            assert_eq!((span.start.line, span.start.column), (2, 9));
+            assert_eq!((span.end.line, span.end.column), (2, 15));
+
+            let span = children.0[0].span(&spans);
+            assert_eq!((span.start.line, span.start.column), (2, 16));
+            assert_eq!((span.end.line, span.end.column), (2, 21));
+        }
+        _ => panic!("Expected a normal element")
+    }
+
+    // Nodes which aren't part of the tree have no span, and trees which don't
+    // match the table have no spans at all.
+    let other = RSXElement::SelfClosing(RSXSelfClosingElement(
+        RSXElementName::Name(RSXIdentifier("div".to_string())),
+        RSXAttributes::from(vec![])
+    ));
+    assert_eq!(other.span(&spans), RSXSpan::default());
+    assert_eq!(RSXSpans::new(&other, &table).err(), Some(RSXSpanMismatch));
+    assert_eq!(RSXParseError::from(RSXSpanMismatch).kind, RSXParseErrorKind::SpanMismatch);
+}
+
+#[test]
+pub fn test_error_mismatched_closing_tag() {
+    let source = "<div>\n  <span>Hello</b>\n</div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!((error.line(), error.column()), (2, 14));
+    assert_eq!(error.expected, vec!["</span>".to_string()]);
+    assert_eq!(error.found, "</b>");
+
+    let span = error.opening_span().unwrap();
+    assert_eq!((span.start.line, span.start.column), (2, 3));
+    assert_eq!((span.end.line, span.end.column), (2, 9));
+    match error.kind {
+        RSXParseErrorKind::MismatchedClosingTag(_) => {}
+        _ => panic!("Expected a mismatched closing tag error")
+    }
+}
+
+#[test]
+pub fn test_error_unterminated_string() {
+    let source = "<div class=\"foo></div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::UnterminatedString);
+    assert_eq!((error.line(), error.column()), (1, 12));
+    assert_eq!(error.found, "end of input");
+}
+
+#[test]
+pub fn test_error_invalid_escape() {
+    let source = "<div title=\"a\\qb\"></div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::InvalidEscape);
+    assert_eq!((error.line(), error.column()), (1, 14));
+
+    let source = "<div title=\"\\u{1F600}\\x41\\v\"></div>";
+    match parse_complete(source).unwrap().0 {
+        RSXElement::Normal(RSXNormalElement(_, ref attributes, _)) => match attributes.0[0] {
+            RSXAttribute::Named(_, RSXAttributeValue::Str(ref s)) => {
+                assert_eq!(s.value(), "\u{1f600}A\u{b}")
+            }
+            _ => panic!("Expected a string attribute")
+        },
+        _ => panic!("Expected a normal element")
+    }
+}
+
+#[test]
+pub fn test_error_unbalanced_code_block() {
+    let source = "<div>{ foo(</div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::UnbalancedCodeBlock);
+    assert_eq!((error.line(), error.column()), (1, 6));
+    assert_eq!(error.expected, vec!["}".to_string()]);
+    assert_eq!(error.found, "end of input");
+}
+
+#[test]
+pub fn test_error_unlexable_code_block() {
+    let source = "<div>{ a \\ b }</div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::InvalidExpression);
+    assert_eq!((error.line(), error.column()), (1, 6));
+    assert!(convert_jsx("<div>{`${a}`}</div>").is_ok());
+}
+
+#[test]
+pub fn test_error_unexpected_eof() {
+    let source = "<div>Hello";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::UnexpectedEof);
+    assert_eq!((error.line(), error.column()), (1, 11));
+    assert_eq!(error.found, "end of input");
+}
+
+#[test]
+pub fn test_error_invalid_attribute_value() {
+    let source = "<div class=></div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::InvalidAttributeValue);
+    assert_eq!((error.line(), error.column()), (1, 12));
+    assert_eq!(error.found, ">");
+
+    let source = "<div width={-1u8}></div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::InvalidAttributeValue);
+    assert_eq!((error.line(), error.column()), (1, 12));
+    assert_eq!(error.expected, vec!["u8 literal".to_string()]);
+    assert_eq!(error.found, "-1u8");
+
+    let source = "<div width=9223372036854775808></div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::InvalidAttributeValue);
+    assert_eq!(error.expected, vec!["i64 literal".to_string()]);
+    assert_eq!(error.found, "9223372036854775808");
+
+    let source = "<div width=1.5u32></div>";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::InvalidAttributeValue);
+    assert_eq!((error.line(), error.column()), (1, 12));
+    assert_eq!(error.found, "u");
+}
+
+#[test]
+pub fn test_error_trailing_input() {
+    let source = "<div></div>\n  foo";
+    assert_eq!(parse(source).unwrap().1, "foo");
+    assert_eq!(parse_with_spans(source).unwrap().2, "foo");
+
+    let error = parse_complete(source).unwrap_err();
+
+    assert_eq!(error.kind, RSXParseErrorKind::TrailingInput);
+    assert_eq!((error.line(), error.column()), (2, 3));
+    assert_eq!(error.found, "foo");
+    assert_eq!(
+        error.to_string(),
+        "2:3: unexpected trailing input, expected `end of input`, found `foo`"
+    );
+}
+
+#[test]
+pub fn test_recovering() {
+    let source = "<div><span>Hello</div>";
+    let (ast, table, diagnostics) = parse_recovering(source);
+
+    {
+        let spans = RSXSpans::new(&ast, &table).unwrap();
+        let span = ast.span(&spans);
+        assert_eq!((span.start.offset, span.end.offset), (0, source.len()));
+        if let RSXElement::Normal(RSXNormalElement(_, _, ref children)) = ast {
+            let span = children.0[0].span(&spans);
+            assert_eq!((span.start.offset, span.end.offset), (5, 16));
+        }
+    }
+
+    // The missing closing tag is an empty error node where `</div>` starts.
+    let position = RSXPosition {
+        offset: 16,
+        line: 1,
+        column: 17
+    };
+    assert_eq!(
+        ast,
+        RSXElement::Normal(RSXNormalElement(
+            RSXElementName::Name(RSXIdentifier("div".to_string())),
+            RSXAttributes::from(vec![]),
+            RSXChildren::from(vec![
+                RSXChild::Element(RSXElement::Normal(RSXNormalElement(
+                    RSXElementName::Name(RSXIdentifier("span".to_string())),
+                    RSXAttributes::from(vec![]),
+                    RSXChildren::from(vec![
+                        RSXChild::Text(RSXText("Hello".to_string())),
+                        RSXChild::Error(RSXErrorNode(RSXSpan::new(position, position))),
+                    ])
+                ))),
+            ])
+        ))
+    );
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].error.expected, vec!["</span>".to_string()]);
+    assert_eq!(diagnostics[0].error.found, "</div>");
+    assert_eq!((diagnostics[0].error.line(), diagnostics[0].error.column()), (1, 17));
+}
+
+#[test]
+pub fn test_recovering_multiple_errors() {
+    let source = "<div class=>\n  <p>{ foo(</p>\n  <b>bold\n</div>";
+    let (ast, table, diagnostics) = parse_recovering(source);
+    assert!(RSXSpans::new(&ast, &table).is_ok());
+
+    let kinds = diagnostics.iter().map(|d| d.error.kind).collect::<Vec<_>>();
+    assert_eq!(kinds.len(), 3);
+    assert_eq!(kinds[0], RSXParseErrorKind::InvalidAttributeValue);
+    assert_eq!(kinds[1], RSXParseErrorKind::UnbalancedCodeBlock);
+    assert_eq!(diagnostics[2].error.opening_span().unwrap().start.line, 3);
+
+    match ast {
+        RSXElement::Normal(RSXNormalElement(_, attributes, children)) => {
+            assert_eq!(attributes.0.len(), 1);
+            assert_eq!(children.0.len(), 2);
+        }
+        _ => panic!("Expected a normal element")
+    }
+}
+
+#[test]
+pub fn test_recovering_well_formed() {
+    let source = "<div><span>Hello</span></div>";
+    let (ast, _, diagnostics) = parse_recovering(source);
+
+    assert_eq!(ast, parse(source).unwrap().0);
+    assert_eq!(diagnostics.len(), 0);
+}
+
+#[test]
+pub fn test_cst_round_trip() {
+    let source = "\n<div // the root\n  class=\"foo\"   {...props}\n  /* hidden */ hidden\n>\n  \
+                  Hello   <b>world</b>  { greeting() }\n</ div >\n";
+    let cst = parse_cst(source).unwrap();
+
+    assert_eq!(cst.to_string(), source);
+    assert_eq!(cst.lower().unwrap(), parse_complete(source).unwrap());
+
+    let comments = cst.tokens()
+        .into_iter()
+        .filter(|token| token.kind == RSXCstKind::Comment)
+        .map(|token| token.text.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(comments, vec!["// the root\n".to_string(), "/* hidden */".to_string()]);
+}
+
+#[test]
+pub fn test_cst_edit() {
+    let mut cst = parse_cst("<div  class = 'foo' >bar</div>").unwrap();
+
+    if let RSXCstChild::Node(ref mut element) = cst.children[0] {
+        if let RSXCstChild::Node(ref mut attribute) = element.children[3] {
+            if let RSXCstChild::Token(ref mut value) = attribute.children[4] {
+                value.text = "'baz'".to_string();
+            }
+        }
+    }
+
+    assert_eq!(cst.to_string(), "<div  class = 'baz' >bar</div>");
+    let (ast, _) = parse_complete("<div class='baz'>bar</div>").unwrap();
+    assert_eq!(cst.lower().unwrap().0, ast);
+}
+
+#[test]
+pub fn test_cst_options() {
+    let source = "<div>{`${name}`}</div>";
+    let options = RSXParseOptions {
+        lex_code_blocks: false,
+        ..RSXParseOptions::default()
+    };
+    assert_eq!(parse_cst(source).is_err(), true);
+
+    let cst = parse_cst_with_options(source, options).unwrap();
+    assert_eq!(cst.lower().is_err(), true);
+    assert_eq!(
+        cst.lower_with_options(options).unwrap(),
+        parse_complete_with_options(source, options).unwrap()
+    );
+}
+
+#[test]
+pub fn test_render_html() {
+    let (ast, table) = parse_complete(
+        r#"<ul class="list">
+            <li><input type="checkbox" checked/> Fish &amp; chips</li>
+            <li>{item}<br/></li>
+        </ul>"#
+    ).unwrap();
+    let spans = RSXSpans::new(&ast, &table).unwrap();
+
+    let error = render_html(&ast, Some(&spans)).unwrap_err();
+    assert_eq!(error.kind, RSXHtmlErrorKind::UnevaluatedExpression);
+    assert_eq!(error.span.start.line, 3);
+
+    assert_eq!(
+        render_html_with(&ast, Some(&spans), |_| Some("<Tea>".to_string())),
+        Ok(
+            "<ul class=\"list\"><li><input type=\"checkbox\" checked> Fish &amp; chips</li><li>\
+             &lt;Tea&gt;<br></li></ul>"
+                .to_string()
+        )
+    );
+
+    let (imported, _) = import_html("<p title=a>b<br>c</p>");
+    assert_eq!(render_html(&imported, None), Ok("<p title=\"a\">b<br>c</p>".to_string()));
+}
+
+#[test]
+pub fn test_html_to_rsx() {
+    let html = r#"<!DOCTYPE html>
+        <form action="/search">
+            <label for=q>Search &amp; find</label>
+            <input id=q name=q required>
+            <!-- results go here -->
+        </form>"#;
+    let options = RSXPrintOptions::default();
+    let (rsx, diagnostics) = html_to_rsx(html, &options);
+    assert_eq!(diagnostics, vec![]);
+    assert_eq!(
+        rsx,
+        "<form action=\"/search\">\n    <label for=\"q\">Search &amp; find</label>\n    <input \
+         id=\"q\" name=\"q\" required/>\n    {/* results go here */}\n</form>"
+    );
+    assert_eq!(print(&parse_complete(&rsx).unwrap().0, &options), rsx);
+}
+
+#[test]
+pub fn test_convert_jsx() {
+    let conversion = convert_jsx(
+        r#"<ul className="todos">
+            {todos.map(todo => <li className={todo.done && "done"}>{`${todo.title}!`}</li>)}
+        </ul>"#
+    ).unwrap();
+
+    assert_eq!(
+        print(&conversion.element, &RSXPrintOptions::default()),
+        "<ul class=\"todos\">{todos.map(todo => <li class={todo.done && \"done\"}>{`${todo.title}\
+         !`}</li>)}</ul>"
+    );
+
+    let issues = conversion.issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        issues,
+        vec![
+            "2:19: array mapped with `.map()`, try `.iter().map(|todo| <li class={todo.done && \
+             \"done\"}>{`${todo.title}!`}</li>).collect::<Vec<_>>()`",
+            "2:69: template string, try `format!(\"{}!\", todo.title)`",
+        ]
+    );
+}
+
+#[test]
+pub fn test_print_round_trip() {
+    let sources = [
+        r#"
+        <Dropdown show={props.visible} class="dropdown" data-count={3} hidden>
+            A dropdown list
+            <Menu icon={props.menu.icon} onHide={|e: Event| println!("{:?}", e)} {...props}>
+                <MenuItem>Do Something</MenuItem>
+                {
+                    if should_do_something_fun() {
+                        <MenuItem label='fun' />
+                    } else {
+                        <MenuItem label=<b>else</b>></MenuItem>
+                    }
+                }
+            </Menu>
+            <>
+                <Fragment>nested</Fragment>
+            </>
+        </Dropdown>
+    "#,
+        "<p title='&quot;a&quot; &amp; b'>1 &lt; 2 &amp;&amp; 3 &gt; 2 &#123;x&#125;</p>",
+        "<p>first&#10;second&#13;&#10;third &amp;nbsp; {x} &lt;/p&gt;</p>",
+        "<div><pre>  keep\n    &lt;this&gt;&#10;</pre>&#10;</div>",
+    ];
+
+    let default = RSXPrintOptions::default();
+    let narrow = RSXPrintOptions {
+        indent_width: 2,
+        max_width: 40,
+        attribute_wrap: RSXAttributeWrap::Always,
+        quote_style: RSXQuoteStyle::Single,
+        ..RSXPrintOptions::default()
+    };
+    let wide = RSXPrintOptions {
+        max_width: 1000,
+        attribute_wrap: RSXAttributeWrap::Never,
+        quote_style: RSXQuoteStyle::Double,
+        ..RSXPrintOptions::default()
+    };
+
+    for source in sources.iter() {
+        let (ast, _) = parse(source).unwrap();
+        for options in &[&default, &narrow, &wide] {
+            let printed = print(&ast, options);
+            assert_eq!(parse_complete(&printed).unwrap().0, ast);
+            assert_eq!(print(&parse_complete(&printed).unwrap().0, options), printed);
+        }
+    }
+}
+
+#[test]
+pub fn test_entities() {
+    let source = "<p title='&quot;a&quot; &amp; &#x27;b&#39;'>\
+                  &copy; 2017 &mdash; &#169;&nbsp;&foo;\
+                  </p>";
+    let (ast, _) = parse_complete(source).unwrap();
+
+    match ast {
+        RSXElement::Normal(RSXNormalElement(_, ref attributes, ref children)) => {
+            match attributes.0[0] {
+                RSXAttribute::Named(_, RSXAttributeValue::Str(ref s)) => {
+                    assert_eq!(s.value(), "\"a\" & 'b'")
+                }
+                _ => panic!("Expected a string attribute")
+            }
+            match children.0[0] {
+                RSXChild::Text(ref text) => {
+                    assert_eq!(text.0, "\u{a9} 2017 \u{2014} \u{a9}\u{a0}&foo;")
+                }
+                _ => panic!("Expected a text child")
+            }
+        }
+        _ => panic!("Expected a normal element")
+    }
+
+    let options = RSXParseOptions {
+        decode_entities: false,
+        ..RSXParseOptions::default()
+    };
+    let (ast, _) = parse_complete_with_options("<p title='&amp;'>&copy;</p>", options).unwrap();
+    assert_eq!(print(&ast, &RSXPrintOptions::default()), "<p title='&amp;amp;'>&amp;copy;</p>");
+
+    let printed = print(&parse_complete(source).unwrap().0, &RSXPrintOptions::default());
+    assert_eq!(parse_complete(&printed).unwrap().0, parse_complete(source).unwrap().0);
+}
+
+fn children_text(element: &RSXElement) -> Vec<String> {
+    let children = match element {
+        &RSXElement::Normal(RSXNormalElement(_, _, ref children)) => children,
+        _ => panic!("Expected a normal element")
+    };
+    children
+        .0
+        .iter()
+        .map(|child| match child {
+            &RSXChild::Text(ref text) => text.0.clone(),
+            &RSXChild::Element(ref element) => format!("<{}>", children_text(element).concat()),
+            _ => "{}".to_string()
+        })
+        .collect()
+}
+
+#[test]
+pub fn test_whitespace() {
+    let source = "<div>\n  Hello,\n  world!  <b>x</b>  {y}\n  <pre>  a\n  b </pre>\n</div>";
+
+    let (ast, _) = parse_complete(source).unwrap();
+    assert_eq!(children_text(&ast), vec!["Hello, world!  ", "<x>", "  ", "{}", "<  a\n  b >"]);
+
+    let printed = print(&ast, &RSXPrintOptions::default());
+    assert_eq!(
+        printed,
+        "<div>\n    Hello, world!  <b>x</b>  {y}\n    <pre>  a\n  b </pre>\n</div>"
+    );
+    assert_eq!(parse_complete(&printed).unwrap().0, ast);
+
+    let options = RSXParseOptions {
+        whitespace: RSXWhitespaceMode::Collapse,
+        ..RSXParseOptions::default()
+    };
+    let (ast, _) = parse_complete_with_options(source, options).unwrap();
+    assert_eq!(children_text(&ast), vec![" Hello, world! ", "<x>", " ", "{}", "<  a\n  b >"]);
+
+    let options = RSXParseOptions {
+        whitespace: RSXWhitespaceMode::Preserve,
+        ..RSXParseOptions::default()
+    };
+    let (ast, _) = parse_complete_with_options(source, options).unwrap();
+    assert_eq!(
+        children_text(&ast),
+        vec!["\n  Hello,\n  world!  ", "<x>", "  ", "{}", "\n  ", "<  a\n  b >", "\n"]
+    );
+}
+
+#[test]
+pub fn test_references() {
+    let source = "<ul>{ props.items.iter().map(|item| <li>{ item.name }</li>) }</ul>";
+
+    let (ast, table) = parse_complete(source).unwrap();
+    let blocks = references(&ast, &RSXSpans::new(&ast, &table).unwrap());
+    assert_eq!(blocks.len(), 2);
+
+    let outer = &blocks[0];
+    assert_eq!(outer.span.start.column, 5);
+    assert_eq!(outer.kind, RSXExpressionKind::Child);
+    assert_eq!(outer.variables.iter().collect::<Vec<_>>(), vec!["props"]);
+    assert_eq!(outer.field_paths.iter().collect::<Vec<_>>(), vec!["props.items"]);
+
+    let inner = &blocks[1];
+    assert_eq!(inner.span.start.column, 41);
+    assert!(inner.variables.is_empty());
+    assert!(inner.field_paths.is_empty());
+}
+
+#[test]
+pub fn test_validate() {
+    let source = "<div {...props b}>{ items.map(|x| <li>{x}</li>) }{ x = }</div>";
+
+    let (ast, table) = parse_complete(source).unwrap();
+    let diagnostics = validate(&ast, &RSXSpans::new(&ast, &table).unwrap());
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "1:6: invalid Rust expression, found `failed to parse expression: \"{props b}\"`"
+    );
+    assert_eq!(diagnostics[1].span.start.column, 50);
+    assert_eq!(diagnostics[1].span.end.column, 57);
+
+    let options = RSXParseOptions {
+        validate_expressions: true,
+        ..RSXParseOptions::default()
+    };
+    let error = parse_complete_with_options(source, options).unwrap_err();
+    assert_eq!(error.kind, RSXParseErrorKind::InvalidExpression);
+    assert_eq!(error.found, diagnostics[0].error.found);
+    assert!(parse_complete_with_options("<div>{ items.len() }</div>", options).is_ok());
+}
+
 #[test]
 pub fn test_tokenize_1() {
     let source = "<foo>Hello world!</foo>";
@@ -44,7 +622,8 @@ pub fn test_tokenize_1() {
 
     assert_eq!(
         tokens.to_string(),
-        "DOMNode :: from ( ( DOMTagName :: from ( \"foo\" ) , DOMChildren :: from ( vec ! [ \
+        ":: rsx_dom :: types :: DOMNode :: from ( ( :: rsx_dom :: types :: DOMTagName :: from ( \
+         \"foo\" ) , :: rsx_dom :: types :: DOMChildren :: from ( vec ! [ :: rsx_dom :: types :: \
          DOMNode :: from ( \"Hello world!\" ) , ] ) ) )"
     );
 }
@@ -59,12 +638,14 @@ pub fn test_tokenize_2() {
 
     assert_eq!(
         tokens.to_string(),
-        "DOMNode :: from ( ( DOMTagName :: from ( KnownElementName :: Div ) , vec ! [ \
-         DOMAttribute :: from ( ( DOMAttributeName :: from ( KnownAttributeName :: Hidden ) , \
-         DOMAttributeValue :: from ( true ) ) ) , DOMAttribute :: from ( ( DOMAttributeName :: \
-         from ( KnownAttributeName :: Style ) , DOMAttributeValue :: from ( \
-         {stylesheet.get(\".foo\")} ) ) ) , ] , vec ! [ DOMNode :: from ( \"Hello world!\" ) , ] \
-         ) )",
+        ":: rsx_dom :: types :: DOMNode :: from ( ( :: rsx_dom :: types :: DOMTagName :: from ( :: \
+         rsx_dom :: types :: KnownElementName :: Div ) , vec ! [ :: rsx_dom :: types :: \
+         DOMAttribute :: from ( ( :: rsx_dom :: types :: DOMAttributeName :: from ( :: rsx_dom :: \
+         types :: KnownAttributeName :: Hidden ) , :: rsx_dom :: types :: DOMAttributeValue :: \
+         from ( true ) ) ) , :: rsx_dom :: types :: DOMAttribute :: from ( ( :: rsx_dom :: types \
+         :: DOMAttributeName :: from ( :: rsx_dom :: types :: KnownAttributeName :: Style ) , :: \
+         rsx_dom :: types :: DOMAttributeValue :: from ( { stylesheet . get ( \".foo\" ) } ) ) ) \
+         , ] , vec ! [ :: rsx_dom :: types :: DOMNode :: from ( \"Hello world!\" ) , ] ) )",
     );
 }
 
@@ -78,7 +659,206 @@ pub fn test_tokenize_3() {
 
     assert_eq!(
         tokens.to_string(),
-        "DOMNode :: from ( ( DOMTagName :: from ( \"x-foo-bar\" ) , DOMChildren :: from ( vec ! [ \
-         DOMNode :: from ( \"Hello world!\" ) , ] ) ) )"
+        ":: rsx_dom :: types :: DOMNode :: from ( ( :: rsx_dom :: types :: DOMTagName :: from ( \
+         \"x-foo-bar\" ) , :: rsx_dom :: types :: DOMChildren :: from ( vec ! [ :: rsx_dom :: \
+         types :: DOMNode :: from ( \"Hello world!\" ) , ] ) ) )"
+    );
+}
+
+#[test]
+pub fn test_tokenize_path_prefix() {
+    let (ast, _) = parse_complete("<foo><br/></foo>").unwrap();
+
+    let backend = DefaultCodegenBackend::with_path_prefix("my_dom::types");
+    assert_eq!(
+        tokenize_with_backend(&ast, &backend).to_string(),
+        "my_dom :: types :: DOMNode :: from ( ( my_dom :: types :: DOMTagName :: from ( \"foo\" ) \
+         , my_dom :: types :: DOMChildren :: from ( vec ! [ my_dom :: types :: DOMNode :: from ( \
+         my_dom :: types :: DOMTagName :: from ( my_dom :: types :: KnownElementName :: Br ) ) , \
+         ] ) ) )"
+    );
+
+    let backend = DefaultCodegenBackend::with_path_prefix("");
+    assert_eq!(
+        tokenize_with_backend(&ast, &backend).to_string(),
+        "DOMNode :: from ( ( DOMTagName :: from ( \"foo\" ) , DOMChildren :: from ( vec ! [ \
+         DOMNode :: from ( DOMTagName :: from ( KnownElementName :: Br ) ) , ] ) ) )"
+    );
+}
+
+#[test]
+pub fn test_tokenize_c_strings() {
+    let (ast, _) = parse_complete(r##"<foo>{c"x"}{f(cr#"y"#)}</foo>"##).unwrap();
+
+    let backend = DefaultCodegenBackend::with_path_prefix("");
+    let tokens = tokenize_with_backend(&ast, &backend).to_string();
+    assert!(tokens.contains(r#"DOMNode :: from ( { c"x" } )"#));
+    assert!(tokens.contains(r##"DOMNode :: from ( { f ( cr#"y"# ) } )"##));
+}
+
+#[test]
+pub fn test_tokenize_name_registry() {
+    let (ast, _) = parse_complete("<Card variant='primary' title='x'/>").unwrap();
+
+    let mut names = NameRegistry::new();
+    names
+        .register_element("card", "::ds::Elements::Card")
+        .register_attribute("Variant", "::ds::Attributes::Variant");
+    let backend = DefaultCodegenBackend {
+        path_prefix: "".into(),
+        names,
+        hoist_static_subtrees: false
+    };
+    assert_eq!(
+        tokenize_with_backend(&ast, &backend).to_string(),
+        "DOMNode :: from ( ( DOMTagName :: from ( :: ds :: Elements :: Card ) , DOMAttributes :: \
+         from ( vec ! [ DOMAttribute :: from ( ( DOMAttributeName :: from ( :: ds :: Attributes \
+         :: Variant ) , DOMAttributeValue :: from ( \"primary\" ) ) ) , DOMAttribute :: from ( ( \
+         DOMAttributeName :: from ( KnownAttributeName :: Title ) , DOMAttributeValue :: from ( \
+         \"x\" ) ) ) , ] ) ) )"
     );
 }
+
+#[test]
+pub fn test_tokenize_hoist_static_subtrees() {
+    let (ast, _) = parse_complete("<div>{x}<p class='a'>Hi</p><br/></div>").unwrap();
+
+    let backend = DefaultCodegenBackend {
+        hoist_static_subtrees: true,
+        ..DefaultCodegenBackend::with_path_prefix("")
+    };
+    assert_eq!(
+        tokenize_with_backend(&ast, &backend).to_string(),
+        "DOMNode :: from ( ( DOMTagName :: from ( KnownElementName :: Div ) , DOMChildren :: from \
+         ( vec ! [ DOMNode :: from ( { x } ) , { thread_local ! { static __RSX_STATIC_NODE : \
+         DOMNode = DOMNode :: from ( ( DOMTagName :: from ( KnownElementName :: P ) , vec ! [ \
+         DOMAttribute :: from ( ( DOMAttributeName :: from ( KnownAttributeName :: Class ) , \
+         DOMAttributeValue :: from ( \"a\" ) ) ) , ] , vec ! [ DOMNode :: from ( \"Hi\" ) , ] ) \
+         ) ; } __RSX_STATIC_NODE . with ( | node | :: std :: clone :: Clone :: clone ( node ) ) } \
+         , DOMNode :: from ( DOMTagName :: from ( KnownElementName :: Br ) ) , ] ) ) )"
+    );
+
+    // Static subtrees inside a static subtree are built along with it.
+    let (ast, _) = parse_complete("<foo><b>Hi</b></foo>").unwrap();
+    let tokens = tokenize_with_backend(&ast, &backend).to_string();
+    assert_eq!(tokens.matches("thread_local").count(), 1);
+    assert_eq!(
+        tokens,
+        "{ thread_local ! { static __RSX_STATIC_NODE : DOMNode = DOMNode :: from ( ( DOMTagName \
+         :: from ( \"foo\" ) , DOMChildren :: from ( vec ! [ DOMNode :: from ( ( DOMTagName :: \
+         from ( KnownElementName :: B ) , DOMChildren :: from ( vec ! [ DOMNode :: from ( \"Hi\" \
+         ) , ] ) ) ) , ] ) ) ) ; } __RSX_STATIC_NODE . with ( | node | :: std :: clone :: Clone \
+         :: clone ( node ) ) }"
+    );
+}
+
+// Stands in for a node type, counting how many times it's built.
+mod counted_dom {
+    use std::cell::Cell;
+
+    thread_local! {
+        pub static BUILT: Cell<usize> = Cell::new(0);
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DOMNode(pub &'static str);
+
+    impl From<&'static str> for DOMNode {
+        fn from(text: &'static str) -> Self {
+            BUILT.with(|built| built.set(built.get() + 1));
+            DOMNode(text)
+        }
+    }
+}
+
+// A closure running the given code, along with the code's text.
+macro_rules! render_fn {
+    ($($code:tt)*) => { (|| { $($code)* }, stringify!($($code)*)) };
+}
+
+#[test]
+pub fn test_tokenize_hoisted_subtrees_are_built_once() {
+    use self::counted_dom::{DOMNode, BUILT};
+
+    let (render, code) = render_fn! {{
+        thread_local! {
+            static __RSX_STATIC_NODE: DOMNode = DOMNode::from("Hi");
+        }
+        __RSX_STATIC_NODE.with(|node| ::std::clone::Clone::clone(node))
+    }};
+
+    let backend = DefaultCodegenBackend::with_path_prefix("");
+    let hoisted = backend.hoisted(test_tokens("DOMNode::from(\"Hi\")".into()));
+    let strip = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    assert_eq!(strip(&hoisted.to_string()), strip(code));
+
+    assert_eq!(render(), DOMNode("Hi"));
+    assert_eq!(render(), DOMNode("Hi"));
+    assert_eq!(BUILT.with(|built| built.get()), 1);
+}
+
+struct TestBackend;
+
+fn test_tokens(s: String) -> Tokens {
+    let mut tokens = Tokens::new();
+    tokens.append(s);
+    tokens
+}
+
+impl CodegenBackend for TestBackend {
+    fn path_prefix(&self) -> &str {
+        ""
+    }
+
+    fn element_name(&self, name: Tokens) -> Tokens {
+        test_tokens(format!("tag({})", name))
+    }
+
+    fn element(
+        &self,
+        name: Tokens,
+        attributes: Option<Tokens>,
+        children: Option<Tokens>
+    ) -> Tokens {
+        let attributes = attributes.map(|v| v.to_string()).unwrap_or_else(|| "None".into());
+        let children = children.map(|v| v.to_string()).unwrap_or_else(|| "None".into());
+        test_tokens(format!("node({}, {}, {})", name, attributes, children))
+    }
+
+    fn attribute(&self, name: Tokens, value: Tokens) -> Tokens {
+        test_tokens(format!("({}, {})", name, value))
+    }
+
+    fn attribute_name(&self, name: Tokens) -> Tokens {
+        name
+    }
+
+    fn attribute_value(&self, value: Tokens) -> Tokens {
+        value
+    }
+
+    fn text(&self, text: &str) -> Tokens {
+        test_tokens(format!("text({:?})", text))
+    }
+
+    fn code_block(&self, code_block: Tokens) -> Tokens {
+        code_block
+    }
+}
+
+#[test]
+pub fn test_tokenize_backend() {
+    let source = "<div hidden>Hi{<br/>}{name}</div>";
+    let (ast, _) = parse_complete(source).unwrap();
+
+    assert_eq!(
+        tokenize_with_backend(&ast, &TestBackend).to_string(),
+        "node(tag(KnownElementName :: Div), vec ! [ (KnownAttributeName :: Hidden, true) , ], \
+         vec ! [ text(\"Hi\") , { node(tag(KnownElementName :: Br), None, None) } , { name } , \
+         ])"
+    );
+
+    let mut tokens = Tokens::new();
+    ast.to_custom_tokens(&mut tokens);
+    assert_eq!(tokenize_with_backend(&ast, &DefaultCodegenBackend::default()), tokens);
+}